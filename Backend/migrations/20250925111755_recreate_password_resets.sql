@@ -0,0 +1,16 @@
+-- 20250925111754 drops password_resets again at its end (its down half was
+-- written into the up script), so the table never survived `migrate up`.
+-- Create it for good. A user keeps one row per code requested: the reset
+-- handler throttles by counting recent ones, so the user index is not unique.
+CREATE TABLE IF NOT EXISTS password_resets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    otp_code VARCHAR(10) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP INDEX IF EXISTS idx_password_resets_user_id;
+CREATE INDEX IF NOT EXISTS idx_password_resets_user_created
+    ON password_resets (user_id, created_at);
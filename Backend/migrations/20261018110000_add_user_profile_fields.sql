@@ -0,0 +1,3 @@
+-- Optional display fields shown in the user's profile
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name VARCHAR(100);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_url VARCHAR(2048);
@@ -0,0 +1,25 @@
+-- Pending and completed email address changes
+CREATE TABLE IF NOT EXISTS email_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    old_email VARCHAR(255) NOT NULL,
+    new_email VARCHAR(255) NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    -- "Undo this change" link sent to the old address
+    revert_token_hash VARCHAR(64),
+    revert_expires_at TIMESTAMPTZ,
+    reverted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_changes_user_created
+    ON email_changes (user_id, created_at DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_email_changes_revert_token
+    ON email_changes (revert_token_hash);
+
+-- Locked accounts cannot log in
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS lock_reason VARCHAR(64);
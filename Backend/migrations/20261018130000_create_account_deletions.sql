@@ -0,0 +1,22 @@
+-- Scheduled self-service account deletions
+CREATE TABLE IF NOT EXISTS account_deletions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    cancel_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_deletions_due
+    ON account_deletions (scheduled_for)
+    WHERE cancelled_at IS NULL;
+
+-- Minimal record of each hard-deleted account, kept for audit.
+-- The email is stored only as a SHA-256 hex digest of its lowercase form.
+CREATE TABLE IF NOT EXISTS deleted_accounts (
+    user_id UUID PRIMARY KEY,
+    email_hash VARCHAR(64) NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,14 @@
+-- Personal data export archives (GDPR Art. 15/20)
+CREATE TABLE IF NOT EXISTS data_exports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- pending -> ready | failed; ready archives become expired once removed
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    file_path TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user_created
+    ON data_exports (user_id, created_at DESC);
@@ -0,0 +1,82 @@
+-- Row-level security for user-owned data.
+--
+-- Request handlers run their queries through `database::rls::begin_user_scope`,
+-- which opens a transaction, switches to the unprivileged `uiso_app` role and
+-- sets `app.user_id` / `app.tenant_id` from the validated token. Under that
+-- role a query that forgets its `WHERE user_id = ...` still only sees the
+-- caller's rows. The connection's own role (the table owner) bypasses these
+-- policies, which login, registration and the background jobs rely on.
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'uiso_app') THEN
+        CREATE ROLE uiso_app NOLOGIN NOSUPERUSER NOBYPASSRLS;
+    END IF;
+END
+$$;
+
+-- The application's login role must be able to SET ROLE uiso_app
+GRANT uiso_app TO CURRENT_USER;
+
+GRANT USAGE ON SCHEMA public TO uiso_app;
+GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO uiso_app;
+GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA public TO uiso_app;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+    GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO uiso_app;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+    GRANT USAGE, SELECT ON SEQUENCES TO uiso_app;
+
+-- Unset (or reset to '') settings mean "nobody", so every policy fails closed
+CREATE OR REPLACE FUNCTION app_current_user_id() RETURNS UUID
+    LANGUAGE sql STABLE
+    AS $$ SELECT NULLIF(current_setting('app.user_id', TRUE), '')::uuid $$;
+
+CREATE OR REPLACE FUNCTION app_current_tenant_id() RETURNS UUID
+    LANGUAGE sql STABLE
+    AS $$ SELECT NULLIF(current_setting('app.tenant_id', TRUE), '')::uuid $$;
+
+-- Users see and edit only their own row
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS users_self ON users;
+CREATE POLICY users_self ON users
+    USING (id = app_current_user_id())
+    WITH CHECK (id = app_current_user_id());
+
+-- Tables keyed by user_id
+DO $$
+DECLARE
+    t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY[
+        'sessions', 'failed_logins', 'password_resets', 'password_history',
+        'email_changes', 'account_deletions', 'data_exports'
+    ]
+    LOOP
+        EXECUTE format('ALTER TABLE %I ENABLE ROW LEVEL SECURITY', t);
+        EXECUTE format('DROP POLICY IF EXISTS %I ON %I', t || '_owner', t);
+        EXECUTE format(
+            'CREATE POLICY %I ON %I USING (user_id = app_current_user_id()) '
+            'WITH CHECK (user_id = app_current_user_id())',
+            t || '_owner', t
+        );
+    END LOOP;
+END
+$$;
+
+-- Own memberships, plus every membership of the active organization
+ALTER TABLE memberships ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS memberships_visible ON memberships;
+CREATE POLICY memberships_visible ON memberships
+    USING (user_id = app_current_user_id() OR organization_id = app_current_tenant_id());
+
+-- Organizations the user belongs to
+ALTER TABLE organizations ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS organizations_member ON organizations;
+CREATE POLICY organizations_member ON organizations
+    USING (
+        id = app_current_tenant_id()
+        OR id IN (SELECT organization_id FROM memberships WHERE user_id = app_current_user_id())
+    );
+
+-- Tombstones are never visible to request handlers (no policy = no rows)
+ALTER TABLE deleted_accounts ENABLE ROW LEVEL SECURITY;
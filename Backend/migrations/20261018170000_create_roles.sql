@@ -0,0 +1,78 @@
+-- Application-wide roles and permissions (separate from organization member roles)
+CREATE TABLE IF NOT EXISTS roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Permission names are "<resource>:<action>", e.g. "users:read"
+CREATE TABLE IF NOT EXISTS permissions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id UUID NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_roles_role_id ON user_roles (role_id);
+
+INSERT INTO roles (name, description) VALUES
+    ('admin', 'Operators with full access to user management'),
+    ('user', 'Every registered account')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO permissions (name, description) VALUES
+    ('users:read', 'View any user account'),
+    ('users:write', 'Lock, unlock and edit user accounts'),
+    ('users:delete', 'Delete and restore user accounts'),
+    ('sessions:revoke', 'Sign users out of their sessions'),
+    ('roles:read', 'View roles and their permissions'),
+    ('roles:manage', 'Grant and revoke roles')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r CROSS JOIN permissions p
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+-- Every account holds the `user` role, however it was created
+CREATE OR REPLACE FUNCTION grant_default_role() RETURNS TRIGGER
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    INSERT INTO user_roles (user_id, role_id)
+    SELECT NEW.id, id FROM roles WHERE name = 'user'
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END
+$$;
+
+DROP TRIGGER IF EXISTS users_default_role ON users;
+CREATE TRIGGER users_default_role
+    AFTER INSERT ON users
+    FOR EACH ROW EXECUTE FUNCTION grant_default_role();
+
+INSERT INTO user_roles (user_id, role_id)
+SELECT u.id, r.id FROM users u CROSS JOIN roles r
+WHERE r.name = 'user'
+ON CONFLICT DO NOTHING;
+
+-- Users may read their own role assignments inside a user scope
+ALTER TABLE user_roles ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS user_roles_owner ON user_roles;
+CREATE POLICY user_roles_owner ON user_roles
+    USING (user_id = app_current_user_id())
+    WITH CHECK (user_id = app_current_user_id());
@@ -0,0 +1,70 @@
+-- Access to one user's resource granted to another user, to the members of an
+-- organization (while it is their active tenant), or to anyone holding a
+-- signed share link
+CREATE TABLE IF NOT EXISTS resource_grants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    resource_type VARCHAR(50) NOT NULL,
+    resource_id UUID NOT NULL,
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    grantee_type VARCHAR(16) NOT NULL
+        CHECK (grantee_type IN ('user', 'organization', 'link')),
+    grantee_user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    grantee_organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE,
+    permission VARCHAR(16) NOT NULL DEFAULT 'read'
+        CHECK (permission IN ('read', 'write')),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((grantee_type = 'user') = (grantee_user_id IS NOT NULL)),
+    CHECK ((grantee_type = 'organization') = (grantee_organization_id IS NOT NULL)),
+    -- Anonymous links always expire
+    CHECK (grantee_type <> 'link' OR expires_at IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_resource_grants_resource ON resource_grants (resource_type, resource_id);
+CREATE INDEX IF NOT EXISTS idx_resource_grants_owner_id ON resource_grants (owner_id);
+CREATE INDEX IF NOT EXISTS idx_resource_grants_grantee_user_id ON resource_grants (grantee_user_id);
+
+-- One live grant per resource and grantee
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_grants_live_user
+    ON resource_grants (resource_type, resource_id, grantee_user_id)
+    WHERE revoked_at IS NULL AND grantee_type = 'user';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resource_grants_live_organization
+    ON resource_grants (resource_type, resource_id, grantee_organization_id)
+    WHERE revoked_at IS NULL AND grantee_type = 'organization';
+
+-- Owners manage their grants; grantees can see the grants they hold
+ALTER TABLE resource_grants ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS resource_grants_owner ON resource_grants;
+CREATE POLICY resource_grants_owner ON resource_grants
+    USING (owner_id = app_current_user_id())
+    WITH CHECK (owner_id = app_current_user_id());
+DROP POLICY IF EXISTS resource_grants_grantee ON resource_grants;
+CREATE POLICY resource_grants_grantee ON resource_grants FOR SELECT
+    USING (
+        grantee_user_id = app_current_user_id()
+        OR grantee_organization_id = app_current_tenant_id()
+    );
+
+-- Whether the user in scope holds a live grant on the resource
+CREATE OR REPLACE FUNCTION app_has_grant(kind TEXT, target UUID) RETURNS BOOLEAN
+    LANGUAGE sql STABLE
+    AS $$
+        SELECT EXISTS (
+            SELECT 1 FROM resource_grants
+            WHERE resource_type = kind
+            AND resource_id = target
+            AND revoked_at IS NULL
+            AND (expires_at IS NULL OR expires_at > NOW())
+            AND (
+                grantee_user_id = app_current_user_id()
+                OR grantee_organization_id = app_current_tenant_id()
+            )
+        )
+    $$;
+
+-- Shared data exports are readable by their grantees (the owner policy still
+-- limits writes to the owner)
+DROP POLICY IF EXISTS data_exports_shared ON data_exports;
+CREATE POLICY data_exports_shared ON data_exports FOR SELECT
+    USING (app_has_grant('data_export', id));
@@ -0,0 +1,27 @@
+-- Invitations to join an organization, sent by email. Only the token hash is stored.
+CREATE TABLE IF NOT EXISTS invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    role VARCHAR(16) NOT NULL DEFAULT 'member'
+        CHECK (role IN ('owner', 'admin', 'member')),
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    accepted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One pending invitation per address and organization
+CREATE UNIQUE INDEX IF NOT EXISTS idx_invitations_pending
+    ON invitations (organization_id, LOWER(email))
+    WHERE accepted_at IS NULL AND revoked_at IS NULL;
+
+-- Visible to members of the active organization
+ALTER TABLE invitations ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS invitations_tenant ON invitations;
+CREATE POLICY invitations_tenant ON invitations
+    USING (organization_id = app_current_tenant_id())
+    WITH CHECK (organization_id = app_current_tenant_id());
@@ -0,0 +1,20 @@
+-- Soft-deleted accounts cannot sign in but keep their data until an admin restores them
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Every change made through the admin user API. Actor and target are plain
+-- ids (no foreign keys) so the trail outlives the accounts it mentions.
+CREATE TABLE IF NOT EXISTS admin_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor_id UUID NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    target_user_id UUID,
+    details JSONB NOT NULL DEFAULT '{}',
+    ip_address VARCHAR(64),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_actions_target
+    ON admin_actions (target_user_id, created_at);
+
+-- Never readable from a user scope (no policy: fails closed)
+ALTER TABLE admin_actions ENABLE ROW LEVEL SECURITY;
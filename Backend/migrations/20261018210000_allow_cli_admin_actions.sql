@@ -0,0 +1,2 @@
+-- Actions run through the authctl CLI have no acting account
+ALTER TABLE admin_actions ALTER COLUMN actor_id DROP NOT NULL;
@@ -0,0 +1,15 @@
+-- Sessions an admin opened as another user. The token carries the admin in
+-- its `act` claim; the session only accepts tokens naming the same admin.
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS impersonator_id UUID REFERENCES users(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_impersonator_id
+    ON sessions (impersonator_id) WHERE impersonator_id IS NOT NULL;
+
+INSERT INTO permissions (name, description) VALUES
+    ('users:impersonate', 'Sign in as another user')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r CROSS JOIN permissions p
+WHERE r.name = 'admin' AND p.name = 'users:impersonate'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,58 @@
+-- Append-only security audit log. Each row stores the hash of the row before
+-- it, so editing, deleting or reordering rows breaks the chain; `authctl
+-- audit verify` walks it. Actor and target are plain ids (no foreign keys) so
+-- the log outlives the accounts it mentions.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    outcome VARCHAR(16) NOT NULL,
+    actor_id UUID,
+    target_user_id UUID,
+    ip_address VARCHAR(64),
+    user_agent TEXT,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL,
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_target ON audit_events (target_user_id, id);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor ON audit_events (actor_id, id);
+CREATE INDEX IF NOT EXISTS idx_audit_events_type ON audit_events (event_type, id);
+
+-- Rows can only be added; the chain still catches changes made around this
+CREATE OR REPLACE FUNCTION reject_audit_event_change() RETURNS TRIGGER
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END
+$$;
+
+DROP TRIGGER IF EXISTS audit_events_append_only ON audit_events;
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION reject_audit_event_change();
+
+DROP TRIGGER IF EXISTS audit_events_no_truncate ON audit_events;
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_audit_event_change();
+
+REVOKE UPDATE, DELETE, TRUNCATE ON audit_events FROM uiso_app;
+
+-- Users may read the events about their own account inside a user scope
+ALTER TABLE audit_events ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS audit_events_target ON audit_events;
+CREATE POLICY audit_events_target ON audit_events
+    FOR SELECT
+    USING (target_user_id = app_current_user_id());
+
+INSERT INTO permissions (name, description) VALUES
+    ('audit:read', 'Search the security audit log')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r CROSS JOIN permissions p
+WHERE r.name = 'admin' AND p.name = 'audit:read'
+ON CONFLICT DO NOTHING;
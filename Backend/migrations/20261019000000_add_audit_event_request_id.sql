@@ -0,0 +1,6 @@
+-- Id of the request that caused the event, matching the `request_id` in the
+-- server logs and in error responses
+ALTER TABLE audit_events ADD COLUMN IF NOT EXISTS request_id VARCHAR(128);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_request_id
+    ON audit_events (request_id) WHERE request_id IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Data exports can no longer be shared by anonymous link; end the links
+-- already handed out
+UPDATE resource_grants SET revoked_at = NOW()
+WHERE resource_type = 'data_export' AND grantee_type = 'link' AND revoked_at IS NULL;
@@ -0,0 +1,6 @@
+-- Members of the organization in scope can read each other's accounts, so
+-- the member list and invitation checks run under row-level security too.
+-- Writes stay limited to the user's own row.
+DROP POLICY IF EXISTS users_tenant_members ON users;
+CREATE POLICY users_tenant_members ON users FOR SELECT
+    USING (id IN (SELECT user_id FROM memberships WHERE organization_id = app_current_tenant_id()));
@@ -0,0 +1,317 @@
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::breach::{BreachCheckConfig, BreachCheckMode};
+
+pub const BREACHED_PASSWORD_MESSAGE: &str =
+    "This password has appeared in a known data breach. Please choose a different one.";
+
+// Index layout (all integers little-endian):
+//   magic (8 bytes) | record count (u64) | fan-out table (65537 x u64) | records (u64 each)
+// Each record is the first 8 bytes of a SHA-1 hash, sorted ascending. The
+// fan-out table maps the top 16 bits of a prefix to its first record, so a
+// lookup is a binary search over a few thousand records at most.
+const INDEX_MAGIC: &[u8; 8] = b"HIBPIDX1";
+const FANOUT_LEN: usize = 65537;
+const HEADER_LEN: u64 = 16 + (FANOUT_LEN as u64) * 8;
+const RECORD_LEN: u64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreachVerdict {
+    Clean,
+    Warn,
+    Reject,
+}
+
+struct BreachIndex {
+    file: File,
+    fanout: Vec<u64>,
+}
+
+/// Offline Pwned Passwords lookup against a local index file
+#[derive(Clone)]
+pub struct BreachedPasswordChecker {
+    index: Option<Arc<BreachIndex>>,
+    mode: BreachCheckMode,
+}
+
+impl BreachedPasswordChecker {
+    /// Open the configured index; the checker is a no-op when none is set
+    pub fn from_config(config: &BreachCheckConfig) -> io::Result<Self> {
+        let index = match &config.index_path {
+            Some(path) => Some(Arc::new(BreachIndex::open(Path::new(path))?)),
+            None => None,
+        };
+
+        Ok(Self { index, mode: config.mode })
+    }
+
+    /// Look the password up and map a hit to the configured mode. The index
+    /// was validated when opened; an I/O error now is logged and treated as
+    /// a miss.
+    pub async fn check(&self, password: &str) -> BreachVerdict {
+        let Some(index) = self.index.clone() else {
+            return BreachVerdict::Clean;
+        };
+
+        let prefix = sha1_prefix(password);
+        let found = tokio::task::spawn_blocking(move || index.contains(prefix)).await;
+
+        match found {
+            Ok(Ok(true)) => match self.mode {
+                BreachCheckMode::Reject => BreachVerdict::Reject,
+                BreachCheckMode::Warn => BreachVerdict::Warn,
+            },
+            Ok(Ok(false)) => BreachVerdict::Clean,
+            Ok(Err(e)) => {
+                tracing::error!("Breached password lookup failed: {}", e);
+                BreachVerdict::Clean
+            }
+            Err(e) => {
+                tracing::error!("Breached password lookup task failed: {}", e);
+                BreachVerdict::Clean
+            }
+        }
+    }
+}
+
+impl BreachIndex {
+    /// Open an index and check that its header matches the file, so a
+    /// truncated or corrupt index stops the server instead of letting
+    /// breached passwords through
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        let mut header = [0u8; 16];
+        file.read_exact_at(&mut header, 0)?;
+        if &header[..8] != INDEX_MAGIC {
+            return Err(invalid_index("not a breached-password index"));
+        }
+        let total = u64::from_le_bytes(header[8..].try_into().expect("8-byte count"));
+
+        let mut raw = vec![0u8; FANOUT_LEN * 8];
+        file.read_exact_at(&mut raw, 16)?;
+        let fanout: Vec<u64> = raw
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("8-byte chunk")))
+            .collect();
+
+        if fanout[0] != 0 || fanout.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(invalid_index("fan-out table is not monotonic"));
+        }
+        let records = fanout[FANOUT_LEN - 1];
+        if records != total {
+            return Err(invalid_index("fan-out table does not match the record count"));
+        }
+        let expected_len = records.checked_mul(RECORD_LEN).and_then(|len| len.checked_add(HEADER_LEN));
+        if expected_len != Some(file.metadata()?.len()) {
+            return Err(invalid_index("file size does not match the record count (truncated?)"));
+        }
+
+        Ok(Self { file, fanout })
+    }
+
+    fn contains(&self, prefix: u64) -> io::Result<bool> {
+        let bucket = (prefix >> 48) as usize;
+        let (mut lo, mut hi) = (self.fanout[bucket], self.fanout[bucket + 1]);
+
+        let mut buf = [0u8; 8];
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            self.file.read_exact_at(&mut buf, HEADER_LEN + mid * RECORD_LEN)?;
+            let record = u64::from_le_bytes(buf);
+
+            if record == prefix {
+                return Ok(true);
+            } else if record < prefix {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+fn invalid_index(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid breached-password index: {}", reason))
+}
+
+fn sha1_prefix(password: &str) -> u64 {
+    let digest = Sha1::digest(password.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-1 digest is 20 bytes"))
+}
+
+/// Build an index from a Pwned Passwords SHA-1 file (`HASH:COUNT` per line,
+/// ordered by hash, as produced by the official downloader).
+/// Returns the number of records written.
+pub fn build_index(source: &Path, output: &Path) -> anyhow::Result<u64> {
+    let reader = BufReader::new(File::open(source)?);
+    let mut out = File::create(output)?;
+
+    // Reserve the header; it is filled in once all records are written
+    out.write_all(&vec![0u8; HEADER_LEN as usize])?;
+
+    let mut counts = vec![0u64; FANOUT_LEN - 1];
+    let mut previous: Option<u64> = None;
+    let mut total = 0u64;
+    {
+        let mut writer = BufWriter::new(&mut out);
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            let hash = line.split(':').next().unwrap_or_default().trim();
+            if hash.is_empty() {
+                continue;
+            }
+            if hash.len() != 40 {
+                anyhow::bail!("Line {}: expected a 40-character SHA-1 hash", line_no + 1);
+            }
+
+            let prefix = u64::from_str_radix(&hash[..16], 16)
+                .map_err(|_| anyhow::anyhow!("Line {}: invalid hex", line_no + 1))?;
+
+            match previous {
+                Some(prev) if prefix < prev => {
+                    anyhow::bail!("Line {}: input must be ordered by hash", line_no + 1)
+                }
+                // Distinct hashes can share a prefix; keep one record
+                Some(prev) if prefix == prev => continue,
+                _ => {}
+            }
+
+            writer.write_all(&prefix.to_le_bytes())?;
+            counts[(prefix >> 48) as usize] += 1;
+            previous = Some(prefix);
+            total += 1;
+        }
+        writer.flush()?;
+    }
+
+    let mut header = Vec::with_capacity(HEADER_LEN as usize);
+    header.extend_from_slice(INDEX_MAGIC);
+    header.extend_from_slice(&total.to_le_bytes());
+    let mut offset = 0u64;
+    for count in counts {
+        header.extend_from_slice(&offset.to_le_bytes());
+        offset += count;
+    }
+    header.extend_from_slice(&offset.to_le_bytes());
+
+    out.seek(SeekFrom::Start(0))?;
+    out.write_all(&header)?;
+    out.sync_all()?;
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const BREACHED: [&str; 3] = ["password", "123456", "qwerty"];
+
+    // A fresh directory with an index of the BREACHED passwords
+    fn build_fixture() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("breach-index-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut hashes: Vec<String> = BREACHED
+            .iter()
+            .map(|password| {
+                Sha1::digest(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect()
+            })
+            .collect();
+        hashes.sort();
+        let source = dir.join("pwned.txt");
+        let lines: Vec<String> = hashes.iter().map(|hash| format!("{}:10", hash)).collect();
+        fs::write(&source, lines.join("\n")).unwrap();
+
+        let index = dir.join("pwned.idx");
+        assert_eq!(build_index(&source, &index).unwrap(), BREACHED.len() as u64);
+        (dir, index)
+    }
+
+    #[test]
+    fn finds_indexed_prefixes_only() {
+        let (dir, path) = build_fixture();
+        let index = BreachIndex::open(&path).unwrap();
+
+        for password in BREACHED {
+            assert!(index.contains(sha1_prefix(password)).unwrap(), "{}", password);
+        }
+        for password in ["Correct-Horse-Battery-9!", "", "password1"] {
+            assert!(!index.contains(sha1_prefix(password)).unwrap(), "{}", password);
+        }
+        assert!(!index.contains(0).unwrap());
+        assert!(!index.contains(u64::MAX).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn checker_applies_the_mode() {
+        let (dir, path) = build_fixture();
+        let config = |mode| BreachCheckConfig { index_path: Some(path.to_str().unwrap().to_string()), mode };
+
+        let checker = BreachedPasswordChecker::from_config(&config(BreachCheckMode::Reject)).unwrap();
+        assert_eq!(checker.check("password").await, BreachVerdict::Reject);
+        assert_eq!(checker.check("Correct-Horse-Battery-9!").await, BreachVerdict::Clean);
+        let checker = BreachedPasswordChecker::from_config(&config(BreachCheckMode::Warn)).unwrap();
+        assert_eq!(checker.check("password").await, BreachVerdict::Warn);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_truncated_index() {
+        let (dir, path) = build_fixture();
+        let len = fs::metadata(&path).unwrap().len();
+        File::options().write(true).open(&path).unwrap().set_len(len - 1).unwrap();
+
+        let error = BreachIndex::open(&path).err().expect("truncated index");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_non_monotonic_fanout() {
+        let (dir, path) = build_fixture();
+        // Bucket 1 starting after the buckets that follow it
+        File::options().write(true).open(&path).unwrap().write_all_at(&u64::MAX.to_le_bytes(), 16 + 8).unwrap();
+
+        let error = BreachIndex::open(&path).err().expect("corrupt fan-out");
+        assert!(error.to_string().contains("monotonic"), "{}", error);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let (dir, _) = build_fixture();
+        let path = dir.join("pwned.txt");
+
+        assert_eq!(BreachIndex::open(&path).err().expect("not an index").kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn build_requires_sorted_input() {
+        let dir = std::env::temp_dir().join(format!("breach-index-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("pwned.txt");
+        fs::write(&source, format!("{}:1\n{}:1", "F".repeat(40), "0".repeat(40))).unwrap();
+
+        assert!(build_index(&source, &dir.join("pwned.idx")).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
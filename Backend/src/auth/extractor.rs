@@ -0,0 +1,58 @@
+use actix_web::{dev::Payload, Error, FromRequest, HttpMessage, HttpRequest};
+use futures_util::future::{err, ok, Ready};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::claims::Claims;
+
+/// The caller authenticated by `AuthMiddleware`.
+/// Use it as a handler argument instead of reading `Claims` from extensions.
+/// During impersonation `user_id` is the impersonated user and
+/// `impersonator_id` the admin acting as them.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+    // Active organization (tenant) the token is scoped to
+    pub organization_id: Option<Uuid>,
+    pub impersonator_id: Option<Uuid>,
+    pub claims: Claims,
+}
+
+impl AuthenticatedUser {
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonator_id.is_some()
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(claims) = req.extensions().get::<Claims>().cloned() else {
+            return err(AppError::invalid_token().into());
+        };
+
+        let organization_id = match claims.org.as_deref().map(Uuid::parse_str).transpose() {
+            Ok(id) => id,
+            Err(_) => return err(AppError::invalid_token().into()),
+        };
+
+        let impersonator_id = match claims.act.as_ref().map(|act| Uuid::parse_str(&act.sub)).transpose() {
+            Ok(id) => id,
+            Err(_) => return err(AppError::invalid_token().into()),
+        };
+
+        match (Uuid::parse_str(&claims.sub), Uuid::parse_str(&claims.sid)) {
+            (Ok(user_id), Ok(session_id)) => ok(AuthenticatedUser {
+                user_id,
+                session_id,
+                organization_id,
+                impersonator_id,
+                claims,
+            }),
+            _ => err(AppError::invalid_token().into()),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::Pool;
+use sqlx::Postgres;
+
+use crate::auth::cookies::clear_access_token;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::reauth::{reject_impersonation, verify_current_password};
+use crate::auth::validation::ValidationError;
+use crate::config::account_deletion::AccountDeletionConfig;
+use crate::config::app::AppConfig;
+use crate::config::login::LoginLimitConfig;
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::account::{CancelDeletionParams, DeleteAccountPayload};
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::pagetemplate::ConfirmActionPageTemplate;
+use crate::services::account_deletion::{cancel_deletion, schedule_deletion};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::sessions::revoke_user_sessions;
+use crate::utils::email::{send_account_deletion_email, spawn_email};
+use crate::utils::hash_pool::HashPool;
+use crate::utils::token::{generate_token, hash_token};
+
+// Schedule deletion of the logged-in user's account after the grace period.
+// The account is locked and signed out everywhere until then.
+#[delete("")]
+#[allow(clippy::too_many_arguments)] // one extractor per piece of app data
+pub async fn delete_me(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    hash_pool: web::Data<HashPool>,
+    login_config: web::Data<LoginLimitConfig>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<AccountDeletionConfig>,
+    payload: web::Json<DeleteAccountPayload>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+
+    let user_id = auth.user_id;
+
+    let user = sqlx::query!(
+        "SELECT username, email, password_hash FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&mut *begin_user_scope(pool.get_ref(), &auth).await?)
+    .await?
+    .ok_or_else(AppError::invalid_token)?;
+
+    // 1. Input validation
+    if payload.current_password.is_empty() {
+        return Err(AppError::validation(ValidationError::new(
+            "current_password",
+            "Current password is required",
+        )));
+    }
+
+    // 2. Re-authenticate (shares the login lockout)
+    verify_current_password(
+        pool.get_ref(),
+        &hash_pool,
+        &login_config,
+        user_id,
+        &user.password_hash,
+        &payload.current_password,
+    )
+    .await?;
+
+    // 3. Schedule the deletion and lock the account
+    let cancel_token = generate_token();
+    let scheduled_for = Utc::now() + Duration::days(config.grace_period_days);
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+    schedule_deletion(&mut tx, user_id, scheduled_for, &hash_token(&cancel_token)).await?;
+
+    // 4. Sign out every device
+    revoke_user_sessions(&mut *tx, user_id, None).await?;
+
+    tx.commit().await?;
+
+    let event = AuditEvent {
+        target_user_id: Some(user_id),
+        details: serde_json::json!({ "scheduled_for": scheduled_for }),
+        ..AuditEvent::from_request(&req, AuditEventType::AccountDeletionScheduled, AuditOutcome::Success)
+    };
+    audit::record(pool.get_ref(), event).await;
+
+    // 5. Email the cancellation link
+    let cancel_url = format!(
+        "{}/api/v1/auth/deletion/cancel?token={}",
+        app_config.public_url, cancel_token
+    );
+    let (email, username) = (user.email, user.username);
+    spawn_email("account deletion", move || {
+        send_account_deletion_email(&email, &username, scheduled_for, &cancel_url)
+    });
+
+    Ok(HttpResponse::Accepted()
+        .cookie(clear_access_token())
+        .json(serde_json::json!({
+            "message": "Your account is scheduled for deletion. Check your email to cancel.",
+            "scheduled_for": scheduled_for
+        })))
+}
+
+// Confirmation page for the cancellation link (public; the account is locked)
+#[get("/deletion/cancel")]
+pub async fn cancel_deletion_page(params: web::Query<CancelDeletionParams>) -> impl Responder {
+    render_cancel_page(&params.token, None)
+}
+
+// Cancel a scheduled deletion and unlock the account
+#[post("/deletion/cancel")]
+pub async fn cancel_account_deletion(
+    req: HttpRequest,
+    pool: web::Data<Pool<Postgres>>,
+    form: web::Form<CancelDeletionParams>,
+) -> Result<HttpResponse, AppError> {
+    let message = match cancel_deletion(pool.get_ref(), &hash_token(&form.token)).await? {
+        Some(user_id) => {
+            let event = AuditEvent {
+                target_user_id: Some(user_id),
+                ..AuditEvent::from_request(&req, AuditEventType::AccountDeletionCancelled, AuditOutcome::Success)
+            };
+            audit::record(pool.get_ref(), event).await;
+            "The deletion has been cancelled. You can sign in to your account again."
+        }
+        None => "This link is invalid or has expired.",
+    };
+    Ok(render_cancel_page("", Some(message)))
+}
+
+fn render_cancel_page(token: &str, message: Option<&str>) -> HttpResponse {
+    ConfirmActionPageTemplate {
+        title: "Cancel account deletion",
+        description: "Your account is scheduled for deletion. Cancel it to keep your account and sign in again.",
+        action: "cancel",
+        button: "Keep my account",
+        token,
+        message,
+    }
+    .into_response()
+}
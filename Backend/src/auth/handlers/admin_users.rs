@@ -0,0 +1,128 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::permissions::require_permission;
+use crate::error::AppError;
+use crate::models::admin::{LockUserPayload, UserSearchParams};
+use crate::models::role::{PERM_SESSIONS_REVOKE, PERM_USERS_DELETE, PERM_USERS_WRITE};
+use crate::services::admin_users::{self, search_users, user_detail, Actor, AdminError};
+
+// The admin behind the request; during impersonation, the impersonating one
+pub fn actor(req: &HttpRequest, auth: &AuthenticatedUser) -> Actor {
+    Actor {
+        user_id: Some(auth.impersonator_id.unwrap_or(auth.user_id)),
+        ip_address: req.connection_info().realip_remote_addr().map(str::to_string),
+    }
+}
+
+// The response for a completed action, or the problem it was refused with
+fn action_response(result: Result<serde_json::Value, AdminError>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(result?))
+}
+
+// Search users; see `UserSearchParams` for the filters
+#[get("")]
+pub async fn list_users(
+    pool: web::Data<Pool<Postgres>>,
+    params: web::Query<UserSearchParams>,
+) -> Result<HttpResponse, AppError> {
+    let page = search_users(pool.get_ref(), &params).await?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// One account with its sessions, failed logins, reset requests and admin history
+#[get("/{id}")]
+pub async fn get_user(pool: web::Data<Pool<Postgres>>, path: web::Path<Uuid>) -> Result<HttpResponse, AppError> {
+    let detail = user_detail(pool.get_ref(), path.into_inner()).await?.ok_or(AdminError::NotFound)?;
+    Ok(HttpResponse::Ok().json(detail))
+}
+
+// Lock the account and sign it out everywhere
+#[post("/{id}/lock", wrap = "require_permission(PERM_USERS_WRITE)")]
+pub async fn lock_user(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+    payload: Option<web::Json<LockUserPayload>>,
+) -> Result<HttpResponse, AppError> {
+    let reason = payload.and_then(|p| p.into_inner().reason);
+    let result = admin_users::lock_user(pool.get_ref(), &actor(&req, &auth), path.into_inner(), reason).await;
+    action_response(result.map(|_| serde_json::json!({ "message": "Account locked" })))
+}
+
+// Lift any lock except a pending self-service deletion
+#[post("/{id}/unlock", wrap = "require_permission(PERM_USERS_WRITE)")]
+pub async fn unlock_user(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::unlock_user(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|_| serde_json::json!({ "message": "Account unlocked" })))
+}
+
+// Sign the user out and lock the account until they reset their password
+#[post("/{id}/force-password-reset", wrap = "require_permission(PERM_USERS_WRITE)")]
+pub async fn force_password_reset(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::force_password_reset(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|_| {
+        serde_json::json!({ "message": "The user must reset their password before signing in again" })
+    }))
+}
+
+// Mark the email address verified
+#[post("/{id}/verify-email", wrap = "require_permission(PERM_USERS_WRITE)")]
+pub async fn verify_email(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::verify_email(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|_| serde_json::json!({ "message": "Email address verified" })))
+}
+
+// Sign the user out of every session
+#[delete("/{id}/sessions", wrap = "require_permission(PERM_SESSIONS_REVOKE)")]
+pub async fn revoke_sessions(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::revoke_sessions(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|revoked| serde_json::json!({ "message": "Sessions revoked", "revoked": revoked })))
+}
+
+// Soft-delete: the account can no longer sign in, but nothing is removed
+#[delete("/{id}", wrap = "require_permission(PERM_USERS_DELETE)")]
+pub async fn delete_user(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::delete_user(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|_| serde_json::json!({ "message": "Account deleted" })))
+}
+
+// Undo a soft delete
+#[post("/{id}/restore", wrap = "require_permission(PERM_USERS_DELETE)")]
+pub async fn restore_user(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let result = admin_users::restore_user(pool.get_ref(), &actor(&req, &auth), path.into_inner()).await;
+    action_response(result.map(|_| serde_json::json!({ "message": "Account restored" })))
+}
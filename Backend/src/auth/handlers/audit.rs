@@ -0,0 +1,30 @@
+use actix_web::{get, web, HttpResponse};
+use sqlx::{Pool, Postgres};
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::audit::{AuditPageParams, AuditSearchParams};
+use crate::services::audit::{search_events, user_events};
+
+// Search the security audit log; see `AuditSearchParams` for the filters
+#[get("")]
+pub async fn list_events(
+    pool: web::Data<Pool<Postgres>>,
+    params: web::Query<AuditSearchParams>,
+) -> Result<HttpResponse, AppError> {
+    let page = search_events(pool.get_ref(), &params).await?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Security events about the current user's account (sign-ins, resets, admin actions)
+#[get("/audit-events")]
+pub async fn my_events(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    params: web::Query<AuditPageParams>,
+) -> Result<HttpResponse, AppError> {
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+    let page = user_events(&mut tx, auth.user_id, params.page, params.per_page).await?;
+    Ok(HttpResponse::Ok().json(page))
+}
@@ -0,0 +1,350 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::Pool;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+use crate::auth::validation::{sanitize_input, validate_email_change_payload};
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::reauth::{reject_impersonation, verify_current_password};
+use crate::config::app::AppConfig;
+use crate::config::email_change::EmailChangeConfig;
+use crate::config::login::LoginLimitConfig;
+use crate::config::otp::OtpConfig;
+use crate::database::rls::{begin_scope, begin_user_scope};
+use crate::error::AppError;
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::email_change::{EmailChangeConfirmPayload, EmailChangeRequestPayload, EmailRevertParams};
+use crate::models::pagetemplate::ConfirmActionPageTemplate;
+use crate::models::user::LOCK_EMAIL_CHANGE_REVERTED;
+use crate::services::audit::{self, AuditEvent};
+use crate::services::email_changes::is_email_taken;
+use crate::services::sessions::revoke_user_sessions;
+use crate::utils::email::{
+    send_email_change_code, send_email_changed_notice, send_email_in_use_notice, spawn_email,
+};
+use crate::utils::hash_pool::HashPool;
+use crate::utils::otp::generate_otp;
+use crate::utils::token::{generate_token, hash_token, sign, verify_signature};
+
+fn invalid_code() -> AppError {
+    AppError::bad_request("invalid_code", "Invalid or expired code")
+}
+
+// Six digits are quickly brute-forced from a plain hash, so the stored value
+// is keyed with a server secret (and bound to the account)
+fn code_message(user_id: Uuid, code: &str) -> String {
+    format!("email-change:{}:{}", user_id, code)
+}
+
+// Start an email change: re-authenticate, then send a code to the new address.
+// The response is the same whether or not the new address is already registered.
+#[post("/email")]
+pub async fn request_email_change(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    hash_pool: web::Data<HashPool>,
+    login_config: web::Data<LoginLimitConfig>,
+    otp_config: web::Data<OtpConfig>,
+    config: web::Data<EmailChangeConfig>,
+    payload: web::Json<EmailChangeRequestPayload>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+
+    let user_id = auth.user_id;
+
+    let user = sqlx::query!(
+        "SELECT email, password_hash FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&mut *begin_user_scope(pool.get_ref(), &auth).await?)
+    .await?
+    .ok_or_else(AppError::invalid_token)?;
+
+    // 1. Input validation
+    validate_email_change_payload(&user.email, &payload.new_email, &payload.current_password)
+        .map_err(AppError::Validation)?;
+    let new_email = sanitize_input(&payload.new_email);
+
+    // 2. Re-authenticate (shares the login lockout)
+    verify_current_password(
+        pool.get_ref(),
+        &hash_pool,
+        &login_config,
+        user_id,
+        &user.password_hash,
+        &payload.current_password,
+    )
+    .await?;
+
+    // 3. Rate limiting, shared with the OTP settings
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+    let recent = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "count!", MAX(created_at) AS last_sent_at
+        FROM email_changes
+        WHERE user_id = $1
+        AND created_at > NOW() - INTERVAL '1 hour'
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if otp_config.exceeds_hourly_limit(recent.count)
+        || recent.last_sent_at.is_some_and(|last| !otp_config.can_resend(last))
+    {
+        return Err(AppError::too_many_requests(
+            "too_many_email_changes",
+            "Too many email change requests. Try again later.",
+        ));
+    }
+
+    // Other accounts are outside the user's scope
+    let in_use = is_email_taken(pool.get_ref(), &new_email).await?;
+
+    // 4. Replace any pending request. A row is stored even when the address is
+    //    taken (its code is never sent) so both cases look and rate-limit alike.
+    let code = generate_otp();
+    let expires_at = Utc::now() + Duration::minutes(otp_config.expiry_minutes);
+
+    sqlx::query!(
+        r#"
+        WITH cleared AS (
+            DELETE FROM email_changes WHERE user_id = $1 AND confirmed_at IS NULL
+        )
+        INSERT INTO email_changes (user_id, old_email, new_email, code_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        user_id,
+        user.email,
+        new_email,
+        sign(&config.code_key, &code_message(user_id, &code)),
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // 5. Code to the new address, or a notice when it already has an account
+    if in_use {
+        spawn_email("email in use", move || send_email_in_use_notice(&new_email));
+    } else {
+        let expiry_minutes = otp_config.expiry_minutes;
+        spawn_email("email change code", move || {
+            send_email_change_code(&new_email, &code, expiry_minutes)
+        });
+    }
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "message": "If the address can be used, a confirmation code has been sent to it."
+    })))
+}
+
+// Apply the pending change once the code sent to the new address is confirmed
+#[post("/email/confirm")]
+pub async fn confirm_email_change(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<EmailChangeConfig>,
+    payload: web::Json<EmailChangeConfirmPayload>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+
+    let user_id = auth.user_id;
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    let pending = match sqlx::query!(
+        r#"
+        SELECT e.id, e.old_email, e.new_email, e.code_hash, e.attempts, u.username
+        FROM email_changes e
+        JOIN users u ON u.id = e.user_id
+        WHERE e.user_id = $1
+        AND e.confirmed_at IS NULL
+        AND e.expires_at > NOW()
+        ORDER BY e.created_at DESC
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    {
+        Some(row) if row.attempts < config.max_code_attempts => row,
+        _ => return Err(invalid_code()),
+    };
+
+    if !verify_signature(&config.code_key, &code_message(user_id, payload.code.trim()), &pending.code_hash) {
+        let _ = sqlx::query!(
+            "UPDATE email_changes SET attempts = attempts + 1 WHERE id = $1",
+            pending.id
+        )
+        .execute(&mut *tx)
+        .await;
+        let _ = tx.commit().await;
+
+        return Err(invalid_code());
+    }
+
+    let revert_token = generate_token();
+    let revert_expires_at = Utc::now() + Duration::hours(config.revert_ttl_hours);
+
+    // Guarded by the old address so a stale request cannot overwrite a newer change
+    let updated = sqlx::query!(
+        "UPDATE users SET email = $1, email_verified = TRUE WHERE id = $2 AND email = $3",
+        pending.new_email,
+        user_id,
+        pending.old_email
+    )
+    .execute(&mut *tx)
+    .await;
+
+    let superseded = match updated {
+        Ok(result) => result.rows_affected() == 0,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => true,
+        Err(e) => return Err(e.into()),
+    };
+    // The address was taken (or the email changed) after the request;
+    // answer like a bad code so nothing is revealed about other accounts
+    if superseded {
+        drop(tx);
+        let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+        let _ = sqlx::query!("DELETE FROM email_changes WHERE id = $1", pending.id)
+            .execute(&mut *tx)
+            .await;
+        let _ = tx.commit().await;
+
+        return Err(invalid_code());
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE email_changes
+        SET confirmed_at = NOW(), revert_token_hash = $1, revert_expires_at = $2
+        WHERE id = $3
+        "#,
+        hash_token(&revert_token),
+        revert_expires_at,
+        pending.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let event = AuditEvent {
+        target_user_id: Some(user_id),
+        ..AuditEvent::from_request(&req, AuditEventType::EmailChanged, AuditOutcome::Success)
+    };
+    audit::record(pool.get_ref(), event).await;
+
+    // Undo link goes to the previous address
+    let revert_url = format!(
+        "{}/api/v1/auth/email/revert?token={}",
+        app_config.public_url, revert_token
+    );
+    let (old_email, new_email, username) =
+        (pending.old_email, pending.new_email.clone(), pending.username);
+    let revert_ttl_hours = config.revert_ttl_hours;
+    spawn_email("email changed", move || {
+        send_email_changed_notice(&old_email, &username, &new_email, &revert_url, revert_ttl_hours)
+    });
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Email address updated",
+        "email": pending.new_email
+    })))
+}
+
+// Confirmation page for the undo link (public; the owner may have lost access)
+#[get("/email/revert")]
+pub async fn revert_email_change_page(params: web::Query<EmailRevertParams>) -> impl Responder {
+    render_revert_page(&params.token, None)
+}
+
+// Restore the previous address, lock the account and sign out everywhere
+#[post("/email/revert")]
+pub async fn revert_email_change(
+    req: HttpRequest,
+    pool: web::Data<Pool<Postgres>>,
+    form: web::Form<EmailRevertParams>,
+) -> Result<HttpResponse, AppError> {
+    let change = match sqlx::query!(
+        r#"
+        SELECT id, user_id, old_email
+        FROM email_changes
+        WHERE revert_token_hash = $1
+        AND reverted_at IS NULL
+        AND revert_expires_at > NOW()
+        "#,
+        hash_token(&form.token)
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    {
+        Some(row) => row,
+        None => {
+            return Ok(render_revert_page("", Some("This link is invalid or has expired.")));
+        }
+    };
+
+    // The token identifies the account; everything else runs in its scope
+    let mut tx = begin_scope(pool.get_ref(), change.user_id, None).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET email = $1, locked_at = NOW(), lock_reason = $2
+        WHERE id = $3
+        "#,
+        change.old_email,
+        LOCK_EMAIL_CHANGE_REVERTED,
+        change.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        WITH pending AS (
+            DELETE FROM email_changes WHERE user_id = $2 AND confirmed_at IS NULL
+        )
+        UPDATE email_changes SET reverted_at = NOW() WHERE id = $1
+        "#,
+        change.id,
+        change.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    revoke_user_sessions(&mut *tx, change.user_id, None).await?;
+
+    tx.commit().await?;
+
+    let event = AuditEvent {
+        target_user_id: Some(change.user_id),
+        ..AuditEvent::from_request(&req, AuditEventType::EmailChangeReverted, AuditOutcome::Success)
+    };
+    audit::record(pool.get_ref(), event).await;
+
+    Ok(render_revert_page(
+        "",
+        Some("Your previous email address has been restored and your account is locked. Reset your password to sign in again."),
+    ))
+}
+
+fn render_revert_page(token: &str, message: Option<&str>) -> HttpResponse {
+    ConfirmActionPageTemplate {
+        title: "Undo email address change",
+        description: "This restores your previous email address, signs out every device and locks your account until you reset your password.",
+        action: "revert",
+        button: "Restore my email address",
+        token,
+        message,
+    }
+    .into_response()
+}
@@ -0,0 +1,181 @@
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{get, post, web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::Pool;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::reauth::reject_impersonation;
+use crate::config::app::AppConfig;
+use crate::config::export::ExportConfig;
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::export::{DataExportStatus, ExportDownloadParams};
+use crate::models::grant::{GrantPermission, ResourceType};
+use crate::services::data_export::{download_url, spawn_export, verify_download};
+use crate::services::grants::can_access;
+
+fn export_not_found() -> AppError {
+    AppError::not_found("export_not_found", "Export not found")
+}
+
+// Request a copy of everything stored about the logged-in user.
+// The archive is built in the background; at most one per interval.
+#[post("/export")]
+pub async fn request_export(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<ExportConfig>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+    let user_id = auth.user_id;
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    // Failed exports do not count towards the limit
+    let last_requested = sqlx::query_scalar!(
+        r#"
+        SELECT MAX(created_at) FROM data_exports
+        WHERE user_id = $1 AND status <> 'failed'
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(last) = last_requested {
+        let next_allowed = last + Duration::hours(config.min_interval_hours);
+        let wait = (next_allowed - Utc::now()).num_seconds();
+        if wait > 0 {
+            return Err(AppError::too_many_requests(
+                "export_requested_recently",
+                "You can request one data export per day. Try again later.",
+            )
+            .retry_after(wait as u64));
+        }
+    }
+
+    let export = sqlx::query!(
+        "INSERT INTO data_exports (user_id) VALUES ($1) RETURNING id, status, created_at",
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    spawn_export(
+        pool.get_ref().clone(),
+        app_config.get_ref().clone(),
+        config.get_ref().clone(),
+        export.id,
+        user_id,
+    );
+
+    Ok(HttpResponse::Accepted().json(DataExportStatus {
+        id: export.id,
+        status: export.status,
+        created_at: export.created_at,
+        completed_at: None,
+        expires_at: None,
+        download_url: None,
+    }))
+}
+
+// Status of an export the user owns or was granted, with a signed link once it is ready
+#[get("/export/{id}")]
+pub async fn export_status(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<ExportConfig>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    // The status carries the download link
+    reject_impersonation(&auth)?;
+    let export_id = path.into_inner();
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    let export = sqlx::query!(
+        r#"
+        SELECT id, user_id, status, created_at, completed_at, expires_at
+        FROM data_exports
+        WHERE id = $1
+        "#,
+        export_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(export_not_found)?;
+
+    // The owner, or someone the export was shared with
+    if !can_access(&mut *tx, &auth, ResourceType::DataExport, export.id, export.user_id, GrantPermission::Read).await? {
+        return Err(export_not_found());
+    }
+
+    let download_url = match export.expires_at {
+        Some(expires_at) if export.status == "ready" && expires_at > Utc::now() => {
+            Some(download_url(&app_config, &config, export.id, expires_at))
+        }
+        _ => None,
+    };
+
+    Ok(HttpResponse::Ok().json(DataExportStatus {
+        id: export.id,
+        status: export.status,
+        created_at: export.created_at,
+        completed_at: export.completed_at,
+        expires_at: export.expires_at,
+        download_url,
+    }))
+}
+
+// Download an archive through its signed link (no session needed)
+#[get("/{id}")]
+pub async fn download_export(
+    pool: web::Data<Pool<Postgres>>,
+    config: web::Data<ExportConfig>,
+    path: web::Path<Uuid>,
+    params: web::Query<ExportDownloadParams>,
+) -> Result<HttpResponse, AppError> {
+    let export_id = path.into_inner();
+
+    if !verify_download(&config, export_id, params.expires, &params.sig) {
+        return Err(AppError::forbidden("invalid_link", "This download link is invalid or has expired"));
+    }
+
+    serve_archive(pool.get_ref(), export_id).await
+}
+
+/// Response with a ready, unexpired archive as a JSON attachment
+pub async fn serve_archive(pool: &Pool<Postgres>, export_id: Uuid) -> Result<HttpResponse, AppError> {
+    let file_path = sqlx::query_scalar!(
+        r#"
+        SELECT file_path FROM data_exports
+        WHERE id = $1 AND status = 'ready' AND expires_at > NOW()
+        "#,
+        export_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .flatten()
+    .ok_or_else(export_not_found)?;
+
+    let body = tokio::fs::read(&file_path)
+        .await
+        .map_err(|e| AppError::internal(format!("data export read error: {}", e)))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "data-export-{}.json",
+                Utc::now().format("%Y-%m-%d")
+            ))],
+        })
+        .body(body))
+}
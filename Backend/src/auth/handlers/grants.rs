@@ -0,0 +1,252 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::Pool;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::handlers::export::serve_archive;
+use crate::auth::reauth::reject_impersonation;
+use crate::auth::validation::{sanitize_input, ValidationError};
+use crate::config::app::AppConfig;
+use crate::config::sharing::SharingConfig;
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::grant::{
+    CreateGrantPayload, GranteePayload, GranteeType, GrantPermission, ResourceGrant, ResourceType,
+    ShareLinkParams,
+};
+use crate::services::grants::{link_grant_resource, resource_owner, share_url, verify_share_link};
+use crate::services::organizations::membership_role;
+
+fn validation_failed(field: &str, message: &str) -> AppError {
+    AppError::validation(ValidationError::new(field, message))
+}
+
+// Sharing with a user by email; the grant itself is listed by GET /grants
+fn shared_with_user() -> HttpResponse {
+    HttpResponse::Accepted().json(serde_json::json!({
+        "message": "If this email address belongs to an account, it now has access"
+    }))
+}
+
+fn invalid_link() -> AppError {
+    AppError::forbidden("invalid_link", "This link is invalid or has expired")
+}
+
+// Share one of the caller's resources with a user, an organization or a link
+#[post("")]
+pub async fn create_grant(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<SharingConfig>,
+    payload: web::Json<CreateGrantPayload>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+    let payload = payload.into_inner();
+
+    // 1. Input validation
+    let now = Utc::now();
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(validation_failed("expires_at", "Expiry must be in the future"));
+    }
+    if let GranteePayload::Link = payload.grantee {
+        if !payload.resource_type.link_shareable() {
+            return Err(validation_failed("grantee", "This resource cannot be shared by link"));
+        }
+        match payload.expires_at {
+            None => return Err(validation_failed("expires_at", "Share links must have an expiry")),
+            Some(expires_at) if expires_at > now + Duration::hours(config.max_link_ttl_hours) => {
+                return Err(validation_failed(
+                    "expires_at",
+                    &format!("Share links can last at most {} hours", config.max_link_ttl_hours),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    // 2. Only the owner may share; grantees cannot pass access on
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+    match resource_owner(&mut *tx, payload.resource_type, payload.resource_id).await? {
+        Some(owner_id) if owner_id == auth.user_id => {}
+        _ => return Err(AppError::not_found("resource_not_found", "Resource not found")),
+    }
+
+    // 3. Resolve the grantee (outside the user scope: other accounts are not visible in it)
+    let (grantee_type, grantee_user_id, grantee_organization_id) = match &payload.grantee {
+        GranteePayload::User { email } => {
+            let email = sanitize_input(email);
+            match sqlx::query_scalar!("SELECT id FROM users WHERE LOWER(email) = LOWER($1)", email)
+                .fetch_optional(pool.get_ref())
+                .await?
+            {
+                Some(id) if id == auth.user_id => {
+                    return Err(validation_failed("grantee", "You cannot share a resource with yourself"));
+                }
+                Some(id) => (GranteeType::User, Some(id), None),
+                // Answered like a share, so the endpoint does not reveal who has an account
+                None => return Ok(shared_with_user()),
+            }
+        }
+        // Only organizations the owner belongs to
+        GranteePayload::Organization { id } => {
+            match membership_role(pool.get_ref(), *id, auth.user_id).await? {
+                Some(_) => (GranteeType::Organization, None, Some(*id)),
+                None => {
+                    return Err(AppError::not_found("organization_not_found", "Organization not found"));
+                }
+            }
+        }
+        GranteePayload::Link => (GranteeType::Link, None, None),
+    };
+
+    // 4. Store the grant
+    let created = sqlx::query!(
+        r#"
+        INSERT INTO resource_grants (
+            resource_type, resource_id, owner_id, grantee_type,
+            grantee_user_id, grantee_organization_id, permission, expires_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, created_at
+        "#,
+        payload.resource_type as ResourceType,
+        payload.resource_id,
+        auth.user_id,
+        grantee_type as GranteeType,
+        grantee_user_id,
+        grantee_organization_id,
+        payload.permission as GrantPermission,
+        payload.expires_at
+    )
+    .fetch_one(&mut *tx)
+    .await;
+
+    let grant = match created {
+        // A repeated share with a user looks like the first one, for the same reason
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() && grantee_type == GranteeType::User => {
+            return Ok(shared_with_user());
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(AppError::conflict("already_shared", "This resource is already shared with them"));
+        }
+        created => created?,
+    };
+
+    tx.commit().await?;
+
+    if grantee_type == GranteeType::User {
+        return Ok(shared_with_user());
+    }
+
+    let share_url = match (grantee_type, payload.expires_at) {
+        (GranteeType::Link, Some(expires_at)) => Some(share_url(&app_config, &config, grant.id, expires_at)),
+        _ => None,
+    };
+
+    Ok(HttpResponse::Created().json(ResourceGrant {
+        id: grant.id,
+        resource_type: payload.resource_type,
+        resource_id: payload.resource_id,
+        owner_id: auth.user_id,
+        grantee_type,
+        grantee_user_id,
+        grantee_organization_id,
+        permission: payload.permission,
+        expires_at: payload.expires_at,
+        created_at: grant.created_at,
+        share_url,
+    }))
+}
+
+// Live grants the caller made, and those made to them or their active organization
+#[get("")]
+pub async fn list_grants(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+) -> Result<HttpResponse, AppError> {
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    let grants = sqlx::query_as!(
+        ResourceGrant,
+        r#"
+        SELECT id, resource_type AS "resource_type: ResourceType", resource_id, owner_id,
+               grantee_type AS "grantee_type: GranteeType", grantee_user_id,
+               grantee_organization_id, permission AS "permission: GrantPermission",
+               expires_at, created_at, NULL::text AS share_url
+        FROM resource_grants
+        WHERE revoked_at IS NULL
+        AND (expires_at IS NULL OR expires_at > NOW())
+        AND (owner_id = $1 OR grantee_user_id = $1 OR grantee_organization_id = $2)
+        ORDER BY created_at DESC
+        "#,
+        auth.user_id,
+        auth.organization_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let (granted, received): (Vec<_>, Vec<_>) =
+        grants.into_iter().partition(|grant| grant.owner_id == auth.user_id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "granted": granted,
+        "received": received
+    })))
+}
+
+// Revoke a grant the caller made; share links stop working immediately
+#[delete("/{id}")]
+pub async fn revoke_grant(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let grant_id = path.into_inner();
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    let revoked = sqlx::query!(
+        r#"
+        UPDATE resource_grants SET revoked_at = NOW()
+        WHERE id = $1 AND owner_id = $2 AND revoked_at IS NULL
+        "#,
+        grant_id,
+        auth.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if revoked.rows_affected() == 0 {
+        return Err(AppError::not_found("grant_not_found", "Grant not found"));
+    }
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Access revoked"
+    })))
+}
+
+// Open a resource through a signed share link (no session needed)
+#[get("/{id}")]
+pub async fn open_shared_link(
+    pool: web::Data<Pool<Postgres>>,
+    config: web::Data<SharingConfig>,
+    path: web::Path<Uuid>,
+    params: web::Query<ShareLinkParams>,
+) -> Result<HttpResponse, AppError> {
+    let grant_id = path.into_inner();
+
+    if !verify_share_link(&config, grant_id, params.expires, &params.sig) {
+        return Err(invalid_link());
+    }
+
+    // The signature alone is not enough: the grant must still be live
+    match link_grant_resource(pool.get_ref(), grant_id).await? {
+        Some((resource_type, _)) if !resource_type.link_shareable() => Err(invalid_link()),
+        Some((ResourceType::DataExport, export_id)) => serve_archive(pool.get_ref(), export_id).await,
+        None => Err(invalid_link()),
+    }
+}
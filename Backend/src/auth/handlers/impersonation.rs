@@ -0,0 +1,66 @@
+use actix_web::{delete, http::header::USER_AGENT, post, web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::handlers::admin_users::actor;
+use crate::auth::jwt::create_jwt;
+use crate::auth::permissions::require_permission;
+use crate::auth::reauth::reject_impersonation;
+use crate::config::impersonation::ImpersonationConfig;
+use crate::error::AppError;
+use crate::models::role::PERM_USERS_IMPERSONATE;
+use crate::services::admin_users::{start_impersonation, stop_impersonation};
+
+// Act as the user: a short-lived token for them whose `act` claim names the admin.
+// The caller's own session is left as it is.
+#[post("/{id}/impersonate", wrap = "require_permission(PERM_USERS_IMPERSONATE)")]
+pub async fn impersonate_user(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    config: web::Data<ImpersonationConfig>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    // No chains: an impersonated admin cannot impersonate someone else
+    reject_impersonation(&auth)?;
+
+    let user_id = path.into_inner();
+    let expires_at = Utc::now() + Duration::minutes(config.ttl_minutes);
+    let user_agent = req.headers().get(USER_AGENT).and_then(|v| v.to_str().ok());
+
+    let session = start_impersonation(pool.get_ref(), &actor(&req, &auth), user_id, expires_at, user_agent).await?;
+
+    let token = create_jwt(
+        &user_id.to_string(),
+        &session.session_id.to_string(),
+        session.organization_id.map(|id| id.to_string()).as_deref(),
+        Some(&auth.user_id.to_string()),
+        session.expires_at,
+    )?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "token": token,
+        "token_type": "Bearer",
+        "user_id": user_id,
+        "expires_at": session.expires_at
+    })))
+}
+
+// End the impersonation session the request was made with
+#[delete("/impersonation")]
+pub async fn end_impersonation(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+) -> Result<HttpResponse, AppError> {
+    if !auth.is_impersonated() {
+        return Err(AppError::bad_request("not_impersonating", "This session is not an impersonation"));
+    }
+
+    stop_impersonation(pool.get_ref(), &actor(&req, &auth), auth.user_id, auth.session_id).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Impersonation ended"
+    })))
+}
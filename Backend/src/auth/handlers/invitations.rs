@@ -0,0 +1,271 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::Pool;
+use sqlx::Postgres;
+use sqlx::Transaction;
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::handlers::organizations::organization_not_found;
+use crate::auth::policy::PolicySet;
+use crate::auth::validation::{sanitize_input, validate_email};
+use crate::config::app::AppConfig;
+use crate::config::invitation::InvitationConfig;
+use crate::database::rls::{begin_organization_scope, begin_scope};
+use crate::error::AppError;
+use crate::models::invitation::{AcceptInvitationPayload, CreateInvitationPayload, InvitationSummary};
+use crate::models::organization::MemberRole;
+use crate::services::invitations::{accept, find_pending};
+use crate::services::organizations::{member_decision, membership_role};
+use crate::utils::email::{send_organization_invitation_email, spawn_email};
+use crate::utils::token::{generate_token, hash_token};
+
+fn invalid_invitation() -> AppError {
+    AppError::bad_request("invalid_invitation", "This invitation is invalid or has expired")
+}
+
+// The caller must be allowed to manage the organization's invitations.
+// Returns the transaction scoped to the organization.
+async fn authorize_invitations(
+    pool: &Pool<Postgres>,
+    policies: &PolicySet,
+    auth: &AuthenticatedUser,
+    organization_id: Uuid,
+) -> Result<Transaction<'static, Postgres>, AppError> {
+    let mut tx = begin_organization_scope(pool, auth, organization_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+    let role = membership_role(&mut *tx, organization_id, auth.user_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+
+    let decision = member_decision(
+        policies,
+        auth,
+        role,
+        "organization:manage_invitations",
+        serde_json::json!({ "type": "organization", "id": organization_id }),
+    );
+    if !decision.allowed {
+        return Err(AppError::forbidden("not_allowed", "You are not allowed to manage invitations"));
+    }
+
+    Ok(tx)
+}
+
+// Invite someone by email; a pending invitation to the same address is replaced
+#[post("/{id}/invitations")]
+pub async fn create_invitation(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    policies: web::Data<PolicySet>,
+    app_config: web::Data<AppConfig>,
+    config: web::Data<InvitationConfig>,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateInvitationPayload>,
+) -> Result<HttpResponse, AppError> {
+    let organization_id = path.into_inner();
+    let email = sanitize_input(&payload.email);
+
+    // 1. Input validation
+    validate_email(&email).map_err(AppError::validation)?;
+
+    // 2. The caller's role must allow granting the invited role
+    let mut tx = begin_organization_scope(pool.get_ref(), &auth, organization_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+    let role = membership_role(&mut *tx, organization_id, auth.user_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+
+    let decision = member_decision(
+        &policies,
+        &auth,
+        role,
+        "organization:invite",
+        serde_json::json!({
+            "type": "organization",
+            "id": organization_id,
+            "granted_role": payload.role,
+        }),
+    );
+    if !decision.allowed {
+        return Err(AppError::forbidden("not_allowed", "You are not allowed to invite members with this role"));
+    }
+
+    // 3. Nothing to do for existing members
+    let context = sqlx::query!(
+        r#"
+        SELECT o.name AS organization_name, u.username AS inviter,
+               EXISTS (
+                   SELECT 1 FROM memberships m
+                   JOIN users mu ON mu.id = m.user_id
+                   WHERE m.organization_id = o.id AND LOWER(mu.email) = LOWER($3)
+               ) AS "already_member!"
+        FROM organizations o, users u
+        WHERE o.id = $1 AND u.id = $2
+        "#,
+        organization_id,
+        auth.user_id,
+        email
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if context.already_member {
+        return Err(AppError::conflict("already_member", "This user is already a member"));
+    }
+
+    // 4. Store the invitation, replacing a pending one
+    let token = generate_token();
+    let expires_at = Utc::now() + Duration::hours(config.ttl_hours);
+
+    sqlx::query!(
+        r#"
+        UPDATE invitations SET revoked_at = NOW()
+        WHERE organization_id = $1 AND LOWER(email) = LOWER($2)
+        AND accepted_at IS NULL AND revoked_at IS NULL
+        "#,
+        organization_id,
+        email
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let invitation = sqlx::query!(
+        r#"
+        INSERT INTO invitations (organization_id, email, role, token_hash, invited_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_at
+        "#,
+        organization_id,
+        email,
+        payload.role as MemberRole,
+        hash_token(&token),
+        auth.user_id,
+        expires_at
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // 5. Email the link; the web app accepts it, or registers with it
+    let invite_url = format!("{}/invite?token={}", app_config.frontend_url, token);
+    let (to, organization_name, inviter) = (email.clone(), context.organization_name, context.inviter);
+    let role = payload.role.as_str();
+    spawn_email("organization invitation", move || {
+        send_organization_invitation_email(&to, &organization_name, &inviter, role, &invite_url, expires_at)
+    });
+
+    Ok(HttpResponse::Created().json(InvitationSummary {
+        id: invitation.id,
+        email,
+        role: payload.role,
+        invited_by: Some(auth.user_id),
+        expires_at,
+        created_at: invitation.created_at,
+    }))
+}
+
+// Pending invitations of an organization
+#[get("/{id}/invitations")]
+pub async fn list_invitations(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    policies: web::Data<PolicySet>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let organization_id = path.into_inner();
+
+    let mut tx = authorize_invitations(pool.get_ref(), &policies, &auth, organization_id).await?;
+
+    let invitations = sqlx::query_as!(
+        InvitationSummary,
+        r#"
+        SELECT id, email, role AS "role: MemberRole", invited_by, expires_at, created_at
+        FROM invitations
+        WHERE organization_id = $1
+        AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY created_at DESC
+        "#,
+        organization_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(invitations))
+}
+
+// Revoke a pending invitation; its link stops working
+#[delete("/{id}/invitations/{invitation_id}")]
+pub async fn revoke_invitation(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    policies: web::Data<PolicySet>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (organization_id, invitation_id) = path.into_inner();
+
+    let mut tx = authorize_invitations(pool.get_ref(), &policies, &auth, organization_id).await?;
+
+    let revoked = sqlx::query!(
+        r#"
+        UPDATE invitations SET revoked_at = NOW()
+        WHERE id = $1 AND organization_id = $2
+        AND accepted_at IS NULL AND revoked_at IS NULL
+        "#,
+        invitation_id,
+        organization_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if revoked.rows_affected() == 0 {
+        return Err(AppError::not_found("invitation_not_found", "Invitation not found"));
+    }
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Invitation revoked"
+    })))
+}
+
+// Accept an invitation as the logged-in user; it must have been sent to their address
+#[post("/accept")]
+pub async fn accept_invitation(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    payload: web::Json<AcceptInvitationPayload>,
+) -> Result<HttpResponse, AppError> {
+    // Found by its token; the user is not yet a member of its organization
+    let invitation = find_pending(pool.get_ref(), &hash_token(payload.token.trim()))
+        .await?
+        .ok_or_else(invalid_invitation)?;
+
+    let mut tx = begin_scope(pool.get_ref(), auth.user_id, Some(invitation.organization_id)).await?;
+
+    let email = sqlx::query_scalar!("SELECT email FROM users WHERE id = $1", auth.user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(AppError::invalid_token)?;
+
+    if !email.eq_ignore_ascii_case(&invitation.email) {
+        return Err(AppError::forbidden(
+            "invitation_email_mismatch",
+            "This invitation was sent to a different email address",
+        ));
+    }
+
+    if !accept(&mut tx, &invitation, auth.user_id).await? {
+        return Err(invalid_invitation());
+    }
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "You have joined the organization",
+        "organization_id": invitation.organization_id
+    })))
+}
@@ -1,58 +1,60 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{post, web, HttpRequest, HttpResponse};
 use sqlx::Pool;
 use sqlx::Postgres;
 use sqlx::types::chrono::Utc;
+use uuid::Uuid;
 
 
 
-use crate::auth::jwt::create_jwt;
 use crate::auth::validation::validate_login_payload;
 use crate::models::login::LoginPayload;
+use crate::models::user::lock_message;
 use crate::auth::cookies::set_access_token;
-use crate::utils::hash::verify_password;
+use crate::utils::hash::needs_rehash;
+use crate::utils::hash_pool::HashPool;
 use crate::config::login::LoginLimitConfig;
+use crate::config::security::SecurityConfig;
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::sessions::start_session;
 
 
 
 // Login handler
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     pool: web::Data<Pool<Postgres>>,
     config: web::Data<LoginLimitConfig>,
+    security_config: web::Data<SecurityConfig>,
+    hash_pool: web::Data<HashPool>,
     payload: web::Json<LoginPayload>,
-) -> impl Responder {
+) -> Result<HttpResponse, AppError> {
     // Input validation
-    match validate_login_payload(&payload.email, &payload.password) {
-        Ok(_) => {},
-        Err(validation_errors) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Validation failed",
-                "details": validation_errors
-            }));
-        }
-    }
+    validate_login_payload(&payload.email, &payload.password).map_err(AppError::Validation)?;
 
-    let rec = match sqlx::query!("SELECT id, password_hash FROM users WHERE email = $1", payload.email)
+    let rec = sqlx::query!(
+        // Soft-deleted accounts cannot sign in
+        "SELECT id, password_hash, locked_at, lock_reason FROM users WHERE email = $1 AND deleted_at IS NULL",
+        payload.email
+    )
         .fetch_optional(pool.get_ref())
-        .await
-    {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+        .await?;
 
     let row = match rec {
         Some(r) => r,
         None => {
             // Log failed login attempt for security monitoring
-            tracing::warn!("Failed login attempt for email: {}", payload.email);
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Invalid credentials"
-            }));
+            tracing::warn!("Failed login attempt for an unknown account");
+            login_failure(&pool, &req, None, "unknown_account").await;
+            return Err(invalid_credentials());
         }
     };
 
     // 3. Check failed attempts in lockout window
-    let recent_attempts = match sqlx::query!(
+    let recent_attempts = sqlx::query!(
         r#"
         SELECT COUNT(*) as "count!"
         FROM failed_logins
@@ -63,37 +65,63 @@ pub async fn login(
         config.lockout_secs as i32
     )
     .fetch_one(pool.get_ref())
-    .await
-    {
-        Ok(r) => r.count,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+    .await?
+    .count;
 
     if recent_attempts >= config.max_attempts as i64 {
-        return HttpResponse::TooManyRequests().json(serde_json::json!({
-            "error": "Too many failed login attempts. Please try again later."
-        }))
+        METRICS.lockout_rejections.inc();
+        login_failure(&pool, &req, Some(row.id), "locked_out").await;
+        return Err(AppError::too_many_requests(
+            "too_many_attempts",
+            "Too many failed login attempts. Please try again later.",
+        ));
     }
 
 
 
-    // verify password (argon2 PasswordHash)
-    match verify_password(&payload.password, &row.password_hash) {
+    // verify password (Argon2, or an imported legacy hash)
+    match hash_pool.verify(&payload.password, &row.password_hash).await {
         Ok(true) => {
+            // Locked accounts are only reported once the password is proven
+            if row.locked_at.is_some() {
+                login_failure(&pool, &req, Some(row.id), "account_locked").await;
+                return Err(AppError::forbidden("account_locked", lock_message(row.lock_reason.as_deref())));
+            }
+
             // ✅ Success → clear failed attempts
             let _ = sqlx::query!("DELETE FROM failed_logins WHERE user_id = $1", row.id)
                 .execute(pool.get_ref())
                 .await;
 
-            // Create JWT
-            let token = match create_jwt(&row.id.to_string()) {
-                Ok(t) => t,
-                Err(_) => return HttpResponse::InternalServerError().finish(),
+            // Upgrade imported legacy hashes (bcrypt, PBKDF2, scrypt) to Argon2
+            if needs_rehash(&row.password_hash) {
+                match hash_pool.hash(&payload.password).await {
+                    Ok(upgraded) => {
+                        let _ = sqlx::query!(
+                            "UPDATE users SET password_hash = $1 WHERE id = $2",
+                            upgraded,
+                            row.id
+                        )
+                        .execute(pool.get_ref())
+                        .await;
+                    }
+                    Err(e) => tracing::error!("Password rehash error: {}", e),
+                }
+            }
+
+            // Open a session and create the JWT bound to it
+            let token = start_session(pool.get_ref(), row.id, &req, security_config.jwt_expiration_hours).await?;
+
+            let event = AuditEvent {
+                target_user_id: Some(row.id),
+                ..AuditEvent::from_request(&req, AuditEventType::LoginSuccess, AuditOutcome::Success)
             };
+            audit::record(pool.get_ref(), event).await;
+            METRICS.logins.with_label_values(&["success"]).inc();
 
-            return HttpResponse::Ok()
+            Ok(HttpResponse::Ok()
                 .cookie(set_access_token(&token))
-                .json(serde_json::json!({"message": "Logged in successfully"}))
+                .json(serde_json::json!({"message": "Logged in successfully"})))
 
         } // Password correct - Continue
 
@@ -107,14 +135,40 @@ pub async fn login(
             .execute(pool.get_ref())
             .await;
 
-            tracing::warn!("Failed login attempt for email: {}", payload.email);
-            return HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Invalid credentials"
-            }));
-        }
-        Err(e) => {
-            tracing::error!("Password verification error: {}", e);
-            return HttpResponse::InternalServerError().finish();
+            tracing::warn!(user_id = %row.id, "Failed login attempt");
+            login_failure(&pool, &req, Some(row.id), "invalid_password").await;
+
+            // This attempt used up the last one: the account is now locked out
+            if recent_attempts + 1 >= config.max_attempts as i64 {
+                let event = AuditEvent {
+                    target_user_id: Some(row.id),
+                    details: serde_json::json!({
+                        "attempts": recent_attempts + 1,
+                        "lockout_secs": config.lockout_secs
+                    }),
+                    ..AuditEvent::from_request(&req, AuditEventType::Lockout, AuditOutcome::Failure)
+                };
+                audit::record(pool.get_ref(), event).await;
+            }
+
+            Err(invalid_credentials())
         }
+        Err(e) => Err(e.into()),
     }
-}
\ No newline at end of file
+}
+
+// Unknown address and wrong password look the same to the client
+fn invalid_credentials() -> AppError {
+    AppError::unauthorized("invalid_credentials", "Invalid credentials")
+}
+
+// Audit and count a refused sign-in; the address tried is not recorded
+async fn login_failure(pool: &Pool<Postgres>, req: &HttpRequest, user_id: Option<Uuid>, reason: &str) {
+    METRICS.logins.with_label_values(&[reason]).inc();
+    let event = AuditEvent {
+        target_user_id: user_id,
+        details: serde_json::json!({ "reason": reason }),
+        ..AuditEvent::from_request(req, AuditEventType::LoginFailure, AuditOutcome::Failure)
+    };
+    audit::record(pool, event).await;
+}
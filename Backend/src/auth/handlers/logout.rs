@@ -1,15 +1,63 @@
-// Session removed - using JWT-only authentication
-use actix_web::{post, HttpResponse, Responder};
+// Access tokens are JWTs backed by rows in `sessions`
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
 use crate::auth::cookies::clear_access_token;
+use crate::auth::jwt::validate_jwt;
+use crate::auth::middleware::{bearer_token, cookie_token};
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::services::admin_users::{stop_impersonation, Actor};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::sessions::revoke_session;
 use serde_json::json;
 
 #[post("/logout")]
-pub async fn logout() -> impl Responder {
-    // JWT-only authentication - no session cleanup needed
+pub async fn logout(req: HttpRequest, pool: web::Data<Pool<Postgres>>) -> impl Responder {
+    // Revoke the session behind the presented token so it can't be replayed
+    let claims = bearer_token(&req)
+        .or_else(|| cookie_token(&req))
+        .and_then(|token| validate_jwt(&token).ok());
+    let impersonated = claims.as_ref().is_some_and(|c| c.act.is_some());
+    if let Some(claims) = claims
+        && let Ok(session_id) = Uuid::parse_str(&claims.sid)
+    {
+        // Signing out of an impersonation ends it, on the admin's audit trail
+        let impersonation = claims
+            .act
+            .as_ref()
+            .and_then(|act| Uuid::parse_str(&act.sub).ok())
+            .zip(Uuid::parse_str(&claims.sub).ok());
+        let revoked = match impersonation {
+            Some((impersonator_id, user_id)) => {
+                let actor = Actor {
+                    user_id: Some(impersonator_id),
+                    ip_address: req.connection_info().realip_remote_addr().map(str::to_string),
+                };
+                stop_impersonation(pool.get_ref(), &actor, user_id, session_id)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            None => revoke_session(pool.get_ref(), session_id).await.map_err(|e| e.to_string()),
+        };
+        if let Err(e) = revoked {
+            tracing::error!("Failed to revoke session on logout: {}", e);
+        }
 
-    // Instruct the browser to delete the auth cookie (Max-Age=0)
-    HttpResponse::Ok()
-        .cookie(clear_access_token())
-        .json(json!({ "message": "Logged out" }))
-}
+        let event = AuditEvent {
+            actor_id: impersonation.map(|(impersonator_id, _)| impersonator_id),
+            target_user_id: Uuid::parse_str(&claims.sub).ok(),
+            details: serde_json::json!({ "session_id": session_id }),
+            ..AuditEvent::from_request(&req, AuditEventType::Logout, AuditOutcome::Success)
+        };
+        audit::record(pool.get_ref(), event).await;
+    }
 
+    // Instruct the browser to delete the auth cookie (Max-Age=0);
+    // impersonation tokens are never in it, so it is still the admin's own
+    let mut response = HttpResponse::Ok();
+    if !impersonated {
+        response.cookie(clear_access_token());
+    }
+    response.json(json!({ "message": "Logged out" }))
+}
@@ -1,4 +1,6 @@
 pub mod login;
 pub mod signup;
 pub mod logout;
-pub mod reset;
\ No newline at end of file
+pub mod reset;
+pub mod password;
+pub mod profile;
@@ -0,0 +1,237 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::Pool;
+use sqlx::Postgres;
+use uuid::Uuid;
+
+use crate::auth::cookies::set_access_token;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::validation::{sanitize_input, validate_organization_name, validate_slug};
+use crate::database::rls::{begin_organization_scope, begin_scope, begin_user_scope};
+use crate::error::AppError;
+use crate::models::organization::{
+    CreateOrganizationPayload, MemberRole, OrganizationMember, OrganizationSummary,
+    SwitchOrganizationPayload,
+};
+use crate::services::organizations::slugify;
+use crate::services::sessions::switch_organization;
+
+// Non-members get the same answer as for a missing organization
+pub fn organization_not_found() -> AppError {
+    AppError::not_found("organization_not_found", "Organization not found")
+}
+
+// Create an organization; the creator becomes its owner
+#[post("")]
+pub async fn create_organization(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    payload: web::Json<CreateOrganizationPayload>,
+) -> Result<HttpResponse, AppError> {
+    let name = sanitize_input(&payload.name);
+    let slug = match payload.slug.as_deref().map(sanitize_input) {
+        Some(slug) => slug,
+        None => slugify(&name),
+    };
+
+    let errors: Vec<_> = [validate_organization_name(&name), validate_slug(&slug)]
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    // Scoped to the new organization so its owner membership can be added
+    let organization_id = Uuid::new_v4();
+    let mut tx = begin_scope(pool.get_ref(), auth.user_id, Some(organization_id)).await?;
+
+    let created = sqlx::query!(
+        r#"
+        INSERT INTO organizations (id, name, slug, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, created_at
+        "#,
+        organization_id,
+        name,
+        slug,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await;
+
+    let organization = match created {
+        Ok(row) => row,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(AppError::conflict("slug_taken", "This slug is already taken"));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    sqlx::query!(
+        "INSERT INTO memberships (organization_id, user_id, role) VALUES ($1, $2, $3)",
+        organization.id,
+        auth.user_id,
+        MemberRole::Owner as MemberRole
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(OrganizationSummary {
+        id: organization.id,
+        name,
+        slug,
+        role: MemberRole::Owner,
+        created_at: organization.created_at,
+    }))
+}
+
+// Organizations the logged-in user belongs to
+#[get("")]
+pub async fn list_organizations(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+) -> Result<HttpResponse, AppError> {
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    let organizations = sqlx::query_as!(
+        OrganizationSummary,
+        r#"
+        SELECT o.id, o.name, o.slug, m.role AS "role: MemberRole", o.created_at
+        FROM memberships m
+        JOIN organizations o ON o.id = m.organization_id
+        WHERE m.user_id = $1
+        ORDER BY m.created_at
+        "#,
+        auth.user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "organizations": organizations,
+        "active_organization_id": auth.organization_id
+    })))
+}
+
+// Members of an organization (visible to its members only)
+#[get("/{id}/members")]
+pub async fn list_members(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let organization_id = path.into_inner();
+
+    let mut tx = begin_organization_scope(pool.get_ref(), &auth, organization_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+
+    let members = sqlx::query_as!(
+        OrganizationMember,
+        r#"
+        SELECT u.id AS user_id, u.username, u.display_name,
+               m.role AS "role: MemberRole", m.created_at AS joined_at
+        FROM memberships m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.organization_id = $1
+        ORDER BY m.created_at
+        "#,
+        organization_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(members))
+}
+
+// Leave an organization. The last owner cannot leave while others remain;
+// when the last member leaves, the organization is deleted.
+#[delete("/{id}/members/me")]
+pub async fn leave_organization(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let organization_id = path.into_inner();
+
+    let mut tx = begin_organization_scope(pool.get_ref(), &auth, organization_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+
+    let counts = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) AS "members!",
+            COUNT(*) FILTER (WHERE role = 'owner') AS "owners!",
+            BOOL_OR(user_id = $2 AND role = 'owner') AS "is_owner!"
+        FROM memberships
+        WHERE organization_id = $1
+        HAVING BOOL_OR(user_id = $2)
+        "#,
+        organization_id,
+        auth.user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(organization_not_found)?;
+
+    if counts.members == 1 {
+        sqlx::query!("DELETE FROM organizations WHERE id = $1", organization_id)
+            .execute(&mut *tx)
+            .await?;
+    } else if counts.is_owner && counts.owners == 1 {
+        return Err(AppError::conflict("last_owner", "Make another member an owner before leaving"));
+    } else {
+        sqlx::query!(
+            "DELETE FROM memberships WHERE organization_id = $1 AND user_id = $2",
+            organization_id,
+            auth.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    // Tokens scoped to the organization stop working; re-issue this one unscoped
+    let mut response = HttpResponse::Ok();
+    if auth.organization_id == Some(organization_id) {
+        let token = switch_organization(pool.get_ref(), auth.session_id, auth.user_id, None).await?;
+        // Impersonation tokens never replace the admin's own cookie
+        if let Some(token) = token
+            && !auth.is_impersonated()
+        {
+            response.cookie(set_access_token(&token));
+        }
+    }
+
+    Ok(response.json(serde_json::json!({
+        "message": "You have left the organization"
+    })))
+}
+
+// Scope the current session to another organization, or back to personal.
+// The previous token stops working, so the new one is also returned in the body.
+#[post("/switch")]
+pub async fn switch_active_organization(
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    payload: web::Json<SwitchOrganizationPayload>,
+) -> Result<HttpResponse, AppError> {
+    let token = switch_organization(pool.get_ref(), auth.session_id, auth.user_id, payload.organization_id)
+        .await?
+        .ok_or_else(organization_not_found)?;
+
+    let mut response = HttpResponse::Ok();
+    // Impersonation tokens never replace the admin's own cookie
+    if !auth.is_impersonated() {
+        response.cookie(set_access_token(&token));
+    }
+    Ok(response.json(serde_json::json!({
+        "message": "Active organization changed",
+        "active_organization_id": payload.organization_id,
+        "access_token": token
+    })))
+}
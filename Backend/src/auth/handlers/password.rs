@@ -0,0 +1,134 @@
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use sqlx::Pool;
+use sqlx::Postgres;
+
+use crate::auth::breached::{BreachVerdict, BreachedPasswordChecker, BREACHED_PASSWORD_MESSAGE};
+use crate::auth::cookies::clear_access_token;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::password_policy::PasswordPolicy;
+use crate::auth::reauth::{reject_impersonation, verify_current_password};
+use crate::auth::validation::{validate_password_change_payload, ValidationError};
+use crate::config::login::LoginLimitConfig;
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::password::ChangePasswordPayload;
+use crate::services::audit::{self, AuditEvent};
+use crate::services::password_history::{self, PASSWORD_REUSED_MESSAGE};
+use crate::services::sessions::revoke_user_sessions;
+use crate::utils::email::{send_password_changed_email, spawn_email};
+use crate::utils::hash_pool::HashPool;
+
+// Change password for the logged-in user
+#[post("/password")]
+#[allow(clippy::too_many_arguments)] // one extractor per piece of app data
+pub async fn change_password(
+    req: HttpRequest,
+    auth: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    hash_pool: web::Data<HashPool>,
+    policy: web::Data<PasswordPolicy>,
+    breach_checker: web::Data<BreachedPasswordChecker>,
+    login_config: web::Data<LoginLimitConfig>,
+    payload: web::Json<ChangePasswordPayload>,
+) -> Result<HttpResponse, AppError> {
+    reject_impersonation(&auth)?;
+
+    let (user_id, session_id) = (auth.user_id, auth.session_id);
+
+    // Queries run in the user's scope; no transaction is held open while
+    // hashing or checking breaches
+    let user = sqlx::query!(
+        "SELECT username, email, password_hash FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&mut *begin_user_scope(pool.get_ref(), &auth).await?)
+    .await?
+    .ok_or_else(AppError::invalid_token)?;
+
+    // 1. Input validation (policy, contextual checks, new != current)
+    validate_password_change_payload(
+        &payload.current_password,
+        &payload.new_password,
+        &policy,
+        &[&user.username, &user.email],
+    )
+    .map_err(AppError::Validation)?;
+
+    // 2. Re-authenticate (shares the login lockout)
+    verify_current_password(
+        pool.get_ref(),
+        &hash_pool,
+        &login_config,
+        user_id,
+        &user.password_hash,
+        &payload.current_password,
+    )
+    .await?;
+
+    // 3. Breached-password check (reject or warn, per config)
+    let mut password_warning = None;
+    match breach_checker.check(&payload.new_password).await {
+        BreachVerdict::Reject => {
+            return Err(AppError::validation(ValidationError::new("new_password", BREACHED_PASSWORD_MESSAGE)));
+        }
+        BreachVerdict::Warn => password_warning = Some(BREACHED_PASSWORD_MESSAGE),
+        BreachVerdict::Clean => {}
+    }
+
+    // 4. Reject reuse of a recent password. The current one was verified in
+    //    step 2 and the new password differs from it, so its hash (also the
+    //    newest history entry) is not checked again.
+    let mut recent_hashes = password_history::recent_hashes(
+        &mut *begin_user_scope(pool.get_ref(), &auth).await?,
+        user_id,
+        policy.history_size,
+    )
+    .await?;
+    recent_hashes.retain(|hashed| *hashed != user.password_hash);
+    if password_history::is_reused(&hash_pool, &payload.new_password, &recent_hashes).await? {
+        return Err(AppError::validation(ValidationError::new("new_password", PASSWORD_REUSED_MESSAGE)));
+    }
+
+    // 5. Hash and store the new password
+    let hashed = hash_pool.hash(&payload.new_password).await?;
+
+    let mut tx = begin_user_scope(pool.get_ref(), &auth).await?;
+
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1, password_changed_at = NOW() WHERE id = $2",
+        hashed,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    password_history::record(&mut tx, user_id, &hashed, policy.history_size).await?;
+
+    // 6. Sign out other devices (and this one too, unless asked to keep it)
+    let keep = payload.keep_current_session.then_some(session_id);
+    let revoked = revoke_user_sessions(&mut *tx, user_id, keep).await?;
+
+    tx.commit().await?;
+
+    let event = AuditEvent {
+        target_user_id: Some(user_id),
+        details: serde_json::json!({ "revoked_sessions": revoked }),
+        ..AuditEvent::from_request(&req, AuditEventType::PasswordChanged, AuditOutcome::Success)
+    };
+    audit::record(pool.get_ref(), event).await;
+
+    // 7. Notify the account owner
+    let (email, username) = (user.email, user.username);
+    spawn_email("password changed", move || send_password_changed_email(&email, &username));
+
+    let mut response = HttpResponse::Ok();
+    if !payload.keep_current_session {
+        response.cookie(clear_access_token());
+    }
+    Ok(response.json(serde_json::json!({
+        "message": "Password changed successfully",
+        "revoked_sessions": revoked,
+        "warning": password_warning
+    })))
+}
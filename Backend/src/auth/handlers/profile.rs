@@ -0,0 +1,83 @@
+use actix_web::{get, patch, web, HttpResponse};
+use sqlx::Pool;
+use sqlx::Postgres;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::validation::{sanitize_input, validate_profile_update_payload};
+use crate::database::rls::begin_user_scope;
+use crate::error::AppError;
+use crate::models::user::{UpdateProfilePayload, UserProfile};
+
+// Current user's profile
+#[get("")]
+pub async fn get_me(
+    user: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+) -> Result<HttpResponse, AppError> {
+    let mut tx = begin_user_scope(pool.get_ref(), &user).await?;
+
+    let profile = sqlx::query_as!(
+        UserProfile,
+        r#"
+        SELECT id, username, email, display_name, avatar_url,
+               COALESCE(email_verified, FALSE) AS "email_verified!", created_at
+        FROM users
+        WHERE id = $1
+        "#,
+        user.user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(user_not_found)?;
+
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+// Update username and display fields
+#[patch("")]
+pub async fn update_me(
+    user: AuthenticatedUser,
+    pool: web::Data<Pool<Postgres>>,
+    payload: web::Json<UpdateProfilePayload>,
+) -> Result<HttpResponse, AppError> {
+    validate_profile_update_payload(
+        payload.username.as_deref(),
+        payload.display_name.as_deref(),
+        payload.avatar_url.as_deref(),
+    )
+    .map_err(AppError::Validation)?;
+
+    // Omitted fields keep their value; an empty display field is cleared
+    let username = payload.username.as_deref().map(sanitize_input);
+    let display_name = payload.display_name.as_deref().map(sanitize_input);
+    let avatar_url = payload.avatar_url.as_deref().map(sanitize_input);
+
+    let mut tx = begin_user_scope(pool.get_ref(), &user).await?;
+
+    let profile = sqlx::query_as!(
+        UserProfile,
+        r#"
+        UPDATE users SET
+            username = COALESCE($2, username),
+            display_name = CASE WHEN $3::text IS NULL THEN display_name ELSE NULLIF($3, '') END,
+            avatar_url = CASE WHEN $4::text IS NULL THEN avatar_url ELSE NULLIF($4, '') END
+        WHERE id = $1
+        RETURNING id, username, email, display_name, avatar_url,
+                  COALESCE(email_verified, FALSE) AS "email_verified!", created_at
+        "#,
+        user.user_id,
+        username,
+        display_name,
+        avatar_url
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(user_not_found)?;
+
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+fn user_not_found() -> AppError {
+    AppError::not_found("user_not_found", "User not found")
+}
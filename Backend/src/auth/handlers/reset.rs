@@ -1,177 +1,245 @@
-use actix_web::error::ErrorInternalServerError;
-use actix_web::{HttpResponse, Responder, post, web};
+use actix_web::{HttpRequest, HttpResponse, post, web};
 use chrono::{Duration, Utc};
 use sqlx::Pool;
 use sqlx::Postgres;
-
-
+use uuid::Uuid;
+
+use crate::auth::breached::{BreachVerdict, BreachedPasswordChecker, BREACHED_PASSWORD_MESSAGE};
+use crate::auth::password_policy::PasswordPolicy;
+use crate::auth::validation::ValidationError;
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::models::audit::{AuditEventType, AuditOutcome};
 use crate::models::reset::{ResetRequest, ResetVerifyPayload};
-use crate::utils::hash::hash_password;
+use crate::models::user::{LOCK_EMAIL_CHANGE_REVERTED, LOCK_PASSWORD_RESET_REQUIRED};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::password_history::{self, PASSWORD_REUSED_MESSAGE};
+use crate::utils::email::spawn_email;
+use crate::utils::hash_pool::HashPool;
 use crate::utils::otp::{generate_otp, send_otp_email};
 use crate::config::otp::OtpConfig;
 
 #[post("/reset/request")]
-pub async fn reset_request(pool: web::Data<Pool<Postgres>>, data: web::Json<ResetRequest>, otp_config: web::Data<OtpConfig>,) -> impl Responder {
+pub async fn reset_request(req: HttpRequest, pool: web::Data<Pool<Postgres>>, data: web::Json<ResetRequest>, otp_config: web::Data<OtpConfig>,) -> Result<HttpResponse, AppError> {
     let email = data.email.to_lowercase();
 
     // 1. Check if user exists (but do not reveal result!)
-    let user = sqlx::query!("SELECT id FROM users WHERE email = $1", email)
+    let user = sqlx::query!("SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL", email)
         .fetch_optional(pool.get_ref())
-        .await;
+        .await?;
 
     match user {
-        Ok(Some(record)) => {
-            let user_id = record.id;
-
-            // Rate limiting
-            // 1a. Check if more than 5 OTP requests in last hour
-            let count_last_hour: (i64,) = sqlx::query_as(
-                "SELECT COUNT(*) FROM password resets
-                WHERE user_id = $1
-                AND requested_at > NOW() - INTERVAL '1 hour'"
-            )
-            .bind(user_id)
-            .fetch_one(pool.get_ref())
-            .await
-            .unwrap_or((0,));
-
-            if count_last_hour.0 >= otp_config.limit_per_hour {
-                return HttpResponse::TooManyRequests().json(serde_json::json!({
-                    "error": "Too many reset requests. Try again later."
-                }));
-            }
-
-            // 1b. Chech last request < 1 minute ago
-            if let Ok(Some((last_request,))) = sqlx::query_as::<_, (chrono::NaiveDateTime,)>(
-            "SELECT requested_at 
-                FROM password_resets 
-                WHERE user_id = $1 
-                ORDER BY requested_at DESC 
-                LIMIT 1"
-            )
-            .bind(user_id)
-            .fetch_optional(pool.get_ref())
-            .await
-            {
-                let now = Utc::now().naive_utc();
-                if now.signed_duration_since(last_request) < Duration::minutes(1) {
-                    return HttpResponse::TooManyRequests().json(serde_json::json!({
-                        "error": "Please wait at least 1 minute before requesting another OTP."
-                    }));
-                }
-            }
-
-            // 2. Generate OTP
-            let otp = generate_otp();
-            let expires_at = Utc::now() + Duration::minutes(10);
-
-            // 3. Store OTP in password_resets table
-            let query = r#"
-                INSERT INTO password_resets (user_id, otp_code, expires_at, used)
-                VALUES ($1, $2, $3, FALSE, Now())
-            "#;
-
-            if let Err(e) = sqlx::query(query)
-                .bind(user_id)
-                .bind(&otp)
-                .bind(expires_at)
-                .execute(pool.get_ref())
-                .await
-            {
-                eprintln!("DB error inserting password reset: {:?}", e);
-                return HttpResponse::InternalServerError().finish();
-            }
-
-            // 4. Sent OTP email
-            if let Err(e) = send_otp_email(&email, &otp) {
-                eprintln!("Error Sending OTP email: {:?}", e);
+        Some(record) => {
+            // Throttling and failures are only logged: a different response
+            // here would tell the caller the account exists
+            if let Err(e) = issue_reset_code(&pool, &req, &otp_config, record.id, &email).await {
+                tracing::error!(user_id = %record.id, "Failed to issue password reset code: {}", e);
             }
         }
 
-        Ok(None) => {
+        None => {
             // Do nothing if user does not exist
-        }
-
-        Err(e) => {
-            eprintln!("DB query error: {:?}", e);
-            return HttpResponse::InternalServerError().finish();
+            reset_event(&pool, &req, AuditEventType::PasswordResetRequested, None, Some("unknown_account")).await;
+            METRICS.password_reset_requests.with_label_values(&["unknown_account"]).inc();
         }
     }
 
     // 5. Always return a Neutral response
-    HttpResponse::Ok().json(serde_json::json!({
+    Ok(HttpResponse::Ok().json(serde_json::json!({
         "message": "If this email is registered, a reset code has been sent."
-    }))
+    })))
+}
+
+// Store a new code for the account and email it, unless it asked too often
+async fn issue_reset_code(
+    pool: &Pool<Postgres>,
+    req: &HttpRequest,
+    otp_config: &OtpConfig,
+    user_id: Uuid,
+    email: &str,
+) -> Result<(), sqlx::Error> {
+    // Rate limiting: at most `limit_per_hour` codes an hour, and a minimum
+    // interval between two of them
+    let recent = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "count!", MAX(created_at) AS last_sent_at
+        FROM password_resets
+        WHERE user_id = $1 AND created_at > NOW() - INTERVAL '1 hour'
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let too_soon = recent.last_sent_at.is_some_and(|last| !otp_config.can_resend(last));
+    if otp_config.exceeds_hourly_limit(recent.count) || too_soon {
+        METRICS.password_reset_requests.with_label_values(&["throttled"]).inc();
+        reset_event(pool, req, AuditEventType::PasswordResetRequested, Some(user_id), Some("rate_limited")).await;
+        return Ok(());
+    }
+
+    // 2. Generate OTP
+    let otp = generate_otp();
+    let expires_at = Utc::now() + Duration::minutes(otp_config.expiry_minutes);
+
+    // 3. Store OTP in password_resets table; only the newest code is valid
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "UPDATE password_resets SET used = TRUE WHERE user_id = $1 AND used = FALSE",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO password_resets (user_id, otp_code, expires_at) VALUES ($1, $2, $3)",
+        user_id,
+        otp,
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    reset_event(pool, req, AuditEventType::PasswordResetRequested, Some(user_id), None).await;
+    METRICS.password_reset_requests.with_label_values(&["sent"]).inc();
+
+    // 4. Send OTP email off the request path, so the response time does not
+    //    give the account away either
+    let email = email.to_string();
+    let expiry_minutes = otp_config.expiry_minutes;
+    spawn_email("password reset code", move || send_otp_email(&email, &otp, expiry_minutes));
+
+    Ok(())
 }
 
 // Password and OTP verification function
-#[post("/auth/reset/verify")]
+#[post("/reset/verify")]
 pub async fn reset_verify(
+    req: HttpRequest,
     pool: web::Data<Pool<Postgres>>,
+    hash_pool: web::Data<HashPool>,
+    policy: web::Data<PasswordPolicy>,
+    breach_checker: web::Data<BreachedPasswordChecker>,
     payload: web::Json<ResetVerifyPayload>,
-) -> Result<impl Responder, actix_web::Error> {
+) -> Result<HttpResponse, AppError> {
+    let email = payload.email.to_lowercase();
+
     // 1. confirm password check
     if payload.new_password != payload.confirm_password {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Passwords do not match"
-        })));
+        return Err(AppError::validation(ValidationError::new("confirm_password", "Passwords do not match")));
+    }
+
+    // 1b. new password must satisfy the password policy
+    policy.check(&payload.new_password, &[&email]).map_err(AppError::Validation)?;
+
+    // 1c. breached-password check (reject or warn, per config)
+    let mut password_warning = None;
+    match breach_checker.check(&payload.new_password).await {
+        BreachVerdict::Reject => {
+            return Err(AppError::validation(ValidationError::new("password", BREACHED_PASSWORD_MESSAGE)));
+        }
+        BreachVerdict::Warn => password_warning = Some(BREACHED_PASSWORD_MESSAGE),
+        BreachVerdict::Clean => {}
     }
 
-    // 2. check OTP validity
+    // 2. check OTP validity: the newest code issued to this email's account
     let otp_row = sqlx::query!(
-        "SELECT id, expires_at, used FROM password_resets WHERE user_id = $1 AND otp_code = $2",
-        payload.user_id,
+        r#"
+        SELECT pr.id, pr.user_id, pr.expires_at, pr.used
+        FROM password_resets pr
+        JOIN users u ON u.id = pr.user_id
+        WHERE u.email = $1 AND u.deleted_at IS NULL AND pr.otp_code = $2
+        ORDER BY pr.created_at DESC
+        LIMIT 1
+        "#,
+        email,
         payload.otp
     )
     .fetch_optional(pool.get_ref())
-    .await
-    .map_err(|e| ErrorInternalServerError(e))?;
-    
+    .await?;
 
     let otp = match otp_row {
         Some(row) => row,
         None => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid or expired OTP"
-            })));
+            reset_event(&pool, &req, AuditEventType::PasswordResetCompleted, None, Some("invalid_code")).await;
+            return Err(AppError::bad_request("invalid_code", "Invalid or expired OTP"));
         }
     };
+    let user_id = otp.user_id;
 
     if otp.used || otp.expires_at < chrono::Utc::now() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "OTP expired or already used"
-        })));
+        reset_event(&pool, &req, AuditEventType::PasswordResetCompleted, Some(user_id), Some("expired_code")).await;
+        return Err(AppError::bad_request("expired_code", "OTP expired or already used"));
     }
 
-    // 3. hash new password with helper
-    let hashed = hash_password(&payload.new_password)
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to hash password"))?;
+    // 2b. reject reuse of the current or a recent password
+    let recent_hashes = password_history::recent_hashes(pool.get_ref(), user_id, policy.history_size).await?;
+    if password_history::is_reused(&hash_pool, &payload.new_password, &recent_hashes).await? {
+        return Err(AppError::validation(ValidationError::new("password", PASSWORD_REUSED_MESSAGE)));
+    }
+
+    // 3. hash new password on the hashing pool (503 when saturated)
+    let hashed = hash_pool.hash(&payload.new_password).await?;
+
+    // 4. update users table (only the account the OTP was issued for);
+    //    also lifts the lock set when an email change was undone or an
+    //    admin required a new password
+    let mut tx = pool.begin().await?;
 
-    // 4. update users table
     let result = sqlx::query!(
-        "UPDATE users SET password_hash = $1 WHERE email = $2",
+        r#"
+        UPDATE users SET
+            password_hash = $1,
+            locked_at = CASE WHEN lock_reason IN ($3, $4) THEN NULL ELSE locked_at END,
+            lock_reason = CASE WHEN lock_reason IN ($3, $4) THEN NULL ELSE lock_reason END
+        WHERE id = $2
+        "#,
         hashed,
-        payload.email
+        user_id,
+        LOCK_EMAIL_CHANGE_REVERTED,
+        LOCK_PASSWORD_RESET_REQUIRED
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|_| actix_web::error::ErrorInternalServerError("DB update failed"))?;
-    
+    .execute(&mut *tx)
+    .await?;
+
     if result.rows_affected() == 0 {
-        return Ok(HttpResponse::NotFound().body("No user found"));
+        return Err(AppError::not_found("user_not_found", "No user found"));
     }
-    
+
+    // 4b. remember the new hash for reuse checks (prunes old entries)
+    password_history::record(&mut tx, user_id, &hashed, policy.history_size).await?;
 
     // 5. mark OTP as used
     sqlx::query!(
         "UPDATE password_resets SET used = TRUE WHERE id = $1",
         otp.id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| ErrorInternalServerError(e))?;
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    reset_event(&pool, &req, AuditEventType::PasswordResetCompleted, Some(user_id), None).await;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Password reset successful"
+        "message": "Password reset successful",
+        "warning": password_warning
     })))
 }
+
+// Audit a reset step; a failure reason makes it a failed one
+async fn reset_event(
+    pool: &Pool<Postgres>,
+    req: &HttpRequest,
+    event_type: AuditEventType,
+    user_id: Option<Uuid>,
+    failure: Option<&str>,
+) {
+    let outcome = if failure.is_some() { AuditOutcome::Failure } else { AuditOutcome::Success };
+    let event = AuditEvent {
+        target_user_id: user_id,
+        details: failure.map_or_else(|| serde_json::json!({}), |reason| serde_json::json!({ "reason": reason })),
+        ..AuditEvent::from_request(req, event_type, outcome)
+    };
+    audit::record(pool, event).await;
+}
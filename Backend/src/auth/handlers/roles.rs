@@ -0,0 +1,13 @@
+use actix_web::{get, web, HttpResponse};
+use sqlx::Pool;
+use sqlx::Postgres;
+
+use crate::error::AppError;
+use crate::services::rbac::list_roles;
+
+// Roles and the permissions each one grants (guarded by `roles:read`)
+#[get("")]
+pub async fn get_roles(pool: web::Data<Pool<Postgres>>) -> Result<HttpResponse, AppError> {
+    let roles = list_roles(pool.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
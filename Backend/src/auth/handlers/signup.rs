@@ -1,15 +1,26 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{post, web, HttpRequest, HttpResponse};
 use sqlx::Pool;
 use sqlx::Postgres;
 use uuid::Uuid;
 
 
-use crate::auth::jwt::create_jwt;
-use crate::auth::validation::validate_register_payload;
+use crate::auth::breached::{BreachVerdict, BreachedPasswordChecker, BREACHED_PASSWORD_MESSAGE};
+use crate::auth::password_policy::PasswordPolicy;
+use crate::auth::validation::{validate_register_payload, ValidationError};
+use crate::models::audit::{AuditEventType, AuditOutcome};
 use crate::models::signup::RegisterPayload;
 use crate::models::user::User;
 use crate::auth::cookies::set_access_token;
-use crate::utils::hash::{hash_password};
+use crate::config::security::SecurityConfig;
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::services::audit::{self, AuditEvent};
+use crate::services::email_changes::is_email_taken;
+use crate::services::invitations::{accept, find_pending};
+use crate::services::password_history;
+use crate::services::sessions::start_session;
+use crate::utils::hash_pool::HashPool;
+use crate::utils::token::hash_token;
 
 
 
@@ -17,70 +28,115 @@ use crate::utils::hash::{hash_password};
 // Register handler
 #[post("/register")]
 pub async fn register(
+    req: HttpRequest,
     pool: web::Data<Pool<Postgres>>,
+    hash_pool: web::Data<HashPool>,
+    policy: web::Data<PasswordPolicy>,
+    breach_checker: web::Data<BreachedPasswordChecker>,
+    security_config: web::Data<SecurityConfig>,
     payload: web::Json<RegisterPayload>,
-) -> impl Responder {
+) -> Result<HttpResponse, AppError> {
     // Comprehensive input validation
-    match validate_register_payload(&payload.username, &payload.email, &payload.password) {
-        Ok(_) => {},
-        Err(validation_errors) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Validation failed",
-                "details": validation_errors
-            }));
+    validate_register_payload(&payload.username, &payload.email, &payload.password, &policy)
+        .map_err(AppError::Validation)?;
+
+    // Breached-password check (reject or warn, per config)
+    let mut password_warning = None;
+    match breach_checker.check(&payload.password).await {
+        BreachVerdict::Reject => {
+            return Err(AppError::validation(ValidationError::new("password", BREACHED_PASSWORD_MESSAGE)));
         }
+        BreachVerdict::Warn => password_warning = Some(BREACHED_PASSWORD_MESSAGE),
+        BreachVerdict::Clean => {}
     }
 
-    // hash password
-    let password_hash = match hash_password(&payload.password) {
-        Ok(h) => h,
-        Err(e) => {
-            tracing::error!("hashing error: {}", e);
-            return HttpResponse::InternalServerError().finish();
-        }
+    // Addresses held for a pending email-change undo count as registered
+    if is_email_taken(pool.get_ref(), &payload.email).await? {
+        return Err(email_taken());
+    }
+
+    // An invitation must be live and addressed to this email
+    let invitation = match payload.invite_token.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(token) => match find_pending(pool.get_ref(), &hash_token(token)).await? {
+            Some(invitation) if invitation.email.eq_ignore_ascii_case(&payload.email) => Some(invitation),
+            _ => {
+                return Err(AppError::validation(ValidationError::new(
+                    "invite_token",
+                    "This invitation is invalid, has expired or was sent to another email address",
+                )));
+            }
+        },
     };
 
-    // insert user and return full record
+    // hash password
+    let password_hash = hash_pool.hash(&payload.password).await?;
+
+    let mut tx = pool.begin().await?;
+
+    // insert user and return full record; the invitation email proves the address
     let created = sqlx::query_as!(
         User,
         r#"
-        INSERT INTO users (id, username, email, password_hash, created_at)
-        VALUES ($1, $2, $3, $4, NOW())
+        INSERT INTO users (id, username, email, password_hash, email_verified, created_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
         RETURNING id, username, email, password_hash, created_at
         "#,
         Uuid::new_v4(),
         payload.username,
         payload.email,
-        password_hash
+        password_hash,
+        invitation.is_some()
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await;
 
     let user = match created {
         Ok(user) => user,
-        Err(e) => {
-            tracing::error!("register error: {}", e);
-            // Check if it's a unique constraint violation (duplicate email)
-            if e.to_string().contains("duplicate key value violates unique constraint") {
-                return HttpResponse::Conflict().json(serde_json::json!({
-                    "error": "Email already exists",
-                    "message": "An account with this email address already exists"
-                }));
-            }
-            return HttpResponse::InternalServerError().finish();
+        // Check if it's a unique constraint violation (duplicate email)
+        Err(e) if e.to_string().contains("duplicate key value violates unique constraint") => {
+            return Err(email_taken());
         }
+        Err(e) => return Err(e.into()),
     };
 
-    // Create JWT for the new user
-    let token = match create_jwt(&user.id.to_string()) {
-        Ok(t) => t,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
+    // Join the inviting organization before the session picks the active one
+    if let Some(invitation) = &invitation
+        && !accept(&mut tx, invitation, user.id).await?
+    {
+        return Err(AppError::validation(ValidationError::new(
+            "invite_token",
+            "This invitation is no longer valid",
+        )));
+    }
+
+    // Seed password history with the initial password
+    password_history::record(&mut tx, user.id, &user.password_hash, policy.history_size).await?;
+
+    tx.commit().await?;
+
+    let event = AuditEvent {
+        target_user_id: Some(user.id),
+        details: serde_json::json!({ "invited": invitation.is_some() }),
+        ..AuditEvent::from_request(&req, AuditEventType::Registration, AuditOutcome::Success)
     };
+    audit::record(pool.get_ref(), event).await;
+
+    // Open a session and create the JWT for the new user
+    let token = start_session(pool.get_ref(), user.id, &req, security_config.jwt_expiration_hours).await?;
+    METRICS.registrations.inc();
 
         // JWT-only authentication - no session cleanup needed
 
     // Send JWT as HTTP-only cookie
-    HttpResponse::Created()
+    Ok(HttpResponse::Created()
         .cookie(set_access_token(&token))
-        .json(serde_json::json!({"message": "User registered successfully"}))
+        .json(serde_json::json!({
+            "message": "User registered successfully",
+            "warning": password_warning
+        })))
+}
+
+fn email_taken() -> AppError {
+    AppError::conflict("email_taken", "An account with this email address already exists")
 }
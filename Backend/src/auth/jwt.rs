@@ -1,19 +1,31 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use std::env;
-use crate::models::claims::Claims;
+use crate::models::claims::{ActorClaim, Claims};
 
+/// Expiry of a new access token and the session backing it, from
+/// `SecurityConfig::jwt_expiration_hours`
+pub fn token_expiry(ttl_hours: u64) -> DateTime<Utc> {
+    Utc::now()
+        .checked_add_signed(Duration::hours(ttl_hours as i64))
+        .expect("valid timestamp")
+}
 
-pub fn create_jwt(user_id: &str) -> anyhow::Result<String> {
+pub fn create_jwt(
+    user_id: &str,
+    session_id: &str,
+    organization_id: Option<&str>,
+    impersonator_id: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> anyhow::Result<String> {
     let secret = env::var("JWT_SECRET")?;
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
-        .expect("valid timestamp")
-        .timestamp();
 
     let claims = Claims {
         sub: user_id.to_string(),
-        exp: expiration,
+        sid: session_id.to_string(),
+        org: organization_id.map(str::to_string),
+        act: impersonator_id.map(|id| ActorClaim { sub: id.to_string() }),
+        exp: expires_at.timestamp(),
     };
 
     let token = encode(
@@ -24,12 +36,27 @@ pub fn create_jwt(user_id: &str) -> anyhow::Result<String> {
     Ok(token)
 }
 
+/// Tokens signed with `JWT_PREVIOUS_SECRET` stay valid after a key rotation
+/// (see `authctl keys rotate`) until they expire
 pub fn validate_jwt(token: &str) -> anyhow::Result<Claims> {
     let secret = env::var("JWT_SECRET")?;
-    let token_data = decode::<Claims>(
+    let decoded = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret.as_ref()),
         &Validation::default(),
-    )?;
+    );
+
+    let token_data = match (decoded, env::var("JWT_PREVIOUS_SECRET")) {
+        (Err(e), Ok(previous))
+            if !previous.is_empty() && *e.kind() == jsonwebtoken::errors::ErrorKind::InvalidSignature =>
+        {
+            decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(previous.as_ref()),
+                &Validation::default(),
+            )?
+        }
+        (decoded, _) => decoded?,
+    };
     Ok(token_data.claims)
-}
\ No newline at end of file
+}
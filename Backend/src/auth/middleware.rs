@@ -1,12 +1,17 @@
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
-    Error, HttpMessage,
+    web, Error, HttpMessage, HttpRequest,
 };
 use futures_util::future::{ok, Ready, LocalBoxFuture};
 use std::rc::Rc;
 
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
 use crate::auth::jwt::validate_jwt;
+use crate::error::AppError;
+use crate::models::claims::Claims;
+use crate::services::sessions::is_session_active;
 
 pub struct AuthMiddleware;
 
@@ -49,34 +54,70 @@ where
         Box::pin(async move {
             let path = req.path().to_string();
 
-            // Allow public endpoints
-            if path.starts_with("/api/v1/auth/") || path == "/health" {
+            // Allow public endpoints (signed export and share links carry their own authorisation)
+            if path.starts_with("/api/v1/auth/")
+                || path.starts_with("/api/v1/exports/")
+                || path.starts_with("/api/v1/shared/")
+                || path == "/health"
+            {
                 return srv.call(req).await;
             }
 
-            // 1) Try Authorization: Bearer header
-            if let Some(auth_header) = req.headers().get("Authorization") {
-                if let Ok(token) = auth_header.to_str() {
-                    if token.starts_with("Bearer ") {
-                        let token = token.trim_start_matches("Bearer ").trim();
-                        if let Ok(claims) = validate_jwt(token) {
-                            req.extensions_mut().insert(claims);
-                            return srv.call(req).await;
+            // 1) Try Authorization: Bearer header, 2) fallback: HTTP-only cookie `access_token`
+            let candidates = [bearer_token(req.request()), cookie_token(req.request())];
+            for token in candidates.into_iter().flatten() {
+                if let Ok(claims) = validate_jwt(&token) {
+                    // The token's session must not have been revoked
+                    if session_is_active(&req, &claims).await {
+                        if let Some(act) = &claims.act {
+                            tracing::info!(user_id = %claims.sub, impersonator_id = %act.sub, path = %path, "Impersonated request");
                         }
+                        // The request span is opened by RequestIdMiddleware
+                        tracing::Span::current().record("user_id", tracing::field::display(&claims.sub));
+                        req.extensions_mut().insert(claims);
+                        return srv.call(req).await;
                     }
                 }
             }
 
-            // 2) Fallback: HTTP-only cookie `access_token`
-            if let Some(cookie) = req.cookie("access_token") {
-                let token = cookie.value();
-                if let Ok(claims) = validate_jwt(token) {
-                    req.extensions_mut().insert(claims);
-                    return srv.call(req).await;
-                }
-            }
-
-            Err(ErrorUnauthorized("Invalid or missing token"))
+            Err(AppError::invalid_token().into())
         })
     }
 }
+
+/// Token from an `Authorization: Bearer` header
+pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    header
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+}
+
+/// Token from the HTTP-only `access_token` cookie
+pub fn cookie_token(req: &HttpRequest) -> Option<String> {
+    req.cookie("access_token").map(|c| c.value().to_string())
+}
+
+// Fails closed: a malformed claim or a database error rejects the token
+async fn session_is_active(req: &ServiceRequest, claims: &Claims) -> bool {
+    let Some(pool) = req.app_data::<web::Data<Pool<Postgres>>>() else {
+        return false;
+    };
+    let (Ok(user_id), Ok(session_id)) = (Uuid::parse_str(&claims.sub), Uuid::parse_str(&claims.sid)) else {
+        return false;
+    };
+    let Ok(organization_id) = claims.org.as_deref().map(Uuid::parse_str).transpose() else {
+        return false;
+    };
+    let Ok(impersonator_id) = claims.act.as_ref().map(|act| Uuid::parse_str(&act.sub)).transpose() else {
+        return false;
+    };
+
+    match is_session_active(pool.get_ref(), session_id, user_id, organization_id, impersonator_id).await {
+        Ok(active) => active,
+        Err(e) => {
+            tracing::error!("Session lookup failed: {}", e);
+            false
+        }
+    }
+}
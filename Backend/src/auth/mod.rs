@@ -1,11 +1,29 @@
 pub mod jwt;
 pub mod middleware;
+pub mod extractor;
+pub mod reauth;
+pub mod permissions;
+pub mod policy;
 pub mod cookies;
 pub mod validation;
+pub mod password_policy;
+pub mod breached;
 pub mod handlers {
     pub mod login;
     pub mod signup;
     pub mod logout;
     pub mod reset;
+    pub mod password;
+    pub mod profile;
+    pub mod email_change;
+    pub mod account;
+    pub mod admin_users;
+    pub mod audit;
+    pub mod export;
+    pub mod grants;
+    pub mod impersonation;
+    pub mod invitations;
+    pub mod organizations;
+    pub mod roles;
 }
 
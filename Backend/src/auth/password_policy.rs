@@ -0,0 +1,223 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use zxcvbn::zxcvbn;
+
+use crate::auth::validation::ValidationError;
+use crate::config::security::SecurityConfig;
+
+static SPECIAL_CHARS_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[!@#$%^&*()_+\-=\[\]{};':\|,.<>/?]").expect("Invalid special chars regex")
+});
+
+// Personal values shorter than this are too generic to reject on
+const MIN_CONTEXT_TOKEN_LENGTH: usize = 3;
+
+/// Password rules built from `SecurityConfig` and shared with the handlers.
+/// Strength is scored with zxcvbn (0 = guessable in seconds, 4 = very strong).
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    pub min_score: u8,
+    // How many previous passwords may not be reused (0 disables the check)
+    pub history_size: usize,
+}
+
+impl PasswordPolicy {
+    pub fn from_config(config: &SecurityConfig) -> Self {
+        Self {
+            min_length: config.password_min_length,
+            max_length: config.password_max_length,
+            require_uppercase: config.password_require_uppercase,
+            require_lowercase: config.password_require_lowercase,
+            require_digit: config.password_require_digit,
+            require_special: config.password_require_special,
+            min_score: config.password_min_score.min(4),
+            history_size: config.password_history_size,
+        }
+    }
+
+    /// Check a password against the policy and report every rule it breaks.
+    /// `user_inputs` are personal values (username, email) that must not appear
+    /// in the password; they also lower its strength score.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Result<(), Vec<ValidationError>> {
+        if password.is_empty() {
+            return Err(vec![ValidationError::new("password", "Password is required")]);
+        }
+
+        // Too long to be worth scoring
+        let length = password.chars().count();
+        if length > self.max_length {
+            return Err(vec![ValidationError::new(
+                "password",
+                &format!("Password must be at most {} characters long", self.max_length),
+            )]);
+        }
+
+        let mut errors = Vec::new();
+
+        if length < self.min_length {
+            errors.push(ValidationError::new(
+                "password",
+                &format!("Password must be at least {} characters long", self.min_length),
+            ));
+        }
+
+        // Check for spaces in password
+        if password.contains(' ') {
+            errors.push(ValidationError::new("password", "Password cannot contain spaces"));
+        }
+
+        // Contextual checks: username and email local part
+        let lowered = password.to_lowercase();
+        let tokens = context_tokens(user_inputs);
+        if tokens.iter().any(|token| lowered.contains(token.as_str())) {
+            errors.push(ValidationError::new(
+                "password",
+                "Password must not contain your username or email address",
+            ));
+        }
+
+        // Optional composition rules
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            errors.push(ValidationError::new("password", "Password must contain at least one uppercase letter"));
+        }
+
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            errors.push(ValidationError::new("password", "Password must contain at least one lowercase letter"));
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            errors.push(ValidationError::new("password", "Password must contain at least one number"));
+        }
+
+        if self.require_special && !SPECIAL_CHARS_REGEX.is_match(password) {
+            errors.push(ValidationError::new("password", "Password must contain at least one special character"));
+        }
+
+        // Strength estimation
+        let token_refs: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        let entropy = zxcvbn(password, &token_refs);
+        if u8::from(entropy.score()) < self.min_score {
+            let feedback = entropy.feedback();
+            let message = feedback
+                .and_then(|f| f.warning())
+                .map(|w| w.to_string())
+                .unwrap_or_else(|| "Password is too weak".to_string());
+            let mut suggestions: Vec<String> = feedback
+                .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+                .unwrap_or_default();
+            if suggestions.is_empty() {
+                suggestions.push("Add another word or two. Uncommon words are better.".to_string());
+            }
+
+            errors.push(ValidationError::new("password", &message).with_suggestions(suggestions));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+// Lowercased personal values; for emails only the local part is used
+fn context_tokens(user_inputs: &[&str]) -> Vec<String> {
+    user_inputs
+        .iter()
+        .map(|input| input.split('@').next().unwrap_or_default().trim().to_lowercase())
+        .filter(|token| token.chars().count() >= MIN_CONTEXT_TOKEN_LENGTH)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Length and strength only, so each test trips the rule it is about
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 12,
+            max_length: 128,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_special: false,
+            min_score: 3,
+            history_size: 0,
+        }
+    }
+
+    fn messages(result: Result<(), Vec<ValidationError>>) -> Vec<String> {
+        result.err().unwrap_or_default().into_iter().map(|e| e.message).collect()
+    }
+
+    #[test]
+    fn accepts_a_strong_password() {
+        assert!(policy().check("Correct-Horse-Battery-9!", &["alice", "alice@example.com"]).is_ok());
+    }
+
+    #[test]
+    fn enforces_the_length_limits() {
+        assert_eq!(messages(policy().check("", &[])), ["Password is required"]);
+        assert!(
+            messages(policy().check("Vq7#kLz!2p", &[]))
+                .contains(&"Password must be at least 12 characters long".to_string())
+        );
+        // Characters, not bytes
+        assert!(policy().check("ÿþ€ŋœæ→ßðđŋħ", &[]).is_ok());
+        assert_eq!(
+            messages(policy().check(&"Vq7#kLz!2p".repeat(13), &[])),
+            ["Password must be at most 128 characters long"]
+        );
+    }
+
+    #[test]
+    fn enforces_the_minimum_score() {
+        let errors = policy().check("password1234", &[]).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(!errors[0].suggestions.is_empty());
+
+        let lenient = PasswordPolicy { min_score: 0, ..policy() };
+        assert!(lenient.check("password1234", &[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_the_users_own_name_and_email() {
+        let lenient = PasswordPolicy { min_score: 0, ..policy() };
+        let contains_inputs = "Password must not contain your username or email address".to_string();
+
+        assert!(messages(lenient.check("Xx-Bob-Walker-Xx", &["bob", "bob.walker@example.com"])).contains(&contains_inputs));
+        assert!(messages(lenient.check("AliceRocks2024!", &["alice", "a@example.com"])).contains(&contains_inputs));
+        // Only the local part of the email counts, and short values are ignored
+        assert!(lenient.check("Example-Domain-42", &["bo", "bo@example.com"]).is_ok());
+    }
+
+    #[test]
+    fn reports_every_broken_rule() {
+        let strict = PasswordPolicy {
+            require_uppercase: true,
+            require_digit: true,
+            require_special: true,
+            ..policy()
+        };
+        let messages = messages(strict.check("alice pass", &["alice"]));
+        for expected in [
+            "Password must be at least 12 characters long",
+            "Password cannot contain spaces",
+            "Password must not contain your username or email address",
+            "Password must contain at least one uppercase letter",
+            "Password must contain at least one number",
+            "Password must contain at least one special character",
+        ] {
+            assert!(messages.contains(&expected.to_string()), "missing {:?} in {:?}", expected, messages);
+        }
+        // Plus the strength warning
+        assert_eq!(messages.len(), 7);
+    }
+}
@@ -0,0 +1,109 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, Error, HttpMessage,
+};
+use futures_util::future::{ok, Ready, LocalBoxFuture};
+use std::rc::Rc;
+
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::claims::Claims;
+use crate::services::rbac::PermissionCache;
+
+/// Route guard: only callers whose roles grant `permission` get through,
+/// everyone else receives a 403 naming the missing permission.
+/// Impersonation sessions are always refused, whatever the user holds.
+/// Wrap a scope or resource that sits behind `AuthMiddleware`:
+///
+/// `web::scope("/api/v1/admin").wrap(require_permission("users:read"))`
+pub fn require_permission(permission: &'static str) -> RequirePermission {
+    RequirePermission { permission }
+}
+
+pub struct RequirePermission {
+    permission: &'static str,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.permission,
+        })
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = Rc::clone(&self.service);
+        let permission = self.permission;
+
+        Box::pin(async move {
+            // Claims are only present once AuthMiddleware has accepted the token
+            let (user_id, impersonated) = match req.extensions().get::<Claims>() {
+                Some(claims) => (Uuid::parse_str(&claims.sub).ok(), claims.act.is_some()),
+                None => (None, false),
+            };
+            let Some(user_id) = user_id else {
+                return Err(AppError::invalid_token().into());
+            };
+
+            if impersonated {
+                tracing::warn!(user_id = %user_id, permission, "Permission denied to an impersonation session");
+                return Err(AppError::impersonation_forbidden().into());
+            }
+
+            if has_permission(&req, user_id, permission).await {
+                return srv.call(req).await;
+            }
+
+            tracing::warn!(user_id = %user_id, permission, "Permission denied");
+            Err(AppError::MissingPermission(permission).into())
+        })
+    }
+}
+
+// Fails closed: a database error denies the request
+async fn has_permission(req: &ServiceRequest, user_id: Uuid, permission: &str) -> bool {
+    let (Some(pool), Some(cache)) = (
+        req.app_data::<web::Data<Pool<Postgres>>>(),
+        req.app_data::<web::Data<PermissionCache>>(),
+    ) else {
+        return false;
+    };
+
+    match cache.permissions(pool.get_ref(), user_id).await {
+        Ok(permissions) => permissions.contains(permission),
+        Err(e) => {
+            tracing::error!("Permission lookup failed: {}", e);
+            false
+        }
+    }
+}
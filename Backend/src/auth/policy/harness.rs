@@ -0,0 +1,159 @@
+//! Decision tests for the policy files, run with `Backend check-policies`.
+//!
+//! Each `*.json` file in the tests directory holds a list of cases:
+//!
+//! ```text
+//! [{
+//!     "name": "owner can edit",
+//!     "subject": { "id": "u1" },
+//!     "action": "document:edit",
+//!     "resource": { "owner_id": "u1" },
+//!     "expect": "allow",
+//!     "policy": "document-owner-edit"
+//! }]
+//! ```
+//!
+//! `context` is optional, and so is `policy`, which also checks which policy decided.
+//! Unknown fields are rejected, so a misspelt one cannot skip a check.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+use super::{AuthzRequest, PolicySet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expectation {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyTestCase {
+    pub name: String,
+    #[serde(default)]
+    pub subject: Value,
+    pub action: String,
+    #[serde(default)]
+    pub resource: Value,
+    #[serde(default)]
+    pub context: Value,
+    pub expect: Expectation,
+    pub policy: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct HarnessSummary {
+    pub passed: usize,
+    pub failures: Vec<String>,
+}
+
+/// Evaluate every case under `dir` against the policies
+pub fn run_test_dir(policies: &PolicySet, dir: &Path) -> anyhow::Result<HarnessSummary> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("cannot read policy tests {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut summary = HarnessSummary::default();
+    for file in files {
+        let cases: Vec<PolicyTestCase> = serde_json::from_str(&std::fs::read_to_string(&file)?)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+
+        for case in cases {
+            match check_case(policies, &case) {
+                Ok(()) => summary.passed += 1,
+                Err(problem) => summary
+                    .failures
+                    .push(format!("{}: {}: {}", file.display(), case.name, problem)),
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn check_case(policies: &PolicySet, case: &PolicyTestCase) -> Result<(), String> {
+    let decision = policies.evaluate(&AuthzRequest {
+        subject: case.subject.clone(),
+        action: case.action.clone(),
+        resource: case.resource.clone(),
+        context: case.context.clone(),
+    });
+
+    let expected_allow = case.expect == Expectation::Allow;
+    if decision.allowed != expected_allow {
+        return Err(format!(
+            "expected {}, got {} ({})",
+            if expected_allow { "allow" } else { "deny" },
+            if decision.allowed { "allow" } else { "deny" },
+            decision.reason
+        ));
+    }
+
+    if let Some(expected) = &case.policy
+        && decision.policy.as_ref() != Some(expected)
+    {
+        return Err(format!("expected policy \"{}\", got {}", expected, decision.reason));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn repository_policies_pass_their_tests() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("policies");
+        let policies = PolicySet::load_dir(&dir).unwrap();
+        let summary = run_test_dir(&policies, &dir.join("tests")).unwrap();
+
+        assert!(summary.failures.is_empty(), "{:#?}", summary.failures);
+        assert!(summary.passed > 0);
+    }
+
+    fn run(cases: &str) -> anyhow::Result<HarnessSummary> {
+        let dir = std::env::temp_dir().join(format!("policy-tests-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("p.policy"), "permit \"owner-edit\" action == \"doc:edit\" when resource.owner_id == subject.id;").unwrap();
+        fs::write(dir.join("cases.json"), cases).unwrap();
+
+        let policies = PolicySet::load_dir(&dir).unwrap();
+        let summary = run_test_dir(&policies, &dir);
+        fs::remove_dir_all(dir).unwrap();
+        summary
+    }
+
+    #[test]
+    fn reports_failing_cases() {
+        let summary = run(r#"[
+            { "name": "owner", "subject": { "id": "u1" }, "action": "doc:edit",
+              "resource": { "owner_id": "u1" }, "expect": "allow", "policy": "owner-edit" },
+            { "name": "stranger", "subject": { "id": "u2" }, "action": "doc:edit",
+              "resource": { "owner_id": "u1" }, "expect": "allow" },
+            { "name": "wrong policy", "subject": { "id": "u1" }, "action": "doc:edit",
+              "resource": { "owner_id": "u1" }, "expect": "allow", "policy": "other" }
+        ]"#)
+        .unwrap();
+
+        assert_eq!(summary.passed, 1);
+        assert_eq!(summary.failures.len(), 2);
+        assert!(summary.failures[0].ends_with("stranger: expected allow, got deny (no policy permits \"doc:edit\")"));
+        assert!(summary.failures[1].contains("wrong policy: expected policy \"other\""));
+    }
+
+    #[test]
+    fn rejects_unknown_case_fields() {
+        let error = run(r#"[{ "name": "typo", "action": "doc:edit", "expect": "deny", "polcy": "owner-edit" }]"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown field `polcy`"), "{}", error);
+    }
+}
@@ -0,0 +1,319 @@
+//! Attribute-based authorization policies.
+//!
+//! Policies live in `*.policy` files (see `parser` for the syntax), are loaded
+//! and validated once at startup, and decide whether a subject may perform an
+//! action on a resource. Evaluation follows Cedar: nothing is allowed unless a
+//! `permit` matches, and any matching `forbid` overrides every `permit`.
+
+pub mod harness;
+pub mod parser;
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::models::claims::Claims;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Permit,
+    Forbid,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Root {
+    Subject,
+    Resource,
+    Context,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Attr(Root, Vec<String>),
+    List(Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub id: String,
+    pub effect: Effect,
+    // None = applies to every action
+    pub actions: Option<Vec<String>>,
+    pub condition: Option<Expr>,
+    pub line: usize,
+}
+
+/// What is being authorized. Attributes are JSON objects; a missing
+/// attribute reads as `null`.
+#[derive(Debug, Clone)]
+pub struct AuthzRequest {
+    pub subject: Value,
+    pub action: String,
+    pub resource: Value,
+    pub context: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub allowed: bool,
+    // The deciding policy; None when nothing matched (default deny)
+    pub policy: Option<String>,
+    pub reason: String,
+}
+
+/// Subject attributes taken from a validated token: `id`, `session` and `org`
+/// (null for personal tokens). Handlers may add more, e.g. the caller's role.
+pub fn subject_from_claims(claims: &Claims) -> Value {
+    serde_json::json!({
+        "id": claims.sub,
+        "session": claims.sid,
+        "org": claims.org,
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct PolicySet {
+    policies: Vec<Policy>,
+}
+
+impl PolicySet {
+    /// Load every `*.policy` file in `dir`. All syntax errors and duplicate
+    /// policy ids are reported together, prefixed with their file and line.
+    pub fn load_dir(dir: &Path) -> anyhow::Result<Self> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .map_err(|e| anyhow::anyhow!("cannot read policy directory {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "policy"))
+            .collect();
+        files.sort();
+
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+
+        for file in &files {
+            let name = file.display();
+            let source = std::fs::read_to_string(file)?;
+            match parser::parse_policies(&source) {
+                Ok(parsed) => {
+                    for policy in parsed {
+                        if !seen.insert(policy.id.clone()) {
+                            errors.push(format!("{}:{}: duplicate policy id \"{}\"", name, policy.line, policy.id));
+                        }
+                        policies.push(policy);
+                    }
+                }
+                Err(e) => errors.push(format!("{}:{}", name, e)),
+            }
+        }
+
+        if files.is_empty() {
+            errors.push(format!("{}: no .policy files found", dir.display()));
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("invalid policies:\n  {}", errors.join("\n  "));
+        }
+
+        Ok(Self { policies })
+    }
+
+    pub fn len(&self) -> usize {
+        self.policies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    pub fn evaluate(&self, request: &AuthzRequest) -> Decision {
+        let matching = |effect: Effect| {
+            self.policies.iter().find(|policy| policy.effect == effect && policy.matches(request))
+        };
+
+        if let Some(policy) = matching(Effect::Forbid) {
+            return Decision {
+                allowed: false,
+                policy: Some(policy.id.clone()),
+                reason: format!("forbidden by policy \"{}\"", policy.id),
+            };
+        }
+
+        match matching(Effect::Permit) {
+            Some(policy) => Decision {
+                allowed: true,
+                policy: Some(policy.id.clone()),
+                reason: format!("permitted by policy \"{}\"", policy.id),
+            },
+            None => Decision {
+                allowed: false,
+                policy: None,
+                reason: format!("no policy permits \"{}\"", request.action),
+            },
+        }
+    }
+}
+
+impl Policy {
+    fn matches(&self, request: &AuthzRequest) -> bool {
+        let action_matches = self
+            .actions
+            .as_ref()
+            .is_none_or(|actions| actions.contains(&request.action));
+
+        action_matches
+            && self
+                .condition
+                .as_ref()
+                .is_none_or(|condition| eval(condition, request) == Value::Bool(true))
+    }
+}
+
+fn eval(expr: &Expr, request: &AuthzRequest) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Attr(root, path) => {
+            let mut value = match root {
+                Root::Subject => &request.subject,
+                Root::Resource => &request.resource,
+                Root::Context => &request.context,
+            };
+            for key in path {
+                value = value.get(key).unwrap_or(&Value::Null);
+            }
+            value.clone()
+        }
+        Expr::List(items) => Value::Array(items.iter().map(|item| eval(item, request)).collect()),
+        Expr::Not(inner) => Value::Bool(eval(inner, request) != Value::Bool(true)),
+        Expr::And(left, right) => Value::Bool(
+            eval(left, request) == Value::Bool(true) && eval(right, request) == Value::Bool(true),
+        ),
+        Expr::Or(left, right) => Value::Bool(
+            eval(left, request) == Value::Bool(true) || eval(right, request) == Value::Bool(true),
+        ),
+        Expr::Cmp(op, left, right) => {
+            let (left, right) = (eval(left, request), eval(right, request));
+            Value::Bool(match op {
+                CmpOp::Eq => values_equal(&left, &right),
+                CmpOp::Ne => !values_equal(&left, &right),
+                CmpOp::Lt => compare(&left, &right) == Some(Ordering::Less),
+                CmpOp::Le => matches!(compare(&left, &right), Some(Ordering::Less | Ordering::Equal)),
+                CmpOp::Gt => compare(&left, &right) == Some(Ordering::Greater),
+                CmpOp::Ge => matches!(compare(&left, &right), Some(Ordering::Greater | Ordering::Equal)),
+                CmpOp::In => right
+                    .as_array()
+                    .is_some_and(|items| items.iter().any(|item| values_equal(&left, item))),
+            })
+        }
+    }
+}
+
+// Numbers compare by value, so 1 == 1.0
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+// Ordering is defined for two numbers or two strings (e.g. RFC 3339 timestamps)
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(_), Value::Number(_)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn policy_dir(files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("policies-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+        dir
+    }
+
+    fn load_error(files: &[(&str, &str)]) -> String {
+        let dir = policy_dir(files);
+        let error = PolicySet::load_dir(&dir).expect_err("should not load").to_string();
+        fs::remove_dir_all(dir).unwrap();
+        error
+    }
+
+    #[test]
+    fn rejects_duplicate_policy_ids() {
+        let error = load_error(&[
+            ("a.policy", "permit \"p\";\npermit \"p\";"),
+            ("b.policy", "\n\nforbid \"p\";"),
+        ]);
+        assert!(error.contains("a.policy:2: duplicate policy id \"p\""), "{}", error);
+        assert!(error.contains("b.policy:3: duplicate policy id \"p\""), "{}", error);
+    }
+
+    #[test]
+    fn reports_errors_from_every_file() {
+        let error = load_error(&[
+            ("a.policy", "permit \"a\" when user.id == \"u1\";"),
+            ("b.policy", "permit \"b\""),
+            ("c.policy", "permit \"c\";"),
+        ]);
+        assert!(error.contains("a.policy:1:17: unknown name `user`"), "{}", error);
+        assert!(error.contains("b.policy:1:11: expected `;`"), "{}", error);
+        assert!(!error.contains("c.policy"), "{}", error);
+    }
+
+    #[test]
+    fn forbid_overrides_permit() {
+        let dir = policy_dir(&[(
+            "p.policy",
+            r#"
+            permit "members-read" action == "doc:read" when subject.org != null;
+            forbid "no-suspended" when subject.suspended == true;
+            "#,
+        )]);
+        let policies = PolicySet::load_dir(&dir).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        let decide = |subject: Value, action: &str| {
+            policies.evaluate(&AuthzRequest {
+                subject,
+                action: action.to_string(),
+                resource: Value::Null,
+                context: Value::Null,
+            })
+        };
+        let member = serde_json::json!({ "org": "o1" });
+        let suspended = serde_json::json!({ "org": "o1", "suspended": true });
+
+        assert_eq!(decide(member.clone(), "doc:read").policy.as_deref(), Some("members-read"));
+        assert!(decide(member.clone(), "doc:read").allowed);
+        assert!(!decide(member, "doc:edit").allowed);
+        assert!(!decide(serde_json::json!({}), "doc:read").allowed);
+        let decision = decide(suspended, "doc:read");
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy.as_deref(), Some("no-suspended"));
+    }
+}
@@ -0,0 +1,473 @@
+//! Parser for the policy language.
+//!
+//! ```text
+//! // Owners may edit their own documents
+//! permit "document-owner-edit"
+//!     action in ["document:read", "document:edit"]
+//!     when resource.owner_id == subject.id;
+//! ```
+//!
+//! A policy is `permit` or `forbid`, a unique quoted id, an optional action
+//! clause (`action == "..."` or `action in [...]`; omitted = every action) and
+//! an optional `when` condition, terminated by `;`. Conditions combine
+//! `==`, `!=`, `<`, `<=`, `>`, `>=`, `in`, `!`, `&&`, `||` and parentheses over
+//! string, number, boolean and `null` literals, lists, and attributes of
+//! `subject`, `resource` or `context` (missing attributes are `null`).
+
+use serde_json::Value;
+use std::fmt;
+
+use super::{CmpOp, Effect, Expr, Policy, Root};
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "`{}`", name),
+            Token::Str(s) => write!(f, "\"{}\"", s),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Symbol(s) => write!(f, "`{}`", s),
+        }
+    }
+}
+
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+// Longest symbols first so `==` is not read as `=`
+const SYMBOLS: [&str; 16] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", "[", "]", ",", ".", ";",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line, mut column) = (0, 1, 1);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let (start_line, start_column) = (line, column);
+        let error = |message: String| ParseError { line: start_line, column: start_column, message };
+
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            column += 1;
+            continue;
+        }
+        // Comments run to the end of the line
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let start = i;
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse() {
+                Ok(n) => Token::Number(n),
+                Err(_) => return Err(error(format!("invalid number `{}`", text))),
+            }
+        } else if c == '"' {
+            i += 1;
+            let mut value = String::new();
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => return Err(error("unterminated string".into())),
+                    Some('"') => break,
+                    Some('\\') => {
+                        match chars.get(i + 1) {
+                            Some('"') => value.push('"'),
+                            Some('\\') => value.push('\\'),
+                            _ => return Err(error("unsupported escape in string".into())),
+                        }
+                        i += 2;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            Token::Str(value)
+        } else if let Some(symbol) = SYMBOLS
+            .iter()
+            .find(|s| s.chars().enumerate().all(|(k, sc)| chars.get(i + k) == Some(&sc)))
+        {
+            i += symbol.len();
+            Token::Symbol(symbol)
+        } else {
+            return Err(error(format!("unexpected character `{}`", c)));
+        };
+
+        column += i - start;
+        tokens.push(Spanned { token, line: start_line, column: start_column });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    // Position reported for errors at the end of the input
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let (line, column) = self
+            .tokens
+            .get(self.pos)
+            .map(|t| (t.line, t.column))
+            .unwrap_or(self.end);
+        ParseError { line, column, message: message.into() }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {}, found {}", expected, token)),
+            None => self.error(format!("expected {}, found end of file", expected)),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|t| t.token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(name)) if name == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", symbol)))
+        }
+    }
+
+    fn expect_string(&mut self, what: &str) -> Result<String, ParseError> {
+        if let Some(Token::Str(s)) = self.peek().cloned() {
+            self.pos += 1;
+            Ok(s)
+        } else {
+            Err(self.unexpected(what))
+        }
+    }
+
+    fn policy(&mut self) -> Result<Policy, ParseError> {
+        let line = self.tokens[self.pos].line;
+        let effect = if self.eat_keyword("permit") {
+            Effect::Permit
+        } else if self.eat_keyword("forbid") {
+            Effect::Forbid
+        } else {
+            return Err(self.unexpected("`permit` or `forbid`"));
+        };
+
+        let id = self.expect_string("a quoted policy id")?;
+
+        let actions = if self.eat_keyword("action") {
+            let actions = if self.eat_symbol("==") {
+                vec![self.action_name()?]
+            } else if self.eat_keyword("in") {
+                self.expect_symbol("[")?;
+                let mut actions = vec![self.action_name()?];
+                while self.eat_symbol(",") {
+                    actions.push(self.action_name()?);
+                }
+                self.expect_symbol("]")?;
+                actions
+            } else {
+                return Err(self.unexpected("`==` or `in` after `action`"));
+            };
+            Some(actions)
+        } else {
+            None
+        };
+
+        let condition = if self.eat_keyword("when") {
+            Some(self.or()?)
+        } else {
+            None
+        };
+
+        self.expect_symbol(";")?;
+
+        Ok(Policy { id, effect, actions, condition, line })
+    }
+
+    // Actions are named `<resource>:<verb>`, e.g. "document:edit"
+    fn action_name(&mut self) -> Result<String, ParseError> {
+        let action = self.expect_string("a quoted action name")?;
+        let valid = action.split_once(':').is_some_and(|(resource, verb)| {
+            let part = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+            part(resource) && part(verb)
+        });
+        if !valid {
+            self.pos -= 1;
+            return Err(self.error(format!(
+                "invalid action \"{}\", expected \"<resource>:<verb>\"",
+                action
+            )));
+        }
+        Ok(action)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.and()?;
+        while self.eat_symbol("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while self.eat_symbol("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat_symbol("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.operand()?;
+
+        let op = match self.peek() {
+            Some(Token::Symbol("==")) => CmpOp::Eq,
+            Some(Token::Symbol("!=")) => CmpOp::Ne,
+            Some(Token::Symbol("<")) => CmpOp::Lt,
+            Some(Token::Symbol("<=")) => CmpOp::Le,
+            Some(Token::Symbol(">")) => CmpOp::Gt,
+            Some(Token::Symbol(">=")) => CmpOp::Ge,
+            Some(Token::Ident(name)) if name == "in" => CmpOp::In,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+
+        Ok(Expr::Cmp(op, Box::new(left), Box::new(self.operand()?)))
+    }
+
+    fn operand(&mut self) -> Result<Expr, ParseError> {
+        if self.eat_symbol("(") {
+            let expr = self.or()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+
+        if self.eat_symbol("[") {
+            let mut items = Vec::new();
+            if !self.eat_symbol("]") {
+                items.push(self.operand()?);
+                while self.eat_symbol(",") {
+                    items.push(self.operand()?);
+                }
+                self.expect_symbol("]")?;
+            }
+            return Ok(Expr::List(items));
+        }
+
+        match self.peek().cloned() {
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Value::String(s)))
+            }
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(serde_json::json!(n)))
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                match name.as_str() {
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    "subject" => self.attribute(Root::Subject),
+                    "resource" => self.attribute(Root::Resource),
+                    "context" => self.attribute(Root::Context),
+                    _ => {
+                        self.pos -= 1;
+                        Err(self.error(format!(
+                            "unknown name `{}`; attributes start with `subject`, `resource` or `context`",
+                            name
+                        )))
+                    }
+                }
+            }
+            _ => Err(self.unexpected("a value or attribute")),
+        }
+    }
+
+    fn attribute(&mut self, root: Root) -> Result<Expr, ParseError> {
+        let mut path = Vec::new();
+        while self.eat_symbol(".") {
+            match self.next() {
+                Some(Token::Ident(name)) => path.push(name),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected("an attribute name"));
+                }
+            }
+        }
+        if path.is_empty() {
+            return Err(self.unexpected("`.` and an attribute name"));
+        }
+        Ok(Expr::Attr(root, path))
+    }
+}
+
+/// Parse every policy in a file
+pub fn parse_policies(source: &str) -> Result<Vec<Policy>, ParseError> {
+    let tokens = tokenize(source)?;
+    let end = source
+        .lines()
+        .enumerate()
+        .last()
+        .map(|(n, line)| (n + 1, line.chars().count() + 1))
+        .unwrap_or((1, 1));
+
+    let mut parser = Parser { tokens, pos: 0, end };
+    let mut policies = Vec::new();
+    while parser.peek().is_some() {
+        policies.push(parser.policy()?);
+    }
+    Ok(policies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        parse_policies(source).expect_err("should not parse").to_string()
+    }
+
+    #[test]
+    fn parses_effects_actions_and_conditions() {
+        let policies = parse_policies(
+            r#"
+            // Comments are ignored
+            permit "owner-edit"
+                action in ["document:read", "document:edit"]
+                when resource.owner_id == subject.id && !context.locked;
+            forbid "everything";
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies[0].id, "owner-edit");
+        assert_eq!(policies[0].effect, Effect::Permit);
+        assert_eq!(policies[0].line, 3);
+        assert_eq!(
+            policies[0].actions,
+            Some(vec!["document:read".to_string(), "document:edit".to_string()])
+        );
+        assert!(matches!(policies[0].condition, Some(Expr::And(..))));
+        assert_eq!(policies[1].effect, Effect::Forbid);
+        assert!(policies[1].actions.is_none() && policies[1].condition.is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_policies() {
+        let cases = [
+            ("allow \"p\";", "1:1: expected `permit` or `forbid`, found `allow`"),
+            ("permit p;", "1:8: expected a quoted policy id, found `p`"),
+            ("permit \"p\"", "1:11: expected `;`, found end of file"),
+            ("permit \"p\" action = \"a:b\";", "1:19: unexpected character `=`"),
+            ("permit \"p\" action == \"edit\";", "1:22: invalid action \"edit\", expected \"<resource>:<verb>\""),
+            ("permit \"p\" action in [];", "1:23: expected a quoted action name, found `]`"),
+            ("permit \"p\" when (subject.id == \"u1\";", "1:36: expected `)`, found `;`"),
+            ("permit \"p\" when subject.id == ;", "1:31: expected a value or attribute, found `;`"),
+            ("permit \"p\" when subject.id == \"u1;", "1:31: unterminated string"),
+            ("permit \"p\" when subject.id == \"\\n\";", "1:31: unsupported escape in string"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(error(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_attributes() {
+        assert_eq!(
+            error("permit \"p\"\n    when user.id == resource.owner_id;"),
+            "2:10: unknown name `user`; attributes start with `subject`, `resource` or `context`"
+        );
+        assert_eq!(
+            error("permit \"p\" when subject == \"u1\";"),
+            "1:25: expected `.` and an attribute name, found `==`"
+        );
+        assert_eq!(
+            error("permit \"p\" when subject.\"id\" == \"u1\";"),
+            "1:25: expected an attribute name, found \"id\""
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::validation::ValidationError;
+use crate::config::login::LoginLimitConfig;
+use crate::database::rls::begin_scope;
+use crate::error::AppError;
+use crate::utils::hash_pool::HashPool;
+
+/// Re-authenticate a logged-in user before a sensitive change.
+/// Wrong passwords count towards, and are blocked by, the login lockout.
+/// Its queries run in the user's scope; none is held open while hashing.
+pub async fn verify_current_password(
+    pool: &Pool<Postgres>,
+    hash_pool: &HashPool,
+    login_config: &LoginLimitConfig,
+    user_id: Uuid,
+    password_hash: &str,
+    current_password: &str,
+) -> Result<(), AppError> {
+    let mut tx = begin_scope(pool, user_id, None).await?;
+    let recent_attempts = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM failed_logins
+        WHERE user_id = $1
+        AND attempt_time > NOW() - ($2::int * interval '1 second')
+        "#,
+        user_id,
+        login_config.lockout_secs as i32
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .count;
+    drop(tx);
+
+    if recent_attempts >= login_config.max_attempts as i64 {
+        return Err(AppError::too_many_requests(
+            "too_many_attempts",
+            "Too many failed attempts. Please try again later.",
+        ));
+    }
+
+    if hash_pool.verify(current_password, password_hash).await? {
+        return Ok(());
+    }
+
+    if let Err(e) = record_failed_attempt(pool, user_id).await {
+        tracing::error!("failed login not recorded: {}", e);
+    }
+
+    Err(AppError::validation(ValidationError::new(
+        "current_password",
+        "Current password is incorrect",
+    )))
+}
+
+async fn record_failed_attempt(pool: &Pool<Postgres>, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = begin_scope(pool, user_id, None).await?;
+    sqlx::query!("INSERT INTO failed_logins (user_id) VALUES ($1)", user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Sensitive changes (password, email address, account deletion, second
+/// factors) and taking data out of the account (exports, shares) must be
+/// done by the user themselves, never while impersonated.
+pub fn reject_impersonation(auth: &AuthenticatedUser) -> Result<(), AppError> {
+    if auth.is_impersonated() {
+        return Err(AppError::impersonation_forbidden());
+    }
+
+    Ok(())
+}
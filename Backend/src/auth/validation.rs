@@ -1,13 +1,34 @@
 use validator::validate_email as is_valid_email;
+use validator::validate_url as is_valid_url;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tracing::{warn, info};
 
+use crate::auth::password_policy::PasswordPolicy;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
+    // Actionable hints for the user, e.g. from password strength estimation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ValidationError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
 }
 
 // Precompiled regexes for better performance
@@ -15,148 +36,67 @@ static USERNAME_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[a-zA-Z0-9_]+$").expect("Invalid username regex")
 });
 
-static SPECIAL_CHARS_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"[!@#$%^&*()_+\-=\[\]{};':\|,.<>/?]").expect("Invalid special chars regex")
-});
-
-// Common weak passwords to check against
-static COMMON_PASSWORDS: Lazy<Vec<&str>> = Lazy::new(|| {
-    vec![
-        "12345678", "password", "123456789", "1234567890", "qwerty123",
-        "password123", "admin123", "letmein", "welcome123", "monkey123",
-        "dragon123", "master123", "hello123", "login123", "princess123",
-        "qwertyuiop", "123456789a", "password1", "1234567890a", "admin1234"
-    ]
+static SLUG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-z0-9]+(-[a-z0-9]+)*$").expect("Invalid slug regex")
 });
 
 pub fn validate_email(email: &str) -> Result<(), ValidationError> {
     if email.is_empty() {
-        return Err(ValidationError {
-            field: "email".to_string(),
-            message: "Email is required".to_string(),
-        });
+        return Err(ValidationError::new("email", "Email is required"));
     }
 
     if email.len() > 254 {
-        return Err(ValidationError {
-            field: "email".to_string(),
-            message: "Email is too long".to_string(),
-        });
+        return Err(ValidationError::new("email", "Email is too long"));
     }
 
     // Use validator crate for email validation
     if is_valid_email(email) {
         Ok(())
     } else {
-        warn!("Invalid email format attempted: {}", email);
-        Err(ValidationError {
-            field: "email".to_string(),
-            message: "Invalid email format".to_string(),
-        })
+        warn!("Invalid email format attempted");
+        Err(ValidationError::new("email", "Invalid email format"))
     }
 }
 
-pub fn validate_password(password: &str) -> Result<(), ValidationError> {
-    if password.is_empty() {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password is required".to_string(),
-        });
-    }
-
-    if password.len() < 8 {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password must be at least 8 characters long".to_string(),
-        });
-    }
-
-    if password.len() > 128 {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password is too long".to_string(),
-        });
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    if username.is_empty() {
+        return Err(ValidationError::new("username", "Username is required"));
     }
 
-    // Check for spaces in password
-    if password.contains(' ') {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password cannot contain spaces".to_string(),
-        });
+    if username.len() < 3 {
+        return Err(ValidationError::new("username", "Username must be at least 3 characters long"));
     }
 
-    // Check against common weak passwords
-    if COMMON_PASSWORDS.contains(&password.to_lowercase().as_str()) {
-        warn!("Common password attempted: {}", password);
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password is too common, please choose a stronger password".to_string(),
-        });
+    if username.len() > 50 {
+        return Err(ValidationError::new("username", "Username is too long"));
     }
 
-    // Check for at least one uppercase letter
-    if !password.chars().any(|c| c.is_uppercase()) {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password must contain at least one uppercase letter".to_string(),
-        });
+    if !USERNAME_REGEX.is_match(username) {
+        return Err(ValidationError::new("username", "Username can only contain letters, numbers, and underscores"));
     }
 
-    // Check for at least one lowercase letter
-    if !password.chars().any(|c| c.is_lowercase()) {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password must contain at least one lowercase letter".to_string(),
-        });
-    }
+    Ok(())
+}
 
-    // Check for at least one digit
-    if !password.chars().any(|c| c.is_ascii_digit()) {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password must contain at least one number".to_string(),
-        });
+pub fn validate_display_name(display_name: &str) -> Result<(), ValidationError> {
+    if display_name.chars().count() > 100 {
+        return Err(ValidationError::new("display_name", "Display name is too long"));
     }
 
-    // Check for at least one special character
-    if !SPECIAL_CHARS_REGEX.is_match(password) {
-        return Err(ValidationError {
-            field: "password".to_string(),
-            message: "Password must contain at least one special character".to_string(),
-        });
+    if display_name.chars().any(|c| c.is_control()) {
+        return Err(ValidationError::new("display_name", "Display name contains invalid characters"));
     }
 
     Ok(())
 }
 
-pub fn validate_username(username: &str) -> Result<(), ValidationError> {
-    if username.is_empty() {
-        return Err(ValidationError {
-            field: "username".to_string(),
-            message: "Username is required".to_string(),
-        });
+pub fn validate_avatar_url(avatar_url: &str) -> Result<(), ValidationError> {
+    if avatar_url.len() > 2048 {
+        return Err(ValidationError::new("avatar_url", "Avatar URL is too long"));
     }
 
-    if username.len() < 3 {
-        return Err(ValidationError {
-            field: "username".to_string(),
-            message: "Username must be at least 3 characters long".to_string(),
-        });
-    }
-
-    if username.len() > 50 {
-        return Err(ValidationError {
-            field: "username".to_string(),
-            message: "Username is too long".to_string(),
-        });
-    }
-
-    if !USERNAME_REGEX.is_match(username) {
-        return Err(ValidationError {
-            field: "username".to_string(),
-            message: "Username can only contain letters, numbers, and underscores".to_string(),
-        });
+    if !avatar_url.starts_with("https://") || !is_valid_url(avatar_url) {
+        return Err(ValidationError::new("avatar_url", "Avatar URL must be a valid https:// URL"));
     }
 
     Ok(())
@@ -170,6 +110,7 @@ pub fn validate_register_payload(
     username: &str,
     email: &str,
     password: &str,
+    policy: &PasswordPolicy,
 ) -> Result<(), Vec<ValidationError>> {
     let mut errors = Vec::new();
 
@@ -186,16 +127,15 @@ pub fn validate_register_payload(
         errors.push(e);
     }
 
-    if let Err(e) = validate_password(&password) {
-        errors.push(e);
+    if let Err(e) = policy.check(&password, &[&username, &email]) {
+        errors.extend(e);
     }
 
     // Log validation failures for analytics
     if !errors.is_empty() {
-        warn!("Registration validation failed for email: {}, errors: {}", 
-                email, errors.len());
+        warn!("Registration validation failed, errors: {}", errors.len());
     } else {
-        info!("Registration validation passed for email: {}", email);
+        info!("Registration validation passed");
     }
 
     if errors.is_empty() {
@@ -218,17 +158,47 @@ pub fn validate_login_payload(email: &str, password: &str) -> Result<(), Vec<Val
     }
 
     if password.is_empty() {
-        errors.push(ValidationError {
-            field: "password".to_string(),
-            message: "Password is required".to_string(),
-        });
+        errors.push(ValidationError::new("password", "Password is required"));
     }
 
     // Log validation failures (but don't expose specific details)
     if !errors.is_empty() {
-        warn!("Login validation failed for email: {}", email);
+        warn!("Login validation failed");
     } else {
-        info!("Login validation passed for email: {}", email);
+        info!("Login validation passed");
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Validate a profile update; only provided fields are checked, "" clears a display field
+pub fn validate_profile_update_payload(
+    username: Option<&str>,
+    display_name: Option<&str>,
+    avatar_url: Option<&str>,
+) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    if let Some(username) = username
+        && let Err(e) = validate_username(&sanitize_input(username))
+    {
+        errors.push(e);
+    }
+
+    if let Some(display_name) = display_name
+        && let Err(e) = validate_display_name(&sanitize_input(display_name))
+    {
+        errors.push(e);
+    }
+
+    if let Some(avatar_url) = avatar_url.map(sanitize_input).filter(|url| !url.is_empty())
+        && let Err(e) = validate_avatar_url(&avatar_url)
+    {
+        errors.push(e);
     }
 
     if errors.is_empty() {
@@ -244,11 +214,11 @@ pub fn validate_password_reset_payload(email: &str) -> Result<(), ValidationErro
     
     match validate_email(&email) {
         Ok(_) => {
-            info!("Password reset validation passed for email: {}", email);
+            info!("Password reset validation passed");
             Ok(())
         },
         Err(e) => {
-            warn!("Password reset validation failed for email: {}", email);
+            warn!("Password reset validation failed");
             Err(e)
         }
     }
@@ -258,6 +228,8 @@ pub fn validate_password_reset_payload(email: &str) -> Result<(), ValidationErro
 pub fn validate_password_change_payload(
     current_password: &str,
     new_password: &str,
+    policy: &PasswordPolicy,
+    user_inputs: &[&str],
 ) -> Result<(), Vec<ValidationError>> {
     let mut errors = Vec::new();
 
@@ -265,22 +237,16 @@ pub fn validate_password_change_payload(
     let new_password = sanitize_input(new_password);
 
     if current_password.is_empty() {
-        errors.push(ValidationError {
-            field: "current_password".to_string(),
-            message: "Current password is required".to_string(),
-        });
+        errors.push(ValidationError::new("current_password", "Current password is required"));
     }
 
-    if let Err(e) = validate_password(&new_password) {
-        errors.push(e);
+    if let Err(e) = policy.check(&new_password, user_inputs) {
+        errors.extend(e);
     }
 
     // Check if new password is different from current
     if current_password == new_password {
-        errors.push(ValidationError {
-            field: "new_password".to_string(),
-            message: "New password must be different from current password".to_string(),
-        });
+        errors.push(ValidationError::new("new_password", "New password must be different from current password"));
     }
 
     if !errors.is_empty() {
@@ -294,4 +260,57 @@ pub fn validate_password_change_payload(
     } else {
         Err(errors)
     }
-}
\ No newline at end of file
+}
+
+// Validate an email change request (re-authentication is checked by the handler)
+pub fn validate_email_change_payload(
+    current_email: &str,
+    new_email: &str,
+    current_password: &str,
+) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let new_email = sanitize_input(new_email);
+    if let Err(e) = validate_email(&new_email) {
+        errors.push(ValidationError::new("new_email", &e.message));
+    } else if new_email.eq_ignore_ascii_case(current_email) {
+        errors.push(ValidationError::new("new_email", "New email must be different from your current email"));
+    }
+
+    if current_password.is_empty() {
+        errors.push(ValidationError::new("current_password", "Current password is required"));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn validate_organization_name(name: &str) -> Result<(), ValidationError> {
+    if name.is_empty() {
+        return Err(ValidationError::new("name", "Organization name is required"));
+    }
+
+    if name.chars().count() > 100 {
+        return Err(ValidationError::new("name", "Organization name must be at most 100 characters long"));
+    }
+
+    Ok(())
+}
+
+pub fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    if slug.len() < 3 || slug.len() > 50 {
+        return Err(ValidationError::new("slug", "Slug must be between 3 and 50 characters long"));
+    }
+
+    if !SLUG_REGEX.is_match(slug) {
+        return Err(ValidationError::new(
+            "slug",
+            "Slug can only contain lowercase letters, numbers and single hyphens",
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,512 @@
+//! `authctl`: operator CLI for the tasks that used to need hand-written SQL.
+//! Reads the same environment (and `.env`) as the server.
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use backend::auth::password_policy::PasswordPolicy;
+use backend::auth::validation::{validate_register_payload, ValidationError};
+use backend::config::hashing::HashingConfig;
+use backend::config::login::LoginLimitConfig;
+use backend::config::security::SecurityConfig;
+use backend::database::db::establish_connection;
+use backend::models::admin::{AdminUserSummary, UserSearchParams};
+use backend::services::admin_users::{self, search_users, user_detail, Actor};
+use backend::services::audit::verify_chain;
+use backend::services::maintenance::purge_expired;
+use backend::utils::hash_pool::HashPool;
+use backend::utils::token::generate_token;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// Changes made here are audited without an acting account
+const CLI_ACTOR: Actor = Actor { user_id: None, ip_address: None };
+
+#[derive(Parser)]
+#[command(name = "authctl", about = "Operational tasks for the auth service")]
+struct Cli {
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage user accounts
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manage sign-in sessions
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
+    /// Manage signing keys
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Apply or list database migrations (they are forward-only)
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Check the security audit log
+    #[command(subcommand)]
+    Audit(AuditCommand),
+    /// Remove expired reset codes, stale failed logins, dead invitations,
+    /// expired exports and accounts past their deletion grace period
+    PurgeExpired,
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Create an account (no verification email is sent)
+    Create {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        username: String,
+        /// Mark the email address as verified
+        #[arg(long)]
+        verified: bool,
+        /// Read the password from stdin instead of prompting
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Search accounts
+    List(ListArgs),
+    /// Show an account with its sessions, failed logins and admin history
+    Show { user: String },
+    /// Lock an account and sign it out everywhere
+    Lock {
+        user: String,
+        /// Kept in the audit trail
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Unlock an account
+    Unlock { user: String },
+    /// Soft-delete an account: it can no longer sign in, but nothing is
+    /// removed and it can be restored through the admin API
+    SoftDelete { user: String },
+    /// Set a new password and sign the account out everywhere
+    SetPassword {
+        user: String,
+        /// Read the password from stdin instead of prompting
+        #[arg(long)]
+        password_stdin: bool,
+    },
+}
+
+#[derive(Args)]
+struct ListArgs {
+    /// Part of the email address
+    #[arg(long)]
+    email: Option<String>,
+    /// Part of the username
+    #[arg(long)]
+    username: Option<String>,
+    #[arg(long)]
+    verified: Option<bool>,
+    #[arg(long)]
+    locked: Option<bool>,
+    /// List soft-deleted accounts instead
+    #[arg(long)]
+    deleted: bool,
+    #[arg(long, default_value_t = 1)]
+    page: i64,
+    #[arg(long, default_value_t = 50)]
+    per_page: i64,
+}
+
+#[derive(Subcommand)]
+enum SessionsCommand {
+    /// Revoke every session of a user
+    Revoke {
+        #[arg(long)]
+        user: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Generate a new JWT signing key and explain how to roll it out
+    Rotate,
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Recompute the hash chain and report the first tampered event
+    Verify,
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// List migrations and whether they are applied
+    Status,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let json = cli.json;
+
+    match cli.command {
+        Command::User(command) => run_user(&establish_connection().await, command, json).await,
+        Command::Sessions(SessionsCommand::Revoke { user }) => {
+            let pool = establish_connection().await;
+            let user_id = resolve_user(&pool, &user).await?;
+            let revoked = admin_users::revoke_sessions(&pool, &CLI_ACTOR, user_id).await?;
+            report(json, json!({ "user_id": user_id, "revoked": revoked }), &format!("Revoked {} sessions", revoked));
+            Ok(())
+        }
+        // Generating a key needs no database
+        Command::Keys(KeysCommand::Rotate) => rotate_keys(json),
+        Command::Migrate(command) => run_migrate(&establish_connection().await, command, json).await,
+        Command::Audit(AuditCommand::Verify) => {
+            let report = verify_chain(&establish_connection().await).await?;
+            if json {
+                print_json(&report)?;
+            }
+            if let Some(broken) = report.broken {
+                bail!("audit event {} {} ({} events verified before it)", broken.id, broken.reason, report.checked);
+            }
+            if !json {
+                println!("{} audit events verified", report.checked);
+                if let Some(head) = report.head {
+                    println!("head: {}", head);
+                }
+            }
+            Ok(())
+        }
+        Command::PurgeExpired => {
+            let pool = establish_connection().await;
+            let summary = purge_expired(&pool, LoginLimitConfig::from_env().lockout_secs).await?;
+            if json {
+                return print_json(&summary);
+            }
+            print_table(
+                &["REMOVED", "COUNT"],
+                vec![
+                    vec!["password resets".into(), summary.password_resets.to_string()],
+                    vec!["failed logins".into(), summary.failed_logins.to_string()],
+                    vec!["invitations".into(), summary.invitations.to_string()],
+                    vec!["data exports".into(), summary.data_exports.to_string()],
+                    vec!["deleted accounts".into(), summary.deleted_accounts.to_string()],
+                ],
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn run_user(pool: &Pool<Postgres>, command: UserCommand, json: bool) -> anyhow::Result<()> {
+    let actor = &CLI_ACTOR;
+
+    match command {
+        UserCommand::Create { email, username, verified, password_stdin } => {
+            let policy = PasswordPolicy::from_config(&SecurityConfig::from_env());
+            let password = read_password(password_stdin)?;
+            validate_register_payload(&username, &email, &password, &policy).map_err(validation_failed)?;
+
+            let hashed = hash(&password).await?;
+            let user_id = admin_users::create_user(
+                pool,
+                actor,
+                username.trim(),
+                email.trim(),
+                &hashed,
+                verified,
+                policy.history_size,
+            )
+            .await?;
+            report(json, json!({ "id": user_id }), &format!("Created user {}", user_id));
+        }
+        UserCommand::List(args) => {
+            let params = UserSearchParams {
+                email: args.email,
+                username: args.username,
+                created_after: None,
+                created_before: None,
+                verified: args.verified,
+                locked: args.locked,
+                deleted: args.deleted,
+                page: Some(args.page),
+                per_page: Some(args.per_page),
+            };
+            let page = search_users(pool, &params).await?;
+            if json {
+                print_json(&page)?;
+            } else {
+                print_table(
+                    &["ID", "EMAIL", "USERNAME", "VERIFIED", "LOCKED", "CREATED"],
+                    page.users.iter().map(user_row).collect(),
+                );
+                println!("page {}, {} users in total", page.page, page.total);
+            }
+        }
+        UserCommand::Show { user } => {
+            let user_id = resolve_user(pool, &user).await?;
+            let detail = user_detail(pool, user_id).await?.ok_or_else(|| anyhow!("User not found"))?;
+            if json {
+                print_json(&detail)?;
+            } else {
+                print_table(
+                    &["ID", "EMAIL", "USERNAME", "VERIFIED", "LOCKED", "CREATED"],
+                    vec![user_row(&detail.user)],
+                );
+                println!("roles: {}", detail.roles.join(", "));
+                if let Some(deleted_at) = detail.user.deleted_at {
+                    println!("deleted: {}", timestamp(deleted_at));
+                }
+                println!("failed logins: {}", detail.failed_logins.len());
+                println!();
+                print_table(
+                    &["SESSION", "CREATED", "EXPIRES", "REVOKED", "IP", "IMPERSONATED BY"],
+                    detail
+                        .sessions
+                        .iter()
+                        .map(|s| {
+                            vec![
+                                s.id.to_string(),
+                                timestamp(s.created_at),
+                                timestamp(s.expires_at),
+                                s.revoked_at.map(timestamp).unwrap_or_default(),
+                                s.ip_address.clone().unwrap_or_default(),
+                                s.impersonator_id.map(|id| id.to_string()).unwrap_or_default(),
+                            ]
+                        })
+                        .collect(),
+                );
+                println!();
+                print_table(
+                    &["ACTION", "BY", "AT"],
+                    detail
+                        .admin_actions
+                        .iter()
+                        .map(|a| {
+                            vec![
+                                a.action.as_str().to_string(),
+                                a.actor_id.map(|id| id.to_string()).unwrap_or_else(|| "authctl".into()),
+                                timestamp(a.created_at),
+                            ]
+                        })
+                        .collect(),
+                );
+            }
+        }
+        UserCommand::Lock { user, reason } => {
+            let user_id = resolve_user(pool, &user).await?;
+            admin_users::lock_user(pool, actor, user_id, reason).await?;
+            report(json, json!({ "id": user_id, "locked": true }), "Account locked");
+        }
+        UserCommand::Unlock { user } => {
+            let user_id = resolve_user(pool, &user).await?;
+            admin_users::unlock_user(pool, actor, user_id).await?;
+            report(json, json!({ "id": user_id, "locked": false }), "Account unlocked");
+        }
+        UserCommand::SoftDelete { user } => {
+            let user_id = resolve_user(pool, &user).await?;
+            admin_users::delete_user(pool, actor, user_id).await?;
+            report(json, json!({ "id": user_id, "soft_deleted": true }), "Account soft-deleted");
+        }
+        UserCommand::SetPassword { user, password_stdin } => {
+            let user_id = resolve_user(pool, &user).await?;
+            let account = sqlx::query!("SELECT username, email FROM users WHERE id = $1", user_id)
+                .fetch_one(pool)
+                .await?;
+
+            let policy = PasswordPolicy::from_config(&SecurityConfig::from_env());
+            let password = read_password(password_stdin)?;
+            policy
+                .check(&password, &[&account.username, &account.email])
+                .map_err(validation_failed)?;
+
+            let hashed = hash(&password).await?;
+            admin_users::set_password(pool, actor, user_id, &hashed, policy.history_size).await?;
+            report(json, json!({ "id": user_id }), "Password set; all sessions were revoked");
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_migrate(pool: &Pool<Postgres>, command: MigrateCommand, json: bool) -> anyhow::Result<()> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashMap<i64, Vec<u8>> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect();
+    drop(conn);
+
+    match command {
+        MigrateCommand::Up => {
+            MIGRATOR.run(pool).await?;
+            let count = MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration() && !applied.contains_key(&m.version))
+                .count();
+            report(json, json!({ "applied": count }), &format!("Applied {} migrations", count));
+        }
+        MigrateCommand::Status => {
+            let rows: Vec<(i64, String, &str)> = MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+                .map(|m| {
+                    let state = match applied.get(&m.version) {
+                        Some(checksum) if *checksum == *m.checksum => "applied",
+                        Some(_) => "changed since applied",
+                        None => "pending",
+                    };
+                    (m.version, m.description.to_string(), state)
+                })
+                .collect();
+
+            if json {
+                let rows: Vec<_> = rows
+                    .iter()
+                    .map(|(version, description, state)| {
+                        json!({ "version": version, "description": description, "state": state })
+                    })
+                    .collect();
+                print_json(&rows)?;
+            } else {
+                print_table(
+                    &["VERSION", "DESCRIPTION", "STATE"],
+                    rows.into_iter()
+                        .map(|(version, description, state)| vec![version.to_string(), description, state.to_string()])
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rotate_keys(json: bool) -> anyhow::Result<()> {
+    let secret = generate_token();
+
+    if json {
+        return print_json(&json!({ "jwt_secret": secret }));
+    }
+
+    println!("New signing key:\n\n    {}\n", secret);
+    println!("To roll it out without signing everyone out:");
+    println!("  1. Set JWT_PREVIOUS_SECRET to the current JWT_SECRET");
+    println!("  2. Set JWT_SECRET to the new key and restart the servers");
+    println!("  3. Remove JWT_PREVIOUS_SECRET once the old tokens have expired (24 hours)");
+    Ok(())
+}
+
+// An account id, or the email address of an account
+async fn resolve_user(pool: &Pool<Postgres>, user: &str) -> anyhow::Result<Uuid> {
+    if let Ok(id) = Uuid::parse_str(user) {
+        return Ok(id);
+    }
+
+    sqlx::query_scalar!("SELECT id FROM users WHERE LOWER(email) = LOWER($1)", user.trim())
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("No account uses {}", user))
+}
+
+fn read_password(from_stdin: bool) -> anyhow::Result<String> {
+    if from_stdin {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password = rpassword::prompt_password("Password: ").context("cannot read the password")?;
+    if rpassword::prompt_password("Repeat password: ")? != password {
+        bail!("Passwords do not match");
+    }
+    Ok(password)
+}
+
+// Argon2 with the server's hashing settings
+async fn hash(password: &str) -> anyhow::Result<String> {
+    HashPool::new(&HashingConfig::from_env())
+        .hash(password)
+        .await
+        .map_err(|e| anyhow!("{}", e))
+}
+
+fn validation_failed(errors: Vec<ValidationError>) -> anyhow::Error {
+    let details: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+    anyhow!("Validation failed\n  {}", details.join("\n  "))
+}
+
+fn user_row(user: &AdminUserSummary) -> Vec<String> {
+    vec![
+        user.id.to_string(),
+        user.email.clone(),
+        user.username.clone(),
+        if user.email_verified { "yes" } else { "no" }.to_string(),
+        user.lock_reason.clone().or(user.locked_at.map(|_| "yes".into())).unwrap_or_default(),
+        user.created_at.map(timestamp).unwrap_or_default(),
+    ]
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn report(json: bool, value: serde_json::Value, message: &str) {
+    if json {
+        println!("{}", value);
+    } else {
+        println!("{}", message);
+    }
+}
+
+fn print_json(value: &impl serde::Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+
+    line(headers.to_vec());
+    for row in &rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Debug, Clone)]
+pub struct AccountDeletionConfig {
+    pub grace_period_days: i64,
+    // How often the background job looks for accounts past their deadline
+    pub sweep_interval_secs: u64,
+}
+
+impl AccountDeletionConfig {
+    pub fn from_env() -> Self {
+        Self {
+            grace_period_days: std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+                .unwrap_or_else(|_| "30".into())
+                .parse()
+                .unwrap_or(30),
+            sweep_interval_secs: std::env::var("ACCOUNT_DELETION_SWEEP_INTERVAL_SECS")
+                .unwrap_or_else(|_| "300".into())
+                .parse()
+                .unwrap_or(300),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use std::env;
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    // Externally reachable base URL of this API, used in links sent by email
+    pub public_url: String,
+    // Base URL of the web app, for links that open a page there
+    pub frontend_url: String,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        Self {
+            public_url: env::var("API_PUBLIC_URL")
+                .unwrap_or_else(|_| "http://localhost:8080".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            frontend_url: env::var("FRONTEND_URL")
+                .unwrap_or_else(|_| "http://localhost:5173".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+/// What to do when a new password appears in the breached-password index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreachCheckMode {
+    Reject,
+    Warn,
+}
+
+#[derive(Debug, Clone)]
+pub struct BreachCheckConfig {
+    // Index built with `Backend build-breach-index`; the check is off when unset
+    pub index_path: Option<String>,
+    pub mode: BreachCheckMode,
+}
+
+impl BreachCheckConfig {
+    pub fn from_env() -> Self {
+        Self {
+            index_path: std::env::var("BREACHED_PASSWORDS_INDEX")
+                .ok()
+                .filter(|p| !p.is_empty()),
+            mode: match std::env::var("BREACHED_PASSWORDS_MODE").as_deref() {
+                Ok("warn") => BreachCheckMode::Warn,
+                _ => BreachCheckMode::Reject,
+            },
+        }
+    }
+}
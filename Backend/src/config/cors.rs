@@ -3,6 +3,8 @@ use actix_cors::Cors;
 use actix_web::http::header;
 use std::env;
 
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+
 
 pub fn cors() -> Cors {
     // Environment-driven CORS: allow all in non-production; restrict in production via CORS_ALLOWED_ORIGINS
@@ -19,7 +21,7 @@ pub fn cors() -> Cors {
         return Cors::default()
             .allow_any_origin()
             .allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]) 
-            .allowed_headers([header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
+            .allowed_headers([header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE, REQUEST_ID_HEADER])
             .expose_any_header()
             .supports_credentials()
             .max_age(3600);
@@ -28,7 +30,7 @@ pub fn cors() -> Cors {
     // Production with expli:cit origins: restrict to the list and allow credentials
     let mut cors = Cors::default()
         .allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]) 
-        .allowed_headers([header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
+        .allowed_headers([header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE, REQUEST_ID_HEADER])
         .expose_any_header()
         .supports_credentials()
         .max_age(3600);
@@ -0,0 +1,25 @@
+#[derive(Debug, Clone)]
+pub struct EmailChangeConfig {
+    pub max_code_attempts: i32,
+    pub revert_ttl_hours: i64,
+    // HMAC key for the stored confirmation codes; defaults to JWT_SECRET
+    pub code_key: String,
+}
+
+impl EmailChangeConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_code_attempts: std::env::var("EMAIL_CHANGE_MAX_CODE_ATTEMPTS")
+                .unwrap_or_else(|_| "5".into())
+                .parse()
+                .unwrap_or(5),
+            revert_ttl_hours: std::env::var("EMAIL_CHANGE_REVERT_TTL_HOURS")
+                .unwrap_or_else(|_| "72".into())
+                .parse()
+                .unwrap_or(72),
+            code_key: std::env::var("EMAIL_CHANGE_CODE_KEY")
+                .or_else(|_| std::env::var("JWT_SECRET"))
+                .expect("EMAIL_CHANGE_CODE_KEY or JWT_SECRET must be set"),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    // Where finished archives are written until they expire
+    pub dir: String,
+    pub link_ttl_hours: i64,
+    // Minimum time between two export requests by the same user
+    pub min_interval_hours: i64,
+    // Key for download URL signatures; defaults to JWT_SECRET
+    pub signing_key: String,
+    // How often expired and orphaned archives are removed
+    pub cleanup_interval_secs: u64,
+}
+
+impl ExportConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dir: env::var("EXPORT_DIR").unwrap_or_else(|_| "./exports".to_string()),
+            link_ttl_hours: env::var("EXPORT_LINK_TTL_HOURS")
+                .unwrap_or_else(|_| "48".into())
+                .parse()
+                .unwrap_or(48),
+            min_interval_hours: env::var("EXPORT_MIN_INTERVAL_HOURS")
+                .unwrap_or_else(|_| "24".into())
+                .parse()
+                .unwrap_or(24),
+            signing_key: env::var("EXPORT_SIGNING_KEY")
+                .or_else(|_| env::var("JWT_SECRET"))
+                .expect("EXPORT_SIGNING_KEY or JWT_SECRET must be set"),
+            cleanup_interval_secs: env::var("EXPORT_CLEANUP_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".into())
+                .parse()
+                .unwrap_or(3600),
+        }
+    }
+}
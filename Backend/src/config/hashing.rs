@@ -0,0 +1,34 @@
+#[derive(Debug, Clone)]
+pub struct HashingConfig {
+    pub max_concurrent: usize,
+    pub max_queue: usize,
+    pub queue_timeout_ms: u64,
+    pub retry_after_secs: u64,
+}
+
+impl HashingConfig {
+    pub fn from_env() -> Self {
+        let default_concurrency = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+
+        Self {
+            max_concurrent: std::env::var("HASH_POOL_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default_concurrency),
+            max_queue: std::env::var("HASH_POOL_MAX_QUEUE")
+                .unwrap_or_else(|_| "64".into())
+                .parse()
+                .unwrap_or(64),
+            queue_timeout_ms: std::env::var("HASH_POOL_QUEUE_TIMEOUT_MS")
+                .unwrap_or_else(|_| "2000".into())
+                .parse()
+                .unwrap_or(2000),
+            retry_after_secs: std::env::var("HASH_POOL_RETRY_AFTER_SECS")
+                .unwrap_or_else(|_| "1".into())
+                .parse()
+                .unwrap_or(1),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Debug, Clone)]
+pub struct ImpersonationConfig {
+    // How long an impersonation token stays valid
+    pub ttl_minutes: i64,
+}
+
+impl ImpersonationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            ttl_minutes: std::env::var("IMPERSONATION_TTL_MINUTES")
+                .unwrap_or_else(|_| "15".into())
+                .parse()
+                .unwrap_or(15),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Debug, Clone)]
+pub struct InvitationConfig {
+    // How long an invitation link stays valid
+    pub ttl_hours: i64,
+}
+
+impl InvitationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            ttl_hours: std::env::var("INVITATION_TTL_HOURS")
+                .unwrap_or_else(|_| "168".into())
+                .parse()
+                .unwrap_or(168),
+        }
+    }
+}
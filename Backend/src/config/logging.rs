@@ -0,0 +1,42 @@
+use std::env;
+
+// Filter used when neither LOG_FILTER nor RUST_LOG is set: sqlx logs every
+// statement at info
+const DEFAULT_FILTER: &str = "info,sqlx=warn";
+
+/// How log lines are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    // One JSON object per line, for the log collector
+    Json,
+    // Human-readable, for development
+    Pretty,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    // `EnvFilter` directives: a default level plus per-module levels,
+    // e.g. `info,backend::auth=debug,actix_web=warn`
+    pub filter: String,
+}
+
+impl LoggingConfig {
+    pub fn from_env() -> Self {
+        let production = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string()) == "production";
+
+        Self {
+            format: match env::var("LOG_FORMAT").as_deref() {
+                Ok("json") => LogFormat::Json,
+                Ok("pretty") => LogFormat::Pretty,
+                _ if production => LogFormat::Json,
+                _ => LogFormat::Pretty,
+            },
+            filter: env::var("LOG_FILTER")
+                .or_else(|_| env::var("RUST_LOG"))
+                .ok()
+                .filter(|f| !f.is_empty())
+                .unwrap_or_else(|| DEFAULT_FILTER.to_string()),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use std::env;
+
+/// Prometheus scrape endpoint. It is served on its own listener, away from
+/// the public API, and only when `METRICS_ADDR` is set.
+#[derive(Debug, Clone)]
+pub struct MetricsConfig {
+    // host:port of the metrics listener, e.g. 127.0.0.1:9100
+    pub addr: Option<String>,
+    // Bearer token the scraper must send, when set
+    pub token: Option<String>,
+}
+
+impl MetricsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            addr: env::var("METRICS_ADDR").ok().filter(|a| !a.is_empty()),
+            token: env::var("METRICS_TOKEN").ok().filter(|t| !t.is_empty()),
+        }
+    }
+}
@@ -1,4 +1,18 @@
 pub mod cors;
 pub mod security;
 pub mod otp;
-pub mod login;
\ No newline at end of file
+pub mod login;
+pub mod hashing;
+pub mod breach;
+pub mod app;
+pub mod email_change;
+pub mod account_deletion;
+pub mod export;
+pub mod rbac;
+pub mod policy;
+pub mod sharing;
+pub mod invitation;
+pub mod impersonation;
+pub mod security_events;
+pub mod logging;
+pub mod metrics;
@@ -34,7 +34,7 @@ impl OtpConfig {
 
     // Check if minimum intervals has passed since last OTP
     pub fn can_resend(&self, last_sent_at: DateTime<Utc>) -> bool {
-        let next_allowed = last_sent_at + Duration::seconds(self > min_interval_secs);
+        let next_allowed = last_sent_at + Duration::seconds(self.min_interval_secs);
         Utc::now() >= next_allowed
     }
 
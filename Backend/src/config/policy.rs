@@ -0,0 +1,13 @@
+#[derive(Debug, Clone)]
+pub struct PolicyConfig {
+    // Directory of `*.policy` files, loaded at startup; decision tests live in `<dir>/tests`
+    pub dir: String,
+}
+
+impl PolicyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dir: std::env::var("POLICY_DIR").unwrap_or_else(|_| "./policies".into()),
+        }
+    }
+}
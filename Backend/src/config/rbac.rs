@@ -0,0 +1,16 @@
+#[derive(Debug, Clone)]
+pub struct RbacConfig {
+    // How long a user's resolved permissions are reused before reloading them
+    pub permission_cache_ttl_secs: u64,
+}
+
+impl RbacConfig {
+    pub fn from_env() -> Self {
+        Self {
+            permission_cache_ttl_secs: std::env::var("PERMISSION_CACHE_TTL_SECS")
+                .unwrap_or_else(|_| "60".into())
+                .parse()
+                .unwrap_or(60),
+        }
+    }
+}
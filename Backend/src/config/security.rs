@@ -1,11 +1,18 @@
 use std::env;
 use std::time::Duration;
 
+#[derive(Clone)]
 pub struct SecurityConfig {
     pub jwt_secret: String,
     pub jwt_expiration_hours: u64,
     pub password_min_length: usize,
     pub password_max_length: usize,
+    pub password_require_uppercase: bool,
+    pub password_require_lowercase: bool,
+    pub password_require_digit: bool,
+    pub password_require_special: bool,
+    pub password_min_score: u8,
+    pub password_history_size: usize,
     pub rate_limit_auth_requests: u32,
     pub rate_limit_auth_window_minutes: u64,
     pub rate_limit_general_requests: u32,
@@ -32,6 +39,30 @@ impl SecurityConfig {
                 .unwrap_or_else(|_| "128".to_string())
                 .parse()
                 .expect("PASSWORD_MAX_LENGTH must be a number"),
+            password_require_uppercase: env::var("PASSWORD_REQUIRE_UPPERCASE")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            password_require_lowercase: env::var("PASSWORD_REQUIRE_LOWERCASE")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            password_require_digit: env::var("PASSWORD_REQUIRE_DIGIT")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            password_require_special: env::var("PASSWORD_REQUIRE_SPECIAL")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            password_min_score: env::var("PASSWORD_MIN_SCORE")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("PASSWORD_MIN_SCORE must be a number between 0 and 4"),
+            password_history_size: env::var("PASSWORD_HISTORY_SIZE")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("PASSWORD_HISTORY_SIZE must be a number"),
             rate_limit_auth_requests: env::var("RATE_LIMIT_AUTH_REQUESTS")
                 .unwrap_or_else(|_| "5".to_string())
                 .parse()
@@ -83,6 +114,12 @@ impl Default for SecurityConfig {
             jwt_expiration_hours: 24,
             password_min_length: 8,
             password_max_length: 128,
+            password_require_uppercase: true,
+            password_require_lowercase: true,
+            password_require_digit: true,
+            password_require_special: true,
+            password_min_score: 3,
+            password_history_size: 5,
             rate_limit_auth_requests: 5,
             rate_limit_auth_window_minutes: 5,
             rate_limit_general_requests: 100,
@@ -0,0 +1,64 @@
+use std::env;
+
+/// Wire format of exported security events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityEventFormat {
+    Json,
+    // ArcSight Common Event Format
+    Cef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+}
+
+/// Where audit events are exported for the SIEM. Only used when
+/// `LOG_SECURITY_EVENTS` is on; each sink is off until its target is set.
+#[derive(Debug, Clone)]
+pub struct SecurityEventConfig {
+    // Events file, rotated by size
+    pub file_path: Option<String>,
+    pub file_format: SecurityEventFormat,
+    pub file_max_bytes: u64,
+    // Rotated files kept next to the live one (`<path>.1` is the newest)
+    pub file_keep: usize,
+    // Syslog collector as host:port (RFC 5424 messages)
+    pub syslog_addr: Option<String>,
+    pub syslog_protocol: SyslogProtocol,
+    pub syslog_format: SecurityEventFormat,
+    pub syslog_app_name: String,
+}
+
+impl SecurityEventConfig {
+    pub fn from_env() -> Self {
+        Self {
+            file_path: env::var("SECURITY_EVENTS_FILE").ok().filter(|p| !p.is_empty()),
+            file_format: format_from_env("SECURITY_EVENTS_FILE_FORMAT"),
+            file_max_bytes: env::var("SECURITY_EVENTS_FILE_MAX_BYTES")
+                .unwrap_or_else(|_| "10485760".into())
+                .parse()
+                .unwrap_or(10 * 1024 * 1024),
+            file_keep: env::var("SECURITY_EVENTS_FILE_KEEP")
+                .unwrap_or_else(|_| "5".into())
+                .parse()
+                .unwrap_or(5),
+            syslog_addr: env::var("SECURITY_EVENTS_SYSLOG_ADDR").ok().filter(|a| !a.is_empty()),
+            syslog_protocol: match env::var("SECURITY_EVENTS_SYSLOG_PROTOCOL").as_deref() {
+                Ok("tcp") => SyslogProtocol::Tcp,
+                _ => SyslogProtocol::Udp,
+            },
+            syslog_format: format_from_env("SECURITY_EVENTS_SYSLOG_FORMAT"),
+            syslog_app_name: env::var("SECURITY_EVENTS_SYSLOG_APP_NAME")
+                .unwrap_or_else(|_| "uiso-auth".to_string()),
+        }
+    }
+}
+
+fn format_from_env(name: &str) -> SecurityEventFormat {
+    match env::var(name).as_deref() {
+        Ok("cef") => SecurityEventFormat::Cef,
+        _ => SecurityEventFormat::Json,
+    }
+}
@@ -0,0 +1,23 @@
+use std::env;
+
+#[derive(Debug, Clone)]
+pub struct SharingConfig {
+    // Key for share link signatures; defaults to JWT_SECRET
+    pub signing_key: String,
+    // Longest lifetime of an anonymous share link
+    pub max_link_ttl_hours: i64,
+}
+
+impl SharingConfig {
+    pub fn from_env() -> Self {
+        Self {
+            signing_key: env::var("SHARE_SIGNING_KEY")
+                .or_else(|_| env::var("JWT_SECRET"))
+                .expect("SHARE_SIGNING_KEY or JWT_SECRET must be set"),
+            max_link_ttl_hours: env::var("SHARE_LINK_MAX_TTL_HOURS")
+                .unwrap_or_else(|_| "168".into())
+                .parse()
+                .unwrap_or(168),
+        }
+    }
+}
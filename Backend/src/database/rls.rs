@@ -0,0 +1,70 @@
+use sqlx::{Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+
+/// Open a transaction bound to the authenticated user.
+///
+/// Inside it the queries run as the unprivileged `uiso_app` role with
+/// `app.user_id` and `app.tenant_id` set, so row-level security limits every
+/// table to the caller's own rows (and their active organization) even when a
+/// query forgets its filter. Commit it for writes; dropping it rolls back.
+pub async fn begin_user_scope(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    begin_scope(pool, user.user_id, user.organization_id).await
+}
+
+pub async fn begin_scope(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    tenant_id: Option<Uuid>,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SET LOCAL ROLE uiso_app")
+        .execute(&mut *tx)
+        .await?;
+
+    // set_config(.., true) is SET LOCAL with bind parameters
+    sqlx::query(
+        "SELECT set_config('app.user_id', $1, true), set_config('app.tenant_id', $2, true)",
+    )
+    .bind(user_id.to_string())
+    .bind(tenant_id.map(|id| id.to_string()).unwrap_or_default())
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(tx)
+}
+
+/// Open a user-bound transaction whose tenant is `organization_id` rather than
+/// the active organization, for requests that name an organization in their
+/// path. Returns `None` when the user is not a member of it.
+pub async fn begin_organization_scope(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    organization_id: Uuid,
+) -> Result<Option<Transaction<'static, Postgres>>, sqlx::Error> {
+    let mut tx = begin_scope(pool, user.user_id, None).await?;
+
+    // The user's own memberships are visible without a tenant
+    let member: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM memberships WHERE organization_id = $1 AND user_id = $2)",
+    )
+    .bind(organization_id)
+    .bind(user.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !member {
+        return Ok(None);
+    }
+
+    sqlx::query("SELECT set_config('app.tenant_id', $1, true)")
+        .bind(organization_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    Ok(Some(tx))
+}
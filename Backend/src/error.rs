@@ -0,0 +1,247 @@
+//! The API's error type. Every error is rendered as an RFC 7807 problem
+//! (`application/problem+json`) with a stable `code` clients can match on
+//! and the id of the request, to quote when reporting a problem.
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+use crate::auth::validation::ValidationError;
+use crate::middleware::request_id::current_request_id;
+use crate::services::admin_users::AdminError;
+use crate::utils::hash_pool::HashPoolError;
+
+#[derive(Debug)]
+pub enum AppError {
+    // 400, listing every field that failed
+    Validation(Vec<ValidationError>),
+    BadRequest(&'static str, String),
+    Unauthorized(&'static str, String),
+    Forbidden(&'static str, String),
+    // 403 from a permission guard, naming the permission
+    MissingPermission(&'static str),
+    NotFound(&'static str, String),
+    Conflict(&'static str, String),
+    Gone(&'static str, String),
+    TooManyRequests { code: &'static str, detail: String, retry_after_secs: Option<u64> },
+    // Shed load; clients retry after the given seconds
+    Unavailable { retry_after_secs: u64 },
+    // Logged with the request id, never shown to the client
+    Internal(String),
+}
+
+impl AppError {
+    pub fn validation(error: ValidationError) -> Self {
+        AppError::Validation(vec![error])
+    }
+
+    pub fn bad_request(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::BadRequest(code, detail.into())
+    }
+
+    pub fn unauthorized(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::Unauthorized(code, detail.into())
+    }
+
+    pub fn forbidden(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::Forbidden(code, detail.into())
+    }
+
+    pub fn not_found(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::NotFound(code, detail.into())
+    }
+
+    pub fn conflict(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::Conflict(code, detail.into())
+    }
+
+    pub fn gone(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::Gone(code, detail.into())
+    }
+
+    pub fn too_many_requests(code: &'static str, detail: impl Into<String>) -> Self {
+        AppError::TooManyRequests { code, detail: detail.into(), retry_after_secs: None }
+    }
+
+    /// A 429 that tells the client when to try again
+    pub fn retry_after(self, secs: u64) -> Self {
+        match self {
+            AppError::TooManyRequests { code, detail, .. } => {
+                AppError::TooManyRequests { code, detail, retry_after_secs: Some(secs) }
+            }
+            other => other,
+        }
+    }
+
+    pub fn internal(error: impl fmt::Display) -> Self {
+        AppError::Internal(error.to_string())
+    }
+
+    /// The token is missing, invalid, expired or its session was revoked
+    pub fn invalid_token() -> Self {
+        AppError::unauthorized("invalid_token", "Invalid or missing token")
+    }
+
+    /// The action must be taken by the user themselves, not an admin acting as them
+    pub fn impersonation_forbidden() -> Self {
+        AppError::forbidden(
+            "impersonation_forbidden",
+            "This action is not available while impersonating a user",
+        )
+    }
+
+    /// Stable machine-readable identifier of the problem
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation_failed",
+            AppError::MissingPermission(_) => "missing_permission",
+            AppError::Unavailable { .. } => "service_unavailable",
+            AppError::Internal(_) => "internal_error",
+            AppError::BadRequest(code, _)
+            | AppError::Unauthorized(code, _)
+            | AppError::Forbidden(code, _)
+            | AppError::NotFound(code, _)
+            | AppError::Conflict(code, _)
+            | AppError::Gone(code, _)
+            | AppError::TooManyRequests { code, .. } => code,
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            AppError::Validation(_) => "Validation failed",
+            AppError::MissingPermission(_) => "You do not have permission to perform this action",
+            AppError::Unavailable { .. } => "Server is busy. Please try again shortly.",
+            AppError::Internal(_) => "An unexpected error occurred",
+            AppError::BadRequest(_, detail)
+            | AppError::Unauthorized(_, detail)
+            | AppError::Forbidden(_, detail)
+            | AppError::NotFound(_, detail)
+            | AppError::Conflict(_, detail)
+            | AppError::Gone(_, detail)
+            | AppError::TooManyRequests { detail, .. } => detail,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Internal(e) => write!(f, "internal error: {}", e),
+            _ => write!(f, "{}: {}", self.code(), self.detail()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) | AppError::BadRequest(..) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(..) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(..) | AppError::MissingPermission(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(..) => StatusCode::NOT_FOUND,
+            AppError::Conflict(..) => StatusCode::CONFLICT,
+            AppError::Gone(..) => StatusCode::GONE,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let request_id = current_request_id();
+
+        if let AppError::Internal(e) = self {
+            tracing::error!(request_id = request_id.as_deref().unwrap_or("-"), "Request failed: {}", e);
+        }
+
+        let mut problem = Map::new();
+        problem.insert("type".into(), json!("about:blank"));
+        problem.insert("title".into(), json!(status.canonical_reason().unwrap_or("Error")));
+        problem.insert("status".into(), json!(status.as_u16()));
+        problem.insert("detail".into(), json!(self.detail()));
+        problem.insert("code".into(), json!(self.code()));
+        match self {
+            AppError::Validation(errors) => {
+                problem.insert("errors".into(), json!(errors));
+            }
+            AppError::MissingPermission(permission) => {
+                problem.insert("required_permission".into(), json!(permission));
+            }
+            _ => {}
+        }
+        if let Some(request_id) = request_id {
+            problem.insert("request_id".into(), json!(request_id));
+        }
+
+        let mut response = HttpResponse::build(status);
+        response.content_type("application/problem+json");
+        let retry_after_secs = match self {
+            AppError::Unavailable { retry_after_secs } => Some(*retry_after_secs),
+            AppError::TooManyRequests { retry_after_secs, .. } => *retry_after_secs,
+            _ => None,
+        };
+        if let Some(secs) = retry_after_secs {
+            response.insert_header(("Retry-After", secs.to_string()));
+        }
+        response.body(Value::Object(problem).to_string())
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        AppError::Internal(format!("database error: {}", e))
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::Internal(format!("{:#}", e))
+    }
+}
+
+// A saturated hashing queue sheds the request with 503
+impl From<HashPoolError> for AppError {
+    fn from(e: HashPoolError) -> Self {
+        match e {
+            HashPoolError::Saturated { retry_after_secs } => AppError::Unavailable { retry_after_secs },
+            HashPoolError::Failed(_) => AppError::internal(e),
+        }
+    }
+}
+
+impl From<AdminError> for AppError {
+    fn from(e: AdminError) -> Self {
+        match e {
+            AdminError::NotFound => AppError::not_found("user_not_found", "User not found"),
+            AdminError::Conflict(message) => AppError::conflict("invalid_account_state", message),
+            AdminError::OwnAccount(message) => AppError::bad_request("own_account", message),
+            AdminError::Privileged(message) => AppError::forbidden("privileged_account", message),
+            AdminError::Database(e) => e.into(),
+        }
+    }
+}
+
+/// Malformed JSON bodies, as problems instead of actix's plain-text errors
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::bad_request("invalid_body", err.to_string()).into()
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::bad_request("invalid_query", err.to_string()).into()
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::not_found("not_found", err.to_string()).into()
+}
+
+/// Fallback for requests no route matches
+pub async fn not_found() -> Result<HttpResponse, AppError> {
+    Err(AppError::not_found("not_found", "No such resource"))
+}
@@ -0,0 +1,12 @@
+//! Everything shared by the API server (`main.rs`) and the `authctl` admin CLI
+pub mod database;
+pub mod auth;
+pub mod models;
+pub mod config;
+pub mod error;
+pub mod logging;
+pub mod metrics;
+pub mod services;
+pub mod routes;
+pub mod middleware;
+pub mod utils;
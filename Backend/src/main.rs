@@ -2,36 +2,218 @@ use actix_web::{web, App, HttpServer, middleware::Logger};
 // Session middleware removed - using JWT-only authentication
 use std::env;
 
-mod database;
-mod auth;
-mod models;
-mod config;
-mod services;
-mod routes;
-mod middleware;
-mod utils;
-
-use database::db::establish_connection;
-use config::otp::OtpConfig;
-use config::cors::cors;
-use auth::middleware::AuthMiddleware;
-use middleware::security::SecurityHeadersMiddleware;
-use middleware::rate_limit::RateLimitMiddleware;
-use routes::auth_routes::public_routes;
+use backend::database::db::establish_connection;
+use backend::config::otp::OtpConfig;
+use backend::config::app::AppConfig;
+use backend::config::email_change::EmailChangeConfig;
+use backend::config::account_deletion::AccountDeletionConfig;
+use backend::config::export::ExportConfig;
+use backend::config::rbac::RbacConfig;
+use backend::config::policy::PolicyConfig;
+use backend::config::sharing::SharingConfig;
+use backend::config::invitation::InvitationConfig;
+use backend::config::impersonation::ImpersonationConfig;
+use backend::config::login::LoginLimitConfig;
+use backend::config::logging::LoggingConfig;
+use backend::config::metrics::MetricsConfig;
+use backend::config::hashing::HashingConfig;
+use backend::config::security::SecurityConfig;
+use backend::config::security_events::SecurityEventConfig;
+use backend::auth::password_policy::PasswordPolicy;
+use backend::auth::breached::{build_index, BreachedPasswordChecker};
+use backend::config::breach::BreachCheckConfig;
+use backend::config::cors::cors;
+use backend::auth::middleware::AuthMiddleware;
+use backend::error::{json_error_handler, not_found, path_error_handler, query_error_handler};
+use backend::logging;
+use backend::metrics;
+use backend::auth::policy::harness::run_test_dir;
+use backend::auth::policy::PolicySet;
+use backend::middleware::security::SecurityHeadersMiddleware;
+use backend::middleware::rate_limit::RateLimitMiddleware;
+use backend::middleware::request_id::RequestIdMiddleware;
+use backend::middleware::metrics::RequestMetricsMiddleware;
+use backend::routes::auth_routes::public_routes;
+use backend::routes::user_routes::protected_routes;
+use backend::routes::org_routes::{invitation_routes, organization_routes};
+use backend::routes::admin_routes::admin_routes;
+use backend::routes::grant_routes::grant_routes;
+use backend::services::account_deletion::spawn_purge_job;
+use backend::services::data_export::spawn_cleanup_job;
+use backend::services::import::import_users;
+use backend::services::rbac::{grant_role, PermissionCache};
+use backend::services::security_events;
+use backend::utils::hash_pool::HashPool;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load environment variables
     dotenvy::dotenv().ok();
     
-    // Initialize logging
-    env_logger::init();
+    // Initialize logging (format and per-module levels from LOG_FORMAT / LOG_FILTER)
+    logging::init(&LoggingConfig::from_env());
+
+    let args: Vec<String> = env::args().collect();
+
+    // One-off command: `Backend build-breach-index <pwned-passwords.txt> <output.idx>`
+    if args.get(1).map(String::as_str) == Some("build-breach-index") {
+        let (Some(source), Some(output)) = (args.get(2), args.get(3)) else {
+            eprintln!("Usage: {} build-breach-index <pwned-passwords.txt> <output.idx>", args[0]);
+            std::process::exit(2);
+        };
+
+        match build_index(std::path::Path::new(source), std::path::Path::new(output)) {
+            Ok(count) => {
+                println!("Wrote {} hash prefixes to {}", count, output);
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Index build failed: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // One-off command: `Backend check-policies [tests-dir]` validates the policy
+    // files and asserts the decisions listed in the tests directory
+    if args.get(1).map(String::as_str) == Some("check-policies") {
+        let policy_dir = std::path::PathBuf::from(PolicyConfig::from_env().dir);
+        let tests_dir = args
+            .get(2)
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| policy_dir.join("tests"));
+
+        let policies = match PolicySet::load_dir(&policy_dir) {
+            Ok(policies) => policies,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        match run_test_dir(&policies, &tests_dir) {
+            Ok(summary) => {
+                for failure in &summary.failures {
+                    eprintln!("FAIL {}", failure);
+                }
+                println!(
+                    "{} policies, {} cases passed, {} failed",
+                    policies.len(),
+                    summary.passed,
+                    summary.failures.len()
+                );
+                if summary.failures.is_empty() {
+                    return Ok(());
+                }
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Create database connection pool
     let pool = establish_connection().await;
 
+    // One-off command: `Backend import-users <file.csv|file.json>`
+    if args.get(1).map(String::as_str) == Some("import-users") {
+        let Some(path) = args.get(2) else {
+            eprintln!("Usage: {} import-users <file.csv|file.json>", args[0]);
+            std::process::exit(2);
+        };
+
+        match import_users(&pool, std::path::Path::new(path)).await {
+            Ok(summary) => {
+                println!(
+                    "Imported {} users ({} already existed, {} rejected)",
+                    summary.imported, summary.skipped_existing, summary.rejected
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Import failed: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // One-off command: `Backend grant-role <email> <role>` (e.g. to bootstrap the first admin)
+    if args.get(1).map(String::as_str) == Some("grant-role") {
+        let (Some(email), Some(role)) = (args.get(2), args.get(3)) else {
+            eprintln!("Usage: {} grant-role <email> <role>", args[0]);
+            std::process::exit(2);
+        };
+
+        let user_id = sqlx::query_scalar!("SELECT id FROM users WHERE LOWER(email) = LOWER($1)", email)
+            .fetch_optional(&pool)
+            .await;
+
+        match user_id {
+            Ok(Some(user_id)) => match grant_role(&pool, user_id, role, None).await {
+                Ok(true) => {
+                    println!("Granted role {} to {}", role, email);
+                    return Ok(());
+                }
+                Ok(false) => eprintln!("Unknown role: {}", role),
+                Err(e) => eprintln!("Grant failed: {:?}", e),
+            },
+            Ok(None) => eprintln!("No account uses {}", email),
+            Err(e) => eprintln!("Lookup failed: {:?}", e),
+        }
+        std::process::exit(1);
+    }
+
     // otp variable
-    let otp_config = OtpConfig::from_env;
+    let otp_config = OtpConfig::from_env();
+    let login_config = LoginLimitConfig::from_env();
+    let security_config = SecurityConfig::from_env();
+    let password_policy = PasswordPolicy::from_config(&security_config);
+    let app_config = AppConfig::from_env();
+    let email_change_config = EmailChangeConfig::from_env();
+    let account_deletion_config = AccountDeletionConfig::from_env();
+    let sharing_config = SharingConfig::from_env();
+    let invitation_config = InvitationConfig::from_env();
+    let impersonation_config = ImpersonationConfig::from_env();
+
+    // Audit events exported to the SIEM; a sink that cannot be opened stops the server
+    if security_config.log_security_events {
+        security_events::install(&SecurityEventConfig::from_env())
+            .expect("Failed to open the security event sinks");
+    }
+
+    // Resolved permissions, shared by all workers
+    let permission_cache = PermissionCache::new(std::time::Duration::from_secs(
+        RbacConfig::from_env().permission_cache_ttl_secs,
+    ));
+
+    // Hard-delete accounts whose deletion grace period has ended
+    spawn_purge_job(
+        pool.clone(),
+        std::time::Duration::from_secs(account_deletion_config.sweep_interval_secs),
+    );
+
+    // Data export archives, removed once their download link expires
+    let export_config = ExportConfig::from_env();
+    spawn_cleanup_job(
+        pool.clone(),
+        export_config.dir.clone(),
+        std::time::Duration::from_secs(export_config.cleanup_interval_secs),
+    );
+
+    // Authorization policies; invalid files stop the server from starting
+    let policies = web::Data::new(
+        PolicySet::load_dir(std::path::Path::new(&PolicyConfig::from_env().dir))
+            .unwrap_or_else(|e| panic!("Failed to load policies: {}", e)),
+    );
+
+    // Bounded pool for password hashing, shared by all workers
+    let hash_pool = HashPool::new(&HashingConfig::from_env());
+
+    // Offline breached-password index (optional)
+    let breach_checker = BreachedPasswordChecker::from_config(&BreachCheckConfig::from_env())
+        .expect("Failed to open BREACHED_PASSWORDS_INDEX");
 
     // Get server configuration
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -40,25 +222,62 @@ async fn main() -> std::io::Result<()> {
         .parse::<u16>()
         .expect("PORT must be a valid number");
 
-    println!("🚀 Starting server on {}:{}", host, port);
+    // Prometheus metrics on their own listener, outside the API's middleware
+    let metrics_config = MetricsConfig::from_env();
+    let metrics_server = match &metrics_config.addr {
+        Some(addr) => {
+            tracing::info!("Serving metrics on {}/metrics", addr);
+            Some(metrics::server(&metrics_config, addr, pool.clone())?)
+        }
+        None => None,
+    };
+
+    tracing::info!("Starting server on {}:{}", host, port);
 
     // Start the HTTP server
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             // Add database pool to app data
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(otp_config.clone()))
-            .wrap(Logger::default()) //1. Add logging middleware
+            .app_data(web::Data::new(login_config.clone()))
+            .app_data(web::Data::new(hash_pool.clone()))
+            .app_data(web::Data::new(password_policy.clone()))
+            .app_data(web::Data::new(security_config.clone()))
+            .app_data(web::Data::new(breach_checker.clone()))
+            .app_data(web::Data::new(app_config.clone()))
+            .app_data(web::Data::new(email_change_config.clone()))
+            .app_data(web::Data::new(account_deletion_config.clone()))
+            .app_data(web::Data::new(export_config.clone()))
+            .app_data(web::Data::new(sharing_config.clone()))
+            .app_data(web::Data::new(invitation_config.clone()))
+            .app_data(web::Data::new(impersonation_config.clone()))
+            .app_data(web::Data::new(permission_cache.clone()))
+            .app_data(policies.clone())
+            // Malformed bodies, queries and paths answer with problem details
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
+            .wrap(Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{X-Request-Id}i"#)) //1. Add logging middleware
             .wrap(SecurityHeadersMiddleware) //2. Add security headers
             .wrap(cors()) //3. Add CORS middleware
             .wrap(RateLimitMiddleware::auth_endpoints()) //4. Add rate limiting for auth endpoints
             .wrap(AuthMiddleware)//5. Add authentication middleware to all routes
-            .service(public_routes())//6. Register public routes (auth endpoints, health check)
-            
-            // Add protected routes here
-            // .service(protected_routes())
+            .wrap(RequestIdMiddleware)//0. Every response, errors included, knows its request id
+            .wrap(RequestMetricsMiddleware)//Outermost: times every request, refused ones too
+            .service(protected_routes())//6. Register protected routes (before the catch-all public scope)
+            .service(organization_routes())
+            .service(invitation_routes())
+            .service(grant_routes())
+            .service(admin_routes())
+            .service(public_routes())//7. Register public routes (auth endpoints, health check)
+            .default_service(web::to(not_found))
     })
     .bind(format!("{}:{}", host, port))?
-    .run()
-    .await
+    .run();
+
+    match metrics_server {
+        Some(metrics_server) => futures_util::future::try_join(server, metrics_server).await.map(|_| ()),
+        None => server.await,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+//! Prometheus metrics. Every series is registered when `METRICS` is first
+//! used, so the scrape lists them all (at zero) from the start. The scrape
+//! endpoint runs on its own listener, see `server`.
+use actix_web::{dev::Server, web, App, HttpRequest, HttpResponse, HttpServer};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+
+use crate::auth::middleware::bearer_token;
+use crate::config::metrics::MetricsConfig;
+use crate::error::AppError;
+
+// Password hashing takes tens to hundreds of milliseconds by design
+const HASH_DURATION_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+// Waiting for a worker is normally instant; the queue timeout defaults to 2s
+const HASH_QUEUE_WAIT_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    // Labelled by route pattern, never the raw path, to bound the series
+    pub http_request_duration: HistogramVec,
+    pub rate_limit_rejections: IntCounter,
+    // `success`, or the reason the sign-in was refused
+    pub logins: IntCounterVec,
+    pub lockout_rejections: IntCounter,
+    pub registrations: IntCounter,
+    pub password_reset_requests: IntCounterVec,
+    pub otp_emails: IntCounterVec,
+    // Argon2 (or legacy) work on the hash pool, excluding time spent queued
+    pub password_hash_duration: HistogramVec,
+    // Time a job waited for a hash pool worker, including waits that timed out
+    pub password_hash_queue_wait: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+            &["method", "route", "status"],
+        )
+        .expect("Invalid metric");
+        let rate_limit_rejections = IntCounter::new(
+            "http_rate_limit_rejections_total",
+            "Requests refused by the rate limiter",
+        )
+        .expect("Invalid metric");
+        let logins = IntCounterVec::new(
+            Opts::new("auth_logins_total", "Sign-in attempts by outcome"),
+            &["outcome"],
+        )
+        .expect("Invalid metric");
+        let lockout_rejections = IntCounter::new(
+            "auth_lockout_rejections_total",
+            "Sign-ins refused because the account is locked out after failed attempts",
+        )
+        .expect("Invalid metric");
+        let registrations = IntCounter::new("auth_registrations_total", "Accounts registered")
+            .expect("Invalid metric");
+        let password_reset_requests = IntCounterVec::new(
+            Opts::new("auth_password_reset_requests_total", "Password reset requests by outcome"),
+            &["outcome"],
+        )
+        .expect("Invalid metric");
+        let otp_emails = IntCounterVec::new(
+            Opts::new("auth_otp_emails_total", "Password reset code emails by result"),
+            &["result"],
+        )
+        .expect("Invalid metric");
+        let password_hash_duration = HistogramVec::new(
+            HistogramOpts::new("password_hash_duration_seconds", "Password hashing and verification time")
+                .buckets(HASH_DURATION_BUCKETS.to_vec()),
+            &["operation"],
+        )
+        .expect("Invalid metric");
+        let password_hash_queue_wait = HistogramVec::new(
+            HistogramOpts::new("password_hash_queue_wait_seconds", "Time password hashing jobs waited for a worker")
+                .buckets(HASH_QUEUE_WAIT_BUCKETS.to_vec()),
+            &["operation"],
+        )
+        .expect("Invalid metric");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .expect("Invalid metric");
+        let db_pool_max_connections = IntGauge::new("db_pool_max_connections", "Database pool size limit")
+            .expect("Invalid metric");
+
+        // Known outcomes start at zero, so rates can be computed before the first one
+        for outcome in ["success", "unknown_account", "invalid_password", "locked_out", "account_locked"] {
+            logins.with_label_values(&[outcome]);
+        }
+        for outcome in ["sent", "unknown_account", "throttled"] {
+            password_reset_requests.with_label_values(&[outcome]);
+        }
+        for result in ["success", "failure"] {
+            otp_emails.with_label_values(&[result]);
+        }
+
+        registry.register(Box::new(http_request_duration.clone())).expect("Duplicate metric");
+        registry.register(Box::new(rate_limit_rejections.clone())).expect("Duplicate metric");
+        registry.register(Box::new(logins.clone())).expect("Duplicate metric");
+        registry.register(Box::new(lockout_rejections.clone())).expect("Duplicate metric");
+        registry.register(Box::new(registrations.clone())).expect("Duplicate metric");
+        registry.register(Box::new(password_reset_requests.clone())).expect("Duplicate metric");
+        registry.register(Box::new(otp_emails.clone())).expect("Duplicate metric");
+        registry.register(Box::new(password_hash_duration.clone())).expect("Duplicate metric");
+        registry.register(Box::new(password_hash_queue_wait.clone())).expect("Duplicate metric");
+        registry.register(Box::new(db_pool_connections.clone())).expect("Duplicate metric");
+        registry.register(Box::new(db_pool_max_connections.clone())).expect("Duplicate metric");
+
+        Self {
+            registry,
+            http_request_duration,
+            rate_limit_rejections,
+            logins,
+            lockout_rejections,
+            registrations,
+            password_reset_requests,
+            otp_emails,
+            password_hash_duration,
+            password_hash_queue_wait,
+            db_pool_connections,
+            db_pool_max_connections,
+        }
+    }
+
+    // Pool usage is sampled when scraped
+    fn observe_pool(&self, pool: &Pool<Postgres>) {
+        let size = i64::from(pool.size());
+        let idle = pool.num_idle() as i64;
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections.with_label_values(&["in_use"]).set((size - idle).max(0));
+        self.db_pool_max_connections.set(i64::from(pool.options().get_max_connections()));
+    }
+}
+
+/// The metrics listener: `GET /metrics` and nothing else
+pub fn server(config: &MetricsConfig, addr: &str, pool: Pool<Postgres>) -> std::io::Result<Server> {
+    let config = config.clone();
+    Ok(HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .route("/metrics", web::get().to(scrape))
+    })
+    .workers(1)
+    .bind(addr)?
+    .run())
+}
+
+async fn scrape(
+    req: HttpRequest,
+    pool: web::Data<Pool<Postgres>>,
+    config: web::Data<MetricsConfig>,
+) -> Result<HttpResponse, AppError> {
+    if let Some(expected) = &config.token {
+        // Compared as digests, so the time taken says nothing about the token
+        let given = bearer_token(&req).unwrap_or_default();
+        if Sha256::digest(given.as_bytes()) != Sha256::digest(expected.as_bytes()) {
+            return Err(AppError::invalid_token());
+        }
+    }
+
+    METRICS.observe_pool(pool.get_ref());
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    encoder.encode(&METRICS.registry.gather(), &mut body).map_err(AppError::internal)?;
+
+    Ok(HttpResponse::Ok().content_type(encoder.format_type()).body(body))
+}
@@ -0,0 +1,72 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::{ok, Ready, LocalBoxFuture};
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::metrics::METRICS;
+
+// Route label for requests no route matched
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Records every request in `http_request_duration_seconds`. Wrap it
+/// outermost, so requests refused by the other middleware are counted too.
+pub struct RequestMetricsMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestMetricsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestMetricsMiddlewareService {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct RequestMetricsMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = Rc::clone(&self.service);
+        let method = req.method().to_string();
+        let route = req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = srv.call(req).await;
+
+            let status = match &result {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            METRICS
+                .http_request_duration
+                .with_label_values(&[method.as_str(), route.as_str(), status.as_str()])
+                .observe(started.elapsed().as_secs_f64());
+
+            result
+        })
+    }
+}
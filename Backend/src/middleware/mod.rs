@@ -1,2 +1,4 @@
 pub mod security;
 pub mod rate_limit;
+pub mod request_id;
+pub mod metrics;
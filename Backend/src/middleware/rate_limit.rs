@@ -9,6 +9,9 @@ use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
 
+use crate::error::AppError;
+use crate::metrics::METRICS;
+
 // Simple in-memory rate limiter (for production, use Redis or similar)
 type RateLimitStore = Mutex<HashMap<String, (Instant, u32)>>;
 
@@ -102,7 +105,11 @@ where
                         if now.duration_since(window_start) < window_duration {
                             if request_count >= max_requests {
                                 tracing::warn!("Rate limit exceeded for IP: {}", client_ip);
-                                return Err(actix_web::error::ErrorTooManyRequests("Rate limit exceeded"));
+                                METRICS.rate_limit_rejections.inc();
+                                let retry_after = window_duration - now.duration_since(window_start);
+                                return Err(AppError::too_many_requests("rate_limited", "Rate limit exceeded")
+                                    .retry_after(retry_after.as_secs().max(1))
+                                    .into());
                             }
                             // Increment counter
                             store.insert(client_ip.clone(), (window_start, request_count + 1));
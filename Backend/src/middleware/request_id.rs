@@ -0,0 +1,125 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    Error,
+};
+use futures_util::future::{ok, Ready, LocalBoxFuture};
+use std::future::Future;
+use std::rc::Rc;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: Option<String>;
+}
+
+// Longest client-supplied id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of the request being handled, when called while handling one
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok().flatten()
+}
+
+/// Carry the request id and span into a task spawned to outlive the request
+pub fn with_request_context<F: Future>(task: F) -> impl Future<Output = F::Output> {
+    REQUEST_ID.scope(current_request_id(), task.instrument(tracing::Span::current()))
+}
+
+/// `with_request_context` for work run on the blocking pool
+pub fn with_request_context_blocking<R>(work: impl FnOnce() -> R) -> impl FnOnce() -> R {
+    let request_id = current_request_id();
+    let span = tracing::Span::current();
+    move || REQUEST_ID.sync_scope(request_id, || span.in_scope(work))
+}
+
+/// Gives each request an id: the client's `X-Request-Id` when it is usable,
+/// a new UUID otherwise. The request is handled inside a `request` span
+/// carrying the id, route and client address (`AuthMiddleware` adds the user
+/// id), and the id is echoed in the `X-Request-Id` response header. Wrap it
+/// outermost, so errors raised by the other middleware are rendered while the
+/// id is known.
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdMiddlewareService {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let srv = Rc::clone(&self.service);
+        let request_id = incoming_request_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
+        // Always a valid header value: client ids are checked, the rest are UUIDs
+        let header_value = HeaderValue::from_str(&request_id).expect("request id is a valid header value");
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            route = %req.match_pattern().unwrap_or_else(|| req.path().to_string()),
+            client_ip = req.connection_info().realip_remote_addr().unwrap_or("-"),
+            user_id = tracing::field::Empty,
+        );
+
+        // Replaces an unusable client id, so the access log sees the one in use
+        req.headers_mut().insert(REQUEST_ID_HEADER, header_value.clone());
+
+        Box::pin(REQUEST_ID.scope(Some(request_id), async move {
+            match srv.call(req).await {
+                Ok(mut res) => {
+                    echo_request_id(res.headers_mut(), header_value);
+                    Ok(res)
+                }
+                Err(e) => {
+                    // Rendered here rather than by the server, so the body carries the id
+                    let mut response = e.error_response();
+                    echo_request_id(response.headers_mut(), header_value);
+                    Err(InternalError::from_response(e, response).into())
+                }
+            }
+        }.instrument(span)))
+    }
+}
+
+fn echo_request_id(headers: &mut HeaderMap, value: HeaderValue) {
+    headers.insert(REQUEST_ID_HEADER, value);
+}
+
+fn incoming_request_id(req: &ServiceRequest) -> Option<String> {
+    let value = req.headers().get("X-Request-Id")?.to_str().ok()?.trim();
+    let usable = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.chars().all(|c| c.is_ascii_graphic());
+    usable.then(|| value.to_string())
+}
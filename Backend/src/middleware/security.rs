@@ -65,10 +65,10 @@ where
             }
             
             // Strict Transport Security (only in production)
-            if cfg!(not(debug_assertions)) {
-                if let (Ok(name), Ok(value)) = ("Strict-Transport-Security".parse::<HeaderName>(), "max-age=31536000; includeSubDomains".parse::<HeaderValue>()) {
-                    headers.insert(name, value);
-                }
+            if cfg!(not(debug_assertions))
+                && let (Ok(name), Ok(value)) = ("Strict-Transport-Security".parse::<HeaderName>(), "max-age=31536000; includeSubDomains".parse::<HeaderValue>())
+            {
+                headers.insert(name, value);
             }
             
             // Content Security Policy
@@ -0,0 +1,13 @@
+use serde::Deserialize;
+
+// Delete own account (authenticated, requires the current password)
+#[derive(Deserialize)]
+pub struct DeleteAccountPayload {
+    pub current_password: String,
+}
+
+// Cancellation link token, from the query string or the confirmation form
+#[derive(Deserialize)]
+pub struct CancelDeletionParams {
+    pub token: String,
+}
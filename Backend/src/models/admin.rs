@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 25;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+// What an admin did to an account (`admin_actions.action`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum AdminAction {
+    CreateUser,
+    LockUser,
+    UnlockUser,
+    ForcePasswordReset,
+    SetPassword,
+    VerifyEmail,
+    RevokeSessions,
+    DeleteUser,
+    RestoreUser,
+    StartImpersonation,
+    StopImpersonation,
+}
+
+impl AdminAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminAction::CreateUser => "create_user",
+            AdminAction::LockUser => "lock_user",
+            AdminAction::UnlockUser => "unlock_user",
+            AdminAction::ForcePasswordReset => "force_password_reset",
+            AdminAction::SetPassword => "set_password",
+            AdminAction::VerifyEmail => "verify_email",
+            AdminAction::RevokeSessions => "revoke_sessions",
+            AdminAction::DeleteUser => "delete_user",
+            AdminAction::RestoreUser => "restore_user",
+            AdminAction::StartImpersonation => "start_impersonation",
+            AdminAction::StopImpersonation => "stop_impersonation",
+        }
+    }
+}
+
+// User search filters; every one is optional. Soft-deleted accounts are
+// only listed with `deleted=true`.
+#[derive(Debug, Deserialize)]
+pub struct UserSearchParams {
+    pub email: Option<String>,
+    pub username: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub verified: Option<bool>,
+    pub locked: Option<bool>,
+    #[serde(default)]
+    pub deleted: bool,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+// A user as listed in admin search results
+#[derive(Debug, Serialize)]
+pub struct AdminUserSummary {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub lock_reason: Option<String>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserPage {
+    pub users: Vec<AdminUserSummary>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+// Everything support needs to see about one account
+#[derive(Debug, Serialize)]
+pub struct AdminUserDetail {
+    #[serde(flatten)]
+    pub user: AdminUserSummary,
+    pub display_name: Option<String>,
+    pub password_changed_at: Option<DateTime<Utc>>,
+    pub roles: Vec<String>,
+    pub sessions: Vec<AdminSession>,
+    pub failed_logins: Vec<DateTime<Utc>>,
+    pub password_resets: Vec<AdminPasswordReset>,
+    pub admin_actions: Vec<AdminActionRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminSession {
+    pub id: Uuid,
+    pub organization_id: Option<Uuid>,
+    // The admin, for impersonation sessions
+    pub impersonator_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+// Reset codes themselves are never shown
+#[derive(Debug, Serialize)]
+pub struct AdminPasswordReset {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminActionRecord {
+    pub id: Uuid,
+    // None for actions run through authctl
+    pub actor_id: Option<Uuid>,
+    pub action: AdminAction,
+    pub details: serde_json::Value,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Lock Data; the reason is kept in the audit trail
+#[derive(Debug, Default, Deserialize)]
+pub struct LockUserPayload {
+    pub reason: Option<String>,
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// What happened (`audit_events.event_type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum AuditEventType {
+    Registration,
+    LoginSuccess,
+    LoginFailure,
+    Logout,
+    PasswordResetRequested,
+    PasswordResetCompleted,
+    Lockout,
+    AdminAction,
+    PasswordChanged,
+    EmailChanged,
+    EmailChangeReverted,
+    AccountDeletionScheduled,
+    AccountDeletionCancelled,
+    // The account was purged once its grace period ended
+    AccountDeleted,
+}
+
+impl AuditEventType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEventType::Registration => "registration",
+            AuditEventType::LoginSuccess => "login_success",
+            AuditEventType::LoginFailure => "login_failure",
+            AuditEventType::Logout => "logout",
+            AuditEventType::PasswordResetRequested => "password_reset_requested",
+            AuditEventType::PasswordResetCompleted => "password_reset_completed",
+            AuditEventType::Lockout => "lockout",
+            AuditEventType::AdminAction => "admin_action",
+            AuditEventType::PasswordChanged => "password_changed",
+            AuditEventType::EmailChanged => "email_changed",
+            AuditEventType::EmailChangeReverted => "email_change_reverted",
+            AuditEventType::AccountDeletionScheduled => "account_deletion_scheduled",
+            AuditEventType::AccountDeletionCancelled => "account_deletion_cancelled",
+            AuditEventType::AccountDeleted => "account_deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+        }
+    }
+}
+
+// A stored event. `hash` covers every other field and `prev_hash`.
+#[derive(Debug, Serialize)]
+pub struct AuditEventRecord {
+    pub id: i64,
+    pub event_type: AuditEventType,
+    pub outcome: AuditOutcome,
+    pub actor_id: Option<Uuid>,
+    pub target_user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+// Audit log filters; every one is optional
+#[derive(Debug, Deserialize)]
+pub struct AuditSearchParams {
+    pub event_type: Option<AuditEventType>,
+    pub outcome: Option<AuditOutcome>,
+    pub actor_id: Option<Uuid>,
+    pub target_user_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+// Paging for a user's own events
+#[derive(Debug, Deserialize)]
+pub struct AuditPageParams {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEventPage {
+    pub events: Vec<AuditEventRecord>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
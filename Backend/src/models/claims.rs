@@ -3,6 +3,17 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String, // user id
+    pub sid: String, // session id (row in `sessions`)
+    // active organization id; absent for personal (untenanted) tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    // the admin acting as `sub` (RFC 8693 actor claim); only on impersonation tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActorClaim>,
     pub exp: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActorClaim {
+    pub sub: String, // admin user id
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+// Start an email change (authenticated, requires the current password)
+#[derive(Deserialize)]
+pub struct EmailChangeRequestPayload {
+    pub new_email: String,
+    pub current_password: String,
+}
+
+// Confirmation code sent to the new address
+#[derive(Deserialize)]
+pub struct EmailChangeConfirmPayload {
+    pub code: String,
+}
+
+// Undo link token, from the query string or the confirmation form
+#[derive(Deserialize)]
+pub struct EmailRevertParams {
+    pub token: String,
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::organization::MemberRole;
+
+// Export request status as seen by the user
+#[derive(Debug, Serialize)]
+pub struct DataExportStatus {
+    pub id: Uuid,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+// Signed download link parameters
+#[derive(Deserialize)]
+pub struct ExportDownloadParams {
+    pub expires: i64,
+    pub sig: String,
+}
+
+// Everything stored about a user, as written to the archive
+#[derive(Debug, Serialize)]
+pub struct DataExportArchive {
+    pub generated_at: DateTime<Utc>,
+    pub profile: ExportProfile,
+    // Every login (and registration) opens a session, so this is the login history
+    pub sessions: Vec<ExportSession>,
+    pub failed_logins: Vec<ExportFailedLogin>,
+    pub password_resets: Vec<ExportPasswordReset>,
+    pub password_changes: Vec<DateTime<Utc>>,
+    pub email_changes: Vec<ExportEmailChange>,
+    pub account_deletions: Vec<ExportAccountDeletion>,
+    pub memberships: Vec<ExportMembership>,
+    // Security audit log entries about the user or made by them
+    pub audit_events: Vec<ExportAuditEvent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportProfile {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email_verified: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub password_changed_at: Option<DateTime<Utc>>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub lock_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportSession {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportFailedLogin {
+    pub attempt_time: DateTime<Utc>,
+}
+
+// Reset codes themselves are never exported
+#[derive(Debug, Serialize)]
+pub struct ExportPasswordReset {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportEmailChange {
+    pub old_email: String,
+    pub new_email: String,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub reverted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportAccountDeletion {
+    pub created_at: DateTime<Utc>,
+    pub scheduled_for: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportMembership {
+    pub organization_id: Uuid,
+    pub organization_name: String,
+    pub role: MemberRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportAuditEvent {
+    pub event_type: AuditEventType,
+    pub outcome: AuditOutcome,
+    pub actor_id: Option<Uuid>,
+    pub target_user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Kinds of resources that can be shared (`resource_grants.resource_type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ResourceType {
+    DataExport,
+}
+
+impl ResourceType {
+    // Whether anonymous share links may open the resource.
+    // A data export is the owner's complete personal data, so only named
+    // users and organizations may be given it.
+    pub fn link_shareable(self) -> bool {
+        match self {
+            ResourceType::DataExport => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum GrantPermission {
+    Read,
+    Write,
+}
+
+impl GrantPermission {
+    // Write access includes read access
+    pub fn allows(self, needed: GrantPermission) -> bool {
+        self == GrantPermission::Write || needed == GrantPermission::Read
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum GranteeType {
+    User,
+    Organization,
+    Link,
+}
+
+// Who receives access
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GranteePayload {
+    User { email: String },
+    Organization { id: Uuid },
+    // Anyone holding the signed link; requires `expires_at`
+    Link,
+}
+
+// Create grant Data
+#[derive(Deserialize)]
+pub struct CreateGrantPayload {
+    pub resource_type: ResourceType,
+    pub resource_id: Uuid,
+    pub grantee: GranteePayload,
+    #[serde(default = "default_permission")]
+    pub permission: GrantPermission,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn default_permission() -> GrantPermission {
+    GrantPermission::Read
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceGrant {
+    pub id: Uuid,
+    pub resource_type: ResourceType,
+    pub resource_id: Uuid,
+    pub owner_id: Uuid,
+    pub grantee_type: GranteeType,
+    pub grantee_user_id: Option<Uuid>,
+    pub grantee_organization_id: Option<Uuid>,
+    pub permission: GrantPermission,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    // Signed link for link grants, only returned when the grant is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_url: Option<String>,
+}
+
+// Signed share link parameters
+#[derive(Deserialize)]
+pub struct ShareLinkParams {
+    pub expires: i64,
+    pub sig: String,
+}
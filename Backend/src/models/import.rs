@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+// A user record from a legacy system, carrying an already-hashed password
+#[derive(Debug, Deserialize)]
+pub struct ImportUserRecord {
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
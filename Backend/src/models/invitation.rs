@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::organization::MemberRole;
+
+// Invite Data
+#[derive(Deserialize)]
+pub struct CreateInvitationPayload {
+    pub email: String,
+    #[serde(default = "default_invited_role")]
+    pub role: MemberRole,
+}
+
+fn default_invited_role() -> MemberRole {
+    MemberRole::Member
+}
+
+// Accept Data (the token from the invitation email)
+#[derive(Deserialize)]
+pub struct AcceptInvitationPayload {
+    pub token: String,
+}
+
+// A pending invitation as seen by the organization's managers
+#[derive(Debug, Serialize)]
+pub struct InvitationSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub role: MemberRole,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A live invitation looked up by its token
+#[derive(Debug)]
+pub struct PendingInvitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: MemberRole,
+}
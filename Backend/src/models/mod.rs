@@ -1,6 +1,19 @@
+pub mod account;
+pub mod admin;
+pub mod audit;
 pub mod claims;
+pub mod email_change;
+pub mod export;
+pub mod grant;
+pub mod import;
+pub mod invitation;
 pub mod login;
+pub mod notificationtemplate;
+pub mod organization;
 pub mod otptemplate;
+pub mod pagetemplate;
+pub mod password;
 pub mod reset;
+pub mod role;
 pub mod signup;
 pub mod user;
@@ -0,0 +1,54 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "password_changed.html")]
+pub struct PasswordChangedTemplate<'a> {
+    pub username: &'a str,
+    pub changed_at: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email_change_code.html")]
+pub struct EmailChangeCodeTemplate<'a> {
+    pub code: &'a str,
+    pub expiry_minutes: i64,
+}
+
+#[derive(Template)]
+#[template(path = "email_in_use.html")]
+pub struct EmailInUseTemplate;
+
+#[derive(Template)]
+#[template(path = "email_changed.html")]
+pub struct EmailChangedTemplate<'a> {
+    pub username: &'a str,
+    pub new_email: &'a str,
+    pub revert_url: &'a str,
+    pub revert_ttl_hours: i64,
+}
+
+#[derive(Template)]
+#[template(path = "account_deletion_scheduled.html")]
+pub struct AccountDeletionScheduledTemplate<'a> {
+    pub username: &'a str,
+    pub scheduled_for: &'a str,
+    pub cancel_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "data_export_ready.html")]
+pub struct DataExportReadyTemplate<'a> {
+    pub username: &'a str,
+    pub download_url: &'a str,
+    pub expires_at: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "organization_invitation.html")]
+pub struct OrganizationInvitationTemplate<'a> {
+    pub organization_name: &'a str,
+    pub inviter: &'a str,
+    pub role: &'a str,
+    pub invite_url: &'a str,
+    pub expires_at: &'a str,
+}
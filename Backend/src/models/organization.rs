@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Role within an organization (`memberships.role`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum MemberRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl MemberRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberRole::Owner => "owner",
+            MemberRole::Admin => "admin",
+            MemberRole::Member => "member",
+        }
+    }
+}
+
+// An organization as seen by one of its members
+#[derive(Debug, Serialize)]
+pub struct OrganizationSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub role: MemberRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub role: MemberRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+// Create organization Data; the slug is derived from the name when omitted
+#[derive(Deserialize)]
+pub struct CreateOrganizationPayload {
+    pub name: String,
+    pub slug: Option<String>,
+}
+
+// Scope the current session to an organization (null for personal)
+#[derive(Deserialize)]
+pub struct SwitchOrganizationPayload {
+    pub organization_id: Option<Uuid>,
+}
@@ -0,0 +1,29 @@
+use actix_web::HttpResponse;
+use askama::{DynTemplate, Template};
+
+// Landing page for links sent by email. The action only runs when the form is
+// submitted (POST), so link scanners that prefetch URLs cannot trigger it.
+#[derive(Template)]
+#[template(path = "confirm_action.html")]
+pub struct ConfirmActionPageTemplate<'a> {
+    pub title: &'a str,
+    pub description: &'a str,
+    // Form target, relative to the page URL
+    pub action: &'a str,
+    pub button: &'a str,
+    pub token: &'a str,
+    // Outcome shown instead of the form
+    pub message: Option<&'a str>,
+}
+
+impl ConfirmActionPageTemplate<'_> {
+    pub fn into_response(self) -> HttpResponse {
+        match self.dyn_render() {
+            Ok(html) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(html),
+            Err(e) => {
+                tracing::error!("template error: {}", e);
+                HttpResponse::InternalServerError().finish()
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+// Change password Data (authenticated)
+#[derive(Deserialize)]
+pub struct ChangePasswordPayload {
+    pub current_password: String,
+    pub new_password: String,
+    // Stay signed in on this device; every other session is revoked regardless
+    #[serde(default = "keep_current_session_default")]
+    pub keep_current_session: bool,
+}
+
+fn keep_current_session_default() -> bool {
+    true
+}
@@ -1,5 +1,4 @@
 use serde::Deserialize;
-use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct ResetRequest {
@@ -7,9 +6,9 @@ pub struct ResetRequest {
 }
 
 
+// The account is the one the code was issued to for this email
 #[derive(Deserialize)]
 pub struct ResetVerifyPayload {
-    pub user_id: Uuid,
     pub email: String,
     pub otp: String,
     pub new_password: String,
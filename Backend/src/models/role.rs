@@ -0,0 +1,17 @@
+use serde::Serialize;
+
+// Permission names checked by `require_permission` (seeded by the create_roles migration)
+pub const PERM_USERS_READ: &str = "users:read";
+pub const PERM_USERS_WRITE: &str = "users:write";
+pub const PERM_USERS_DELETE: &str = "users:delete";
+pub const PERM_USERS_IMPERSONATE: &str = "users:impersonate";
+pub const PERM_SESSIONS_REVOKE: &str = "sessions:revoke";
+pub const PERM_ROLES_READ: &str = "roles:read";
+pub const PERM_AUDIT_READ: &str = "audit:read";
+
+#[derive(Debug, Serialize)]
+pub struct RoleSummary {
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<String>,
+}
@@ -6,4 +6,6 @@ pub struct RegisterPayload {
     pub username: String,
     pub email: String,
     pub password: String,
+    // Token from an organization invitation; joins the organization on signup
+    pub invite_token: Option<String>,
 }
\ No newline at end of file
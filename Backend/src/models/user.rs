@@ -3,6 +3,25 @@ use sqlx::FromRow;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+// Why an account is locked (`users.lock_reason`)
+pub const LOCK_EMAIL_CHANGE_REVERTED: &str = "email_change_reverted";
+pub const LOCK_DELETION_SCHEDULED: &str = "deletion_scheduled";
+pub const LOCK_ADMIN: &str = "admin";
+pub const LOCK_PASSWORD_RESET_REQUIRED: &str = "password_reset_required";
+
+/// What a locked user is told when they try to log in
+pub fn lock_message(reason: Option<&str>) -> &'static str {
+    match reason {
+        Some(LOCK_EMAIL_CHANGE_REVERTED) | Some(LOCK_PASSWORD_RESET_REQUIRED) => {
+            "Account is locked. Reset your password to unlock it."
+        }
+        Some(LOCK_DELETION_SCHEDULED) => {
+            "This account is scheduled for deletion. Use the link in the email we sent you to cancel."
+        }
+        _ => "Account is locked. Please contact support.",
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -12,3 +31,22 @@ pub struct User {
     pub created_at: Option<DateTime<Utc>>,
 }
 
+// What the user may see about themselves (never the password hash)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserProfile {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email_verified: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// Profile update Data; omitted fields are left unchanged, "" clears a display field
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfilePayload {
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
@@ -0,0 +1,34 @@
+use actix_web::{web, Scope};
+use crate::auth::handlers::{admin_users, audit, impersonation, roles};
+use crate::auth::permissions::require_permission;
+use crate::models::role::{PERM_AUDIT_READ, PERM_ROLES_READ, PERM_USERS_READ};
+
+/// Operator endpoints; each resource is guarded by the permission it needs.
+/// Changes to users additionally need the permission named on their handler.
+pub fn admin_routes() -> Scope {
+    web::scope("/api/v1/admin")
+        .service(
+            web::scope("/roles")
+                .wrap(require_permission(PERM_ROLES_READ))
+                .service(roles::get_roles),
+        )
+        .service(
+            web::scope("/users")
+                .wrap(require_permission(PERM_USERS_READ))
+                .service(admin_users::list_users)
+                .service(admin_users::get_user)
+                .service(admin_users::lock_user)
+                .service(admin_users::unlock_user)
+                .service(admin_users::force_password_reset)
+                .service(admin_users::verify_email)
+                .service(admin_users::revoke_sessions)
+                .service(admin_users::delete_user)
+                .service(admin_users::restore_user)
+                .service(impersonation::impersonate_user),
+        )
+        .service(
+            web::scope("/audit-events")
+                .wrap(require_permission(PERM_AUDIT_READ))
+                .service(audit::list_events),
+        )
+}
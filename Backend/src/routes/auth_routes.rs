@@ -1,5 +1,5 @@
 use actix_web::{web, Scope};
-use crate::auth::handlers::{login, logout, signup, reset};
+use crate::auth::handlers::{account, email_change, export, grants, login, logout, signup, reset};
 
 /// Auth routes configuration
 /// This module provides route grouping for authentication endpoints
@@ -10,12 +10,30 @@ pub fn auth_routes() -> Scope {
         .service(logout::logout)
         .service(reset::reset_request)
         .service(reset::reset_verify)
+        .service(email_change::revert_email_change_page)
+        .service(email_change::revert_email_change)
+        .service(account::cancel_deletion_page)
+        .service(account::cancel_account_deletion)
+}
+
+/// Downloads authorised by a signed link instead of a session
+pub fn signed_link_routes() -> Scope {
+    web::scope("/api/v1/exports")
+        .service(export::download_export)
+}
+
+/// Resources opened through a signed share link
+pub fn shared_link_routes() -> Scope {
+    web::scope("/api/v1/shared")
+        .service(grants::open_shared_link)
 }
 
 /// Public routes that don't require authentication
 pub fn public_routes() -> Scope {
     web::scope("")
         .service(auth_routes())
+        .service(signed_link_routes())
+        .service(shared_link_routes())
         .route("/health", web::get().to(|| async { "Server is healthy" }))
 }
 
@@ -0,0 +1,10 @@
+use actix_web::{web, Scope};
+use crate::auth::handlers::grants;
+
+/// Sharing the logged-in user's resources with other users, organizations or links
+pub fn grant_routes() -> Scope {
+    web::scope("/api/v1/grants")
+        .service(grants::create_grant)
+        .service(grants::list_grants)
+        .service(grants::revoke_grant)
+}
@@ -1 +1,5 @@
+pub mod admin_routes;
 pub mod auth_routes;
+pub mod grant_routes;
+pub mod org_routes;
+pub mod user_routes;
@@ -0,0 +1,21 @@
+use actix_web::{web, Scope};
+use crate::auth::handlers::{invitations, organizations};
+
+/// Organization (tenant) management for the logged-in user
+pub fn organization_routes() -> Scope {
+    web::scope("/api/v1/orgs")
+        .service(organizations::create_organization)
+        .service(organizations::list_organizations)
+        .service(organizations::switch_active_organization)
+        .service(organizations::list_members)
+        .service(organizations::leave_organization)
+        .service(invitations::create_invitation)
+        .service(invitations::list_invitations)
+        .service(invitations::revoke_invitation)
+}
+
+/// Invitations addressed to the logged-in user
+pub fn invitation_routes() -> Scope {
+    web::scope("/api/v1/invitations")
+        .service(invitations::accept_invitation)
+}
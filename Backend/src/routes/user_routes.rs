@@ -0,0 +1,18 @@
+use actix_web::{web, Scope};
+use crate::auth::handlers::{account, audit, email_change, export, impersonation, password, profile};
+
+/// Routes for the logged-in user
+/// Everything outside /api/v1/auth is guarded by `AuthMiddleware`
+pub fn protected_routes() -> Scope {
+    web::scope("/api/v1/me")
+        .service(profile::get_me)
+        .service(profile::update_me)
+        .service(account::delete_me)
+        .service(password::change_password)
+        .service(email_change::request_email_change)
+        .service(email_change::confirm_email_change)
+        .service(export::request_export)
+        .service(export::export_status)
+        .service(impersonation::end_impersonation)
+        .service(audit::my_events)
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::user::LOCK_DELETION_SCHEDULED;
+use crate::services::audit::{self, AuditEvent};
+
+/// Schedule the user's account for deletion and lock it until then.
+/// Only the hash of the cancellation token is stored. Run it inside a transaction.
+pub async fn schedule_deletion(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    scheduled_for: DateTime<Utc>,
+    cancel_token_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO account_deletions (user_id, scheduled_for, cancel_token_hash)
+        VALUES ($1, $2, $3)
+        "#,
+        user_id,
+        scheduled_for,
+        cancel_token_hash
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE users SET locked_at = NOW(), lock_reason = $1 WHERE id = $2",
+        LOCK_DELETION_SCHEDULED,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Cancel a pending deletion by its cancellation token and unlock the account.
+/// Returns the account, or None when the token is unknown, already used or
+/// past the deadline.
+pub async fn cancel_deletion(pool: &Pool<Postgres>, cancel_token_hash: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let cancelled = sqlx::query_scalar!(
+        r#"
+        UPDATE account_deletions
+        SET cancelled_at = NOW()
+        WHERE cancel_token_hash = $1
+        AND cancelled_at IS NULL
+        AND scheduled_for > NOW()
+        RETURNING user_id
+        "#,
+        cancel_token_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(user_id) = cancelled else {
+        return Ok(None);
+    };
+
+    // Leave locks placed for any other reason in place
+    sqlx::query!(
+        r#"
+        UPDATE users SET locked_at = NULL, lock_reason = NULL
+        WHERE id = $1 AND lock_reason = $2
+        "#,
+        user_id,
+        LOCK_DELETION_SCHEDULED
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(user_id))
+}
+
+/// Hard-delete every account whose grace period has ended, leaving a tombstone
+/// and an audit event. Related rows go with the user through `ON DELETE CASCADE`.
+pub async fn purge_due_accounts(pool: &Pool<Postgres>) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // SKIP LOCKED lets several instances run the job without deleting twice
+    let deleted = sqlx::query_scalar!(
+        r#"
+        WITH due AS (
+            SELECT u.id, u.email,
+                   (SELECT MIN(d.created_at) FROM account_deletions d
+                    WHERE d.user_id = u.id AND d.cancelled_at IS NULL) AS requested_at
+            FROM users u
+            WHERE EXISTS (
+                SELECT 1 FROM account_deletions d
+                WHERE d.user_id = u.id
+                AND d.cancelled_at IS NULL
+                AND d.scheduled_for <= NOW()
+            )
+            FOR UPDATE OF u SKIP LOCKED
+        ),
+        removed AS (
+            DELETE FROM users u USING due WHERE u.id = due.id
+            RETURNING u.id, u.email, due.requested_at
+        )
+        INSERT INTO deleted_accounts (user_id, email_hash, requested_at)
+        SELECT id, encode(sha256(convert_to(LOWER(email), 'UTF8')), 'hex'), COALESCE(requested_at, NOW())
+        FROM removed
+        ON CONFLICT (user_id) DO NOTHING
+        RETURNING user_id
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut exported = Vec::with_capacity(deleted.len());
+    for user_id in &deleted {
+        let event = AuditEvent {
+            target_user_id: Some(*user_id),
+            ..AuditEvent::new(AuditEventType::AccountDeleted, AuditOutcome::Success)
+        };
+        exported.push(audit::append(&mut tx, &event).await?);
+    }
+
+    tx.commit().await?;
+    exported.into_iter().for_each(audit::publish);
+
+    Ok(deleted.len() as u64)
+}
+
+/// Run `purge_due_accounts` every `interval` for the lifetime of the server
+pub fn spawn_purge_job(pool: Pool<Postgres>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match purge_due_accounts(&pool).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!(deleted, "Deleted accounts past their grace period"),
+                Err(e) => tracing::error!("Account deletion job failed: {}", e),
+            }
+        }
+    });
+}
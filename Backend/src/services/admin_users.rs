@@ -0,0 +1,628 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Pool, Postgres, Transaction};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::models::admin::{
+    AdminAction, AdminActionRecord, AdminPasswordReset, AdminSession, AdminUserDetail, AdminUserSummary,
+    UserPage, UserSearchParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::user::{
+    LOCK_ADMIN, LOCK_DELETION_SCHEDULED, LOCK_EMAIL_CHANGE_REVERTED, LOCK_PASSWORD_RESET_REQUIRED,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::email_changes::is_email_taken;
+use crate::services::password_history;
+use crate::services::security_events::SecurityEvent;
+use crate::services::sessions::{create_session, default_organization, revoke_user_sessions};
+
+/// Who performs an admin action: an admin through the API, or an operator
+/// through `authctl` (no account)
+#[derive(Debug, Clone, Default)]
+pub struct Actor {
+    pub user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum AdminError {
+    NotFound,
+    // The action does not apply to the account in its current state
+    Conflict(&'static str),
+    // Admins may not do this to their own account
+    OwnAccount(&'static str),
+    // The account holds privileges the action must not hand over
+    Privileged(&'static str),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::NotFound => write!(f, "User not found"),
+            AdminError::Conflict(message) | AdminError::OwnAccount(message) | AdminError::Privileged(message) => {
+                write!(f, "{}", message)
+            }
+            AdminError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<sqlx::Error> for AdminError {
+    fn from(e: sqlx::Error) -> Self {
+        AdminError::Database(e)
+    }
+}
+
+/// A session opened by `start_impersonation`, to issue the token for
+#[derive(Debug)]
+pub struct ImpersonationSession {
+    pub session_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+}
+
+// Account state an admin action depends on, locked for the rest of the transaction
+struct TargetAccount {
+    email_verified: bool,
+    locked_at: Option<DateTime<Utc>>,
+    lock_reason: Option<String>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+// Rows of each history shown in the user detail
+const DETAIL_HISTORY_LIMIT: i64 = 50;
+
+/// One page of users matching every given filter, newest first
+pub async fn search_users(pool: &Pool<Postgres>, params: &UserSearchParams) -> Result<UserPage, sqlx::Error> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let email = params.email.as_deref().map(contains_pattern);
+    let username = params.username.as_deref().map(contains_pattern);
+
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM users
+        WHERE ($1::text IS NULL OR email ILIKE $1)
+        AND ($2::text IS NULL OR username ILIKE $2)
+        AND ($3::timestamptz IS NULL OR created_at >= $3)
+        AND ($4::timestamptz IS NULL OR created_at < $4)
+        AND ($5::bool IS NULL OR COALESCE(email_verified, FALSE) = $5)
+        AND ($6::bool IS NULL OR (locked_at IS NOT NULL) = $6)
+        AND (deleted_at IS NOT NULL) = $7
+        "#,
+        email,
+        username,
+        params.created_after,
+        params.created_before,
+        params.verified,
+        params.locked,
+        params.deleted
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let users = sqlx::query_as!(
+        AdminUserSummary,
+        r#"
+        SELECT id, username, email, COALESCE(email_verified, FALSE) AS "email_verified!",
+               created_at, locked_at, lock_reason, deleted_at
+        FROM users
+        WHERE ($1::text IS NULL OR email ILIKE $1)
+        AND ($2::text IS NULL OR username ILIKE $2)
+        AND ($3::timestamptz IS NULL OR created_at >= $3)
+        AND ($4::timestamptz IS NULL OR created_at < $4)
+        AND ($5::bool IS NULL OR COALESCE(email_verified, FALSE) = $5)
+        AND ($6::bool IS NULL OR (locked_at IS NOT NULL) = $6)
+        AND (deleted_at IS NOT NULL) = $7
+        ORDER BY created_at DESC, id
+        LIMIT $8 OFFSET $9
+        "#,
+        email,
+        username,
+        params.created_after,
+        params.created_before,
+        params.verified,
+        params.locked,
+        params.deleted,
+        per_page,
+        (page - 1) * per_page
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(UserPage { users, page, per_page, total })
+}
+
+/// The account with its roles, recent sessions, failed logins, reset requests
+/// and the admin actions taken on it. `None` when there is no such user.
+pub async fn user_detail(pool: &Pool<Postgres>, user_id: Uuid) -> Result<Option<AdminUserDetail>, sqlx::Error> {
+    let Some(user) = sqlx::query!(
+        r#"
+        SELECT id, username, email, COALESCE(email_verified, FALSE) AS "email_verified!",
+               created_at, locked_at, lock_reason, deleted_at, display_name, password_changed_at
+        FROM users WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let roles = sqlx::query_scalar!(
+        r#"
+        SELECT r.name FROM user_roles ur
+        JOIN roles r ON r.id = ur.role_id
+        WHERE ur.user_id = $1
+        ORDER BY r.name
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let sessions = sqlx::query_as!(
+        AdminSession,
+        r#"
+        SELECT id, organization_id, impersonator_id, created_at, expires_at, revoked_at, ip_address, user_agent
+        FROM sessions WHERE user_id = $1
+        ORDER BY created_at DESC LIMIT $2
+        "#,
+        user_id,
+        DETAIL_HISTORY_LIMIT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let failed_logins = sqlx::query_scalar!(
+        "SELECT attempt_time FROM failed_logins WHERE user_id = $1 ORDER BY attempt_time DESC LIMIT $2",
+        user_id,
+        DETAIL_HISTORY_LIMIT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let password_resets = sqlx::query_as!(
+        AdminPasswordReset,
+        r#"
+        SELECT created_at, expires_at, used FROM password_resets
+        WHERE user_id = $1
+        ORDER BY created_at DESC LIMIT $2
+        "#,
+        user_id,
+        DETAIL_HISTORY_LIMIT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let admin_actions = sqlx::query_as!(
+        AdminActionRecord,
+        r#"
+        SELECT id, actor_id, action AS "action: AdminAction", details, ip_address, created_at
+        FROM admin_actions WHERE target_user_id = $1
+        ORDER BY created_at DESC LIMIT $2
+        "#,
+        user_id,
+        DETAIL_HISTORY_LIMIT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(AdminUserDetail {
+        user: AdminUserSummary {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            email_verified: user.email_verified,
+            created_at: user.created_at,
+            locked_at: user.locked_at,
+            lock_reason: user.lock_reason,
+            deleted_at: user.deleted_at,
+        },
+        display_name: user.display_name,
+        password_changed_at: user.password_changed_at,
+        roles,
+        sessions,
+        failed_logins,
+        password_resets,
+        admin_actions,
+    }))
+}
+
+/// Create an account with an already hashed password, bypassing signup
+/// (operators only: no verification email is sent)
+pub async fn create_user(
+    pool: &Pool<Postgres>,
+    actor: &Actor,
+    username: &str,
+    email: &str,
+    password_hash: &str,
+    email_verified: bool,
+    history_size: usize,
+) -> Result<Uuid, AdminError> {
+    if is_email_taken(pool, email).await? {
+        return Err(AdminError::Conflict("An account with this email address already exists"));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let user_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO users (id, username, email, password_hash, email_verified, created_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        username,
+        email,
+        password_hash,
+        email_verified
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AdminError::Conflict("An account with this email address already exists")
+        }
+        e => AdminError::Database(e),
+    })?;
+
+    password_history::record(&mut tx, user_id, password_hash, history_size).await?;
+
+    let details = serde_json::json!({ "email_verified": email_verified });
+    finish_action(tx, actor, AdminAction::CreateUser, user_id, details).await?;
+    Ok(user_id)
+}
+
+/// Lock the account and sign it out everywhere
+pub async fn lock_user(
+    pool: &Pool<Postgres>,
+    actor: &Actor,
+    user_id: Uuid,
+    reason: Option<String>,
+) -> Result<(), AdminError> {
+    if actor.user_id == Some(user_id) {
+        return Err(AdminError::OwnAccount("You cannot lock your own account"));
+    }
+
+    let (mut tx, target) = begin_action(pool, user_id).await?;
+    if target.locked_at.is_some() {
+        return Err(AdminError::Conflict("This account is already locked"));
+    }
+
+    sqlx::query!(
+        "UPDATE users SET locked_at = NOW(), lock_reason = $1 WHERE id = $2",
+        LOCK_ADMIN,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    revoke_user_sessions(&mut *tx, user_id, None).await?;
+
+    let details = serde_json::json!({ "reason": reason });
+    finish_action(tx, actor, AdminAction::LockUser, user_id, details).await
+}
+
+/// Lift any lock except a pending self-service deletion (the user cancels that)
+pub async fn unlock_user(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<(), AdminError> {
+    let (mut tx, target) = begin_action(pool, user_id).await?;
+    if target.locked_at.is_none() {
+        return Err(AdminError::Conflict("This account is not locked"));
+    }
+    if target.lock_reason.as_deref() == Some(LOCK_DELETION_SCHEDULED) {
+        return Err(AdminError::Conflict("This account is scheduled for deletion"));
+    }
+
+    sqlx::query!(
+        "UPDATE users SET locked_at = NULL, lock_reason = NULL WHERE id = $1",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    // Let the user back in right away
+    sqlx::query!("DELETE FROM failed_logins WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let details = serde_json::json!({ "lock_reason": target.lock_reason });
+    finish_action(tx, actor, AdminAction::UnlockUser, user_id, details).await
+}
+
+/// Sign the user out and lock the account until they reset their password
+pub async fn force_password_reset(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<(), AdminError> {
+    if actor.user_id == Some(user_id) {
+        return Err(AdminError::OwnAccount("You cannot force a password reset on your own account"));
+    }
+
+    let (mut tx, target) = begin_action(pool, user_id).await?;
+    // A reset would lift the lock, so it must not replace a stronger one
+    if target.locked_at.is_some() {
+        return Err(AdminError::Conflict("This account is already locked"));
+    }
+
+    sqlx::query!(
+        "UPDATE users SET locked_at = NOW(), lock_reason = $1 WHERE id = $2",
+        LOCK_PASSWORD_RESET_REQUIRED,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    revoke_user_sessions(&mut *tx, user_id, None).await?;
+
+    finish_action(tx, actor, AdminAction::ForcePasswordReset, user_id, serde_json::json!({})).await
+}
+
+/// Replace the password with an already hashed one, the way a reset would:
+/// lifts reset locks, records the history and signs the user out everywhere
+pub async fn set_password(
+    pool: &Pool<Postgres>,
+    actor: &Actor,
+    user_id: Uuid,
+    password_hash: &str,
+    history_size: usize,
+) -> Result<(), AdminError> {
+    let (mut tx, _) = begin_action(pool, user_id).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE users SET
+            password_hash = $1,
+            password_changed_at = NOW(),
+            locked_at = CASE WHEN lock_reason IN ($3, $4) THEN NULL ELSE locked_at END,
+            lock_reason = CASE WHEN lock_reason IN ($3, $4) THEN NULL ELSE lock_reason END
+        WHERE id = $2
+        "#,
+        password_hash,
+        user_id,
+        LOCK_EMAIL_CHANGE_REVERTED,
+        LOCK_PASSWORD_RESET_REQUIRED
+    )
+    .execute(&mut *tx)
+    .await?;
+    revoke_user_sessions(&mut *tx, user_id, None).await?;
+    password_history::record(&mut tx, user_id, password_hash, history_size).await?;
+
+    finish_action(tx, actor, AdminAction::SetPassword, user_id, serde_json::json!({})).await
+}
+
+/// Mark the email address verified (e.g. after checking it out of band)
+pub async fn verify_email(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<(), AdminError> {
+    let (mut tx, target) = begin_action(pool, user_id).await?;
+    if target.email_verified {
+        return Err(AdminError::Conflict("This email address is already verified"));
+    }
+
+    sqlx::query!("UPDATE users SET email_verified = TRUE WHERE id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    finish_action(tx, actor, AdminAction::VerifyEmail, user_id, serde_json::json!({})).await
+}
+
+/// Sign the user out of every session. Returns the number revoked.
+pub async fn revoke_sessions(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<u64, AdminError> {
+    let (mut tx, _) = begin_action(pool, user_id).await?;
+
+    let revoked = revoke_user_sessions(&mut *tx, user_id, None).await?;
+
+    let details = serde_json::json!({ "revoked": revoked });
+    finish_action(tx, actor, AdminAction::RevokeSessions, user_id, details).await?;
+    Ok(revoked)
+}
+
+/// Soft-delete: the account can no longer sign in, but nothing is removed
+pub async fn delete_user(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<(), AdminError> {
+    if actor.user_id == Some(user_id) {
+        return Err(AdminError::OwnAccount("You cannot delete your own account here"));
+    }
+
+    let (mut tx, _) = begin_action(pool, user_id).await?;
+
+    sqlx::query!("UPDATE users SET deleted_at = NOW() WHERE id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+    revoke_user_sessions(&mut *tx, user_id, None).await?;
+
+    finish_action(tx, actor, AdminAction::DeleteUser, user_id, serde_json::json!({})).await
+}
+
+/// Undo a soft delete
+pub async fn restore_user(pool: &Pool<Postgres>, actor: &Actor, user_id: Uuid) -> Result<(), AdminError> {
+    let mut tx = pool.begin().await?;
+
+    match load_target(&mut tx, user_id).await? {
+        Some(target) if target.deleted_at.is_some() => {}
+        Some(_) => return Err(AdminError::Conflict("This account is not deleted")),
+        None => return Err(AdminError::NotFound),
+    }
+
+    sqlx::query!("UPDATE users SET deleted_at = NULL WHERE id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    finish_action(tx, actor, AdminAction::RestoreUser, user_id, serde_json::json!({})).await
+}
+
+/// Open a short-lived session as the user on behalf of the acting admin.
+/// Locked and deleted accounts cannot be impersonated, nor can accounts
+/// holding an admin role or any permission: the session would carry them.
+pub async fn start_impersonation(
+    pool: &Pool<Postgres>,
+    actor: &Actor,
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+    user_agent: Option<&str>,
+) -> Result<ImpersonationSession, AdminError> {
+    let Some(admin_id) = actor.user_id else {
+        return Err(AdminError::Conflict("Only an admin account can impersonate users"));
+    };
+    if admin_id == user_id {
+        return Err(AdminError::OwnAccount("You cannot impersonate yourself"));
+    }
+
+    let (mut tx, target) = begin_action(pool, user_id).await?;
+    if target.locked_at.is_some() {
+        return Err(AdminError::Conflict("Locked accounts cannot be impersonated"));
+    }
+    if is_privileged(&mut tx, user_id).await? {
+        return Err(AdminError::Privileged("Accounts with admin roles or permissions cannot be impersonated"));
+    }
+
+    let organization_id = default_organization(&mut *tx, user_id).await?;
+    let session_id = create_session(
+        &mut *tx,
+        user_id,
+        organization_id,
+        Some(admin_id),
+        expires_at,
+        actor.ip_address.as_deref(),
+        user_agent,
+    )
+    .await?;
+
+    let details = serde_json::json!({ "session_id": session_id, "expires_at": expires_at });
+    finish_action(tx, actor, AdminAction::StartImpersonation, user_id, details).await?;
+    Ok(ImpersonationSession { session_id, organization_id, expires_at })
+}
+
+/// End an impersonation session the acting admin started.
+/// Returns false when it had already ended.
+pub async fn stop_impersonation(
+    pool: &Pool<Postgres>,
+    actor: &Actor,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<bool, AdminError> {
+    let mut tx = pool.begin().await?;
+
+    let revoked = sqlx::query!(
+        r#"
+        UPDATE sessions SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND impersonator_id = $3 AND revoked_at IS NULL
+        "#,
+        session_id,
+        user_id,
+        actor.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if revoked.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let details = serde_json::json!({ "session_id": session_id });
+    finish_action(tx, actor, AdminAction::StopImpersonation, user_id, details).await?;
+    Ok(true)
+}
+
+/// Append an entry to the admin audit trail and the security audit log.
+/// Run it in the same transaction as the change it records, and publish
+/// the returned event once that commits.
+pub async fn record_action(
+    conn: &mut PgConnection,
+    actor: &Actor,
+    action: AdminAction,
+    target_user_id: Uuid,
+    details: serde_json::Value,
+) -> Result<SecurityEvent, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO admin_actions (actor_id, action, target_user_id, details, ip_address)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        actor.user_id,
+        action as AdminAction,
+        target_user_id,
+        details,
+        actor.ip_address
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let event = AuditEvent {
+        actor_id: actor.user_id,
+        target_user_id: Some(target_user_id),
+        ip_address: actor.ip_address.clone(),
+        details: serde_json::json!({ "action": action.as_str(), "details": details }),
+        ..AuditEvent::new(AuditEventType::AdminAction, AuditOutcome::Success)
+    };
+    audit::append(conn, &event).await
+}
+
+async fn load_target(conn: &mut PgConnection, user_id: Uuid) -> Result<Option<TargetAccount>, sqlx::Error> {
+    sqlx::query_as!(
+        TargetAccount,
+        r#"
+        SELECT COALESCE(email_verified, FALSE) AS "email_verified!", locked_at, lock_reason, deleted_at
+        FROM users WHERE id = $1
+        FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// Open the transaction and load a live (not soft-deleted) target account
+// Holds the admin role or any role that grants a permission
+async fn is_privileged(conn: &mut PgConnection, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM user_roles ur
+            JOIN roles r ON r.id = ur.role_id
+            WHERE ur.user_id = $1
+              AND (r.name = 'admin' OR EXISTS (SELECT 1 FROM role_permissions rp WHERE rp.role_id = r.id))
+        ) AS "privileged!"
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+}
+
+async fn begin_action(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+) -> Result<(Transaction<'static, Postgres>, TargetAccount), AdminError> {
+    let mut tx = pool.begin().await?;
+
+    match load_target(&mut tx, user_id).await? {
+        Some(target) if target.deleted_at.is_some() => Err(AdminError::Conflict("This account is deleted")),
+        Some(target) => Ok((tx, target)),
+        None => Err(AdminError::NotFound),
+    }
+}
+
+// Audit the action and commit it together with the change
+async fn finish_action(
+    mut tx: Transaction<'static, Postgres>,
+    actor: &Actor,
+    action: AdminAction,
+    user_id: Uuid,
+    details: serde_json::Value,
+) -> Result<(), AdminError> {
+    let exported = record_action(&mut tx, actor, action, user_id, details).await?;
+    tx.commit().await?;
+    audit::publish(exported);
+
+    tracing::warn!(admin_id = ?actor.user_id, user_id = %user_id, ?action, "Admin action");
+    Ok(())
+}
+
+// Case-insensitive substring match with LIKE wildcards in the input escaped
+fn contains_pattern(term: &str) -> String {
+    let escaped = term
+        .trim()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
@@ -0,0 +1,448 @@
+use actix_web::{http::header::USER_AGENT, HttpRequest};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgExecutor, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::middleware::request_id::current_request_id;
+use crate::models::admin::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::models::audit::{AuditEventPage, AuditEventRecord, AuditEventType, AuditOutcome, AuditSearchParams};
+use crate::services::security_events::{self, SecurityEvent, SCHEMA_VERSION};
+
+/// `prev_hash` of the first event in the chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Advisory lock key serializing appends, so each event links to the last committed one
+const CHAIN_LOCK_KEY: i64 = 0x6175_6469_745f_6c6f;
+
+// Events read per query while verifying the chain
+const VERIFY_BATCH_SIZE: i64 = 1000;
+
+/// A security event about to be appended to the audit log
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub event_type: AuditEventType,
+    pub outcome: AuditOutcome,
+    // Who did it, when not the target themselves (an admin)
+    pub actor_id: Option<Uuid>,
+    pub target_user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    // Id of the request that caused the event, passed on to the SIEM
+    pub request_id: Option<String>,
+    pub details: serde_json::Value,
+}
+
+impl AuditEvent {
+    pub fn new(event_type: AuditEventType, outcome: AuditOutcome) -> Self {
+        Self {
+            event_type,
+            outcome,
+            actor_id: None,
+            target_user_id: None,
+            ip_address: None,
+            user_agent: None,
+            // Events recorded while handling a request are tied to it
+            request_id: current_request_id(),
+            details: serde_json::json!({}),
+        }
+    }
+
+    /// An event carrying the client address and user agent of the request
+    pub fn from_request(req: &HttpRequest, event_type: AuditEventType, outcome: AuditOutcome) -> Self {
+        Self {
+            ip_address: req.connection_info().realip_remote_addr().map(str::to_string),
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            ..Self::new(event_type, outcome)
+        }
+    }
+}
+
+/// Result of walking the chain with `verify_chain`
+#[derive(Debug, Serialize)]
+pub struct ChainReport {
+    pub checked: u64,
+    // Hash of the newest event; compare it with one noted earlier to detect
+    // events removed from the end of the log
+    pub head: Option<String>,
+    pub broken: Option<ChainBreak>,
+}
+
+/// The first event that does not match the chain
+#[derive(Debug, Serialize)]
+pub struct ChainBreak {
+    pub id: i64,
+    pub reason: &'static str,
+}
+
+/// Append the event to the chain. Run it inside the transaction of the change
+/// it records: the chain lock is held until that transaction ends. Once that
+/// commits, `publish` the returned event for the SIEM.
+pub async fn append(conn: &mut PgConnection, event: &AuditEvent) -> Result<SecurityEvent, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(CHAIN_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let prev_hash = sqlx::query_scalar!("SELECT hash FROM audit_events ORDER BY id DESC LIMIT 1")
+        .fetch_optional(&mut *conn)
+        .await?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    // Postgres keeps microseconds; hash what will be read back
+    let created_at = Utc::now().trunc_subsecs(6);
+    let hash = chain_hash(&prev_hash, event, created_at);
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO audit_events (
+            event_type, outcome, actor_id, target_user_id, ip_address,
+            user_agent, request_id, details, created_at, prev_hash, hash
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id
+        "#,
+        event.event_type as AuditEventType,
+        event.outcome as AuditOutcome,
+        event.actor_id,
+        event.target_user_id,
+        event.ip_address,
+        event.user_agent,
+        event.request_id,
+        event.details,
+        created_at,
+        prev_hash,
+        hash
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(SecurityEvent {
+        schema_version: SCHEMA_VERSION,
+        id,
+        timestamp: created_at,
+        event_type: event.event_type,
+        outcome: event.outcome,
+        actor_id: event.actor_id,
+        target_user_id: event.target_user_id,
+        ip_address: event.ip_address.clone(),
+        user_agent: event.user_agent.clone(),
+        correlation_id: event.request_id.clone(),
+        details: event.details.clone(),
+    })
+}
+
+/// Hand a committed event to the SIEM export, when it is enabled
+pub fn publish(event: SecurityEvent) {
+    security_events::publish(event);
+}
+
+/// Append the event in a transaction of its own. Failures are logged, not
+/// returned, so auditing never turns a sign-in into an error.
+pub async fn record(pool: &Pool<Postgres>, event: AuditEvent) {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let exported = append(&mut tx, &event).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(exported)
+    }
+    .await;
+
+    match result {
+        Ok(exported) => publish(exported),
+        Err(e) => tracing::error!(event_type = event.event_type.as_str(), "audit event error: {}", e),
+    }
+}
+
+/// One page of events matching every given filter, newest first
+pub async fn search_events(pool: &Pool<Postgres>, params: &AuditSearchParams) -> Result<AuditEventPage, sqlx::Error> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let event_type = params.event_type.map(AuditEventType::as_str);
+    let outcome = params.outcome.map(AuditOutcome::as_str);
+
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM audit_events
+        WHERE ($1::text IS NULL OR event_type = $1)
+        AND ($2::text IS NULL OR outcome = $2)
+        AND ($3::uuid IS NULL OR actor_id = $3)
+        AND ($4::uuid IS NULL OR target_user_id = $4)
+        AND ($5::text IS NULL OR request_id = $5)
+        AND ($6::timestamptz IS NULL OR created_at >= $6)
+        AND ($7::timestamptz IS NULL OR created_at < $7)
+        "#,
+        event_type,
+        outcome,
+        params.actor_id,
+        params.target_user_id,
+        params.request_id,
+        params.since,
+        params.until
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let events = sqlx::query_as!(
+        AuditEventRecord,
+        r#"
+        SELECT id, event_type AS "event_type: AuditEventType", outcome AS "outcome: AuditOutcome",
+               actor_id, target_user_id, ip_address, user_agent, request_id, details, created_at, prev_hash, hash
+        FROM audit_events
+        WHERE ($1::text IS NULL OR event_type = $1)
+        AND ($2::text IS NULL OR outcome = $2)
+        AND ($3::uuid IS NULL OR actor_id = $3)
+        AND ($4::uuid IS NULL OR target_user_id = $4)
+        AND ($5::text IS NULL OR request_id = $5)
+        AND ($6::timestamptz IS NULL OR created_at >= $6)
+        AND ($7::timestamptz IS NULL OR created_at < $7)
+        ORDER BY id DESC
+        LIMIT $8 OFFSET $9
+        "#,
+        event_type,
+        outcome,
+        params.actor_id,
+        params.target_user_id,
+        params.request_id,
+        params.since,
+        params.until,
+        per_page,
+        (page - 1) * per_page
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(AuditEventPage { events, page, per_page, total })
+}
+
+/// One page of the events about a user, newest first
+pub async fn user_events(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<AuditEventPage, sqlx::Error> {
+    let page = page.unwrap_or(1).max(1);
+    let per_page = per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM audit_events WHERE target_user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let events = sqlx::query_as!(
+        AuditEventRecord,
+        r#"
+        SELECT id, event_type AS "event_type: AuditEventType", outcome AS "outcome: AuditOutcome",
+               actor_id, target_user_id, ip_address, user_agent, request_id, details, created_at, prev_hash, hash
+        FROM audit_events
+        WHERE target_user_id = $1
+        ORDER BY id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        user_id,
+        per_page,
+        (page - 1) * per_page
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(AuditEventPage { events, page, per_page, total })
+}
+
+/// Walk the whole chain from the first event and recompute every hash.
+/// Stops at the first event that was changed, or whose predecessor was
+/// removed, inserted or changed.
+pub async fn verify_chain(pool: &Pool<Postgres>) -> Result<ChainReport, sqlx::Error> {
+    let mut expected_prev = GENESIS_HASH.to_string();
+    let mut checked = 0;
+    let mut last_id = 0;
+
+    loop {
+        let batch = events_after(pool, last_id).await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = last.id;
+
+        for record in batch {
+            let broken = if record.prev_hash != expected_prev {
+                Some("does not link to the previous event")
+            } else if chain_hash(&record.prev_hash, &AuditEvent::from(&record), record.created_at) != record.hash {
+                Some("does not match its hash")
+            } else {
+                None
+            };
+
+            if let Some(reason) = broken {
+                return Ok(ChainReport {
+                    checked,
+                    head: None,
+                    broken: Some(ChainBreak { id: record.id, reason }),
+                });
+            }
+
+            checked += 1;
+            expected_prev = record.hash;
+        }
+    }
+
+    Ok(ChainReport {
+        checked,
+        head: (checked > 0).then_some(expected_prev),
+        broken: None,
+    })
+}
+
+impl From<&AuditEventRecord> for AuditEvent {
+    fn from(record: &AuditEventRecord) -> Self {
+        Self {
+            event_type: record.event_type,
+            outcome: record.outcome,
+            actor_id: record.actor_id,
+            target_user_id: record.target_user_id,
+            ip_address: record.ip_address.clone(),
+            user_agent: record.user_agent.clone(),
+            request_id: record.request_id.clone(),
+            details: record.details.clone(),
+        }
+    }
+}
+
+async fn events_after<'e>(executor: impl PgExecutor<'e>, id: i64) -> Result<Vec<AuditEventRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        AuditEventRecord,
+        r#"
+        SELECT id, event_type AS "event_type: AuditEventType", outcome AS "outcome: AuditOutcome",
+               actor_id, target_user_id, ip_address, user_agent, request_id, details, created_at, prev_hash, hash
+        FROM audit_events
+        WHERE id > $1
+        ORDER BY id
+        LIMIT $2
+        "#,
+        id,
+        VERIFY_BATCH_SIZE
+    )
+    .fetch_all(executor)
+    .await
+}
+
+// SHA-256 over the previous hash and every field, as a JSON array so field
+// boundaries are unambiguous. JSONB keeps no key order; serde_json sorts keys.
+// The request id, added later, is appended only when set, so events recorded
+// before it still verify.
+fn chain_hash(prev_hash: &str, event: &AuditEvent, created_at: DateTime<Utc>) -> String {
+    let mut canonical = serde_json::json!([
+        prev_hash,
+        event.event_type.as_str(),
+        event.outcome.as_str(),
+        event.actor_id,
+        event.target_user_id,
+        event.ip_address,
+        event.user_agent,
+        event.details,
+        created_at.timestamp_micros(),
+    ]);
+    if let (Some(request_id), Some(fields)) = (&event.request_id, canonical.as_array_mut()) {
+        fields.push(serde_json::json!(request_id));
+    }
+
+    Sha256::digest(canonical.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event() -> AuditEvent {
+        AuditEvent {
+            event_type: AuditEventType::LoginFailure,
+            outcome: AuditOutcome::Failure,
+            actor_id: None,
+            target_user_id: Some(Uuid::nil()),
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: Some("curl/8.0".to_string()),
+            request_id: None,
+            details: serde_json::json!({ "reason": "invalid_password", "attempts": 3 }),
+        }
+    }
+
+    fn created_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 30, 5).unwrap()
+    }
+
+    #[test]
+    fn hash_is_deterministic() {
+        let hash = chain_hash(GENESIS_HASH, &event(), created_at());
+        assert_eq!(hash, chain_hash(GENESIS_HASH, &event(), created_at()));
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let with_request_id = AuditEvent { request_id: Some("req-1".to_string()), ..event() };
+        let hash = chain_hash(GENESIS_HASH, &with_request_id, created_at());
+        assert_eq!(hash, chain_hash(GENESIS_HASH, &with_request_id, created_at()));
+    }
+
+    #[test]
+    fn hash_ignores_detail_key_order() {
+        let reordered = AuditEvent {
+            details: serde_json::from_str(r#"{"attempts": 3, "reason": "invalid_password"}"#).unwrap(),
+            ..event()
+        };
+        assert_eq!(
+            chain_hash(GENESIS_HASH, &event(), created_at()),
+            chain_hash(GENESIS_HASH, &reordered, created_at())
+        );
+    }
+
+    #[test]
+    fn events_without_request_id_hash_as_before_it_existed() {
+        let canonical = serde_json::json!([
+            GENESIS_HASH,
+            "login_failure",
+            "failure",
+            null,
+            Uuid::nil(),
+            "203.0.113.7",
+            "curl/8.0",
+            { "reason": "invalid_password", "attempts": 3 },
+            created_at().timestamp_micros(),
+        ]);
+        let expected: String = Sha256::digest(canonical.to_string().as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        assert_eq!(chain_hash(GENESIS_HASH, &event(), created_at()), expected);
+    }
+
+    #[test]
+    fn hash_covers_every_field() {
+        let base = chain_hash(GENESIS_HASH, &event(), created_at());
+        let changed = [
+            AuditEvent { outcome: AuditOutcome::Success, ..event() },
+            AuditEvent { actor_id: Some(Uuid::nil()), ..event() },
+            AuditEvent { target_user_id: None, ..event() },
+            AuditEvent { ip_address: None, ..event() },
+            AuditEvent { user_agent: Some("curl/8.1".to_string()), ..event() },
+            AuditEvent { request_id: Some("req-1".to_string()), ..event() },
+            AuditEvent { details: serde_json::json!({}), ..event() },
+        ];
+        for changed in &changed {
+            assert_ne!(chain_hash(GENESIS_HASH, changed, created_at()), base, "{:?}", changed);
+        }
+        assert_ne!(chain_hash(&"1".repeat(64), &event(), created_at()), base);
+        assert_ne!(chain_hash(GENESIS_HASH, &event(), created_at() + chrono::Duration::microseconds(1)), base);
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Pool, Postgres};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::config::app::AppConfig;
+use crate::config::export::ExportConfig;
+use crate::middleware::request_id::with_request_context;
+use crate::models::audit::{AuditEventType, AuditOutcome};
+use crate::models::export::{
+    DataExportArchive, ExportAccountDeletion, ExportAuditEvent, ExportEmailChange, ExportFailedLogin,
+    ExportMembership, ExportPasswordReset, ExportProfile, ExportSession,
+};
+use crate::models::organization::MemberRole;
+use crate::utils::email::{send_data_export_ready_email, spawn_email};
+use crate::utils::token::{sign, verify_signature};
+
+/// Gather everything stored about the user. Secrets (password hashes, reset
+/// codes, token hashes) are left out.
+pub async fn collect_user_data(pool: &Pool<Postgres>, user_id: Uuid) -> Result<DataExportArchive, sqlx::Error> {
+    let profile = sqlx::query_as!(
+        ExportProfile,
+        r#"
+        SELECT id, username, email, display_name, avatar_url,
+               COALESCE(email_verified, FALSE) AS "email_verified!", created_at,
+               password_changed_at, locked_at, lock_reason
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let sessions = sqlx::query_as!(
+        ExportSession,
+        r#"
+        SELECT created_at, expires_at, revoked_at, ip_address, user_agent
+        FROM sessions WHERE user_id = $1 ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let failed_logins = sqlx::query_as!(
+        ExportFailedLogin,
+        "SELECT attempt_time FROM failed_logins WHERE user_id = $1 ORDER BY attempt_time",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let password_resets = sqlx::query_as!(
+        ExportPasswordReset,
+        r#"
+        SELECT created_at, expires_at, used
+        FROM password_resets WHERE user_id = $1 ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let password_changes = sqlx::query_scalar!(
+        "SELECT created_at FROM password_history WHERE user_id = $1 ORDER BY created_at",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let email_changes = sqlx::query_as!(
+        ExportEmailChange,
+        r#"
+        SELECT old_email, new_email, created_at, confirmed_at, reverted_at
+        FROM email_changes WHERE user_id = $1 ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let account_deletions = sqlx::query_as!(
+        ExportAccountDeletion,
+        r#"
+        SELECT created_at, scheduled_for, cancelled_at
+        FROM account_deletions WHERE user_id = $1 ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let memberships = sqlx::query_as!(
+        ExportMembership,
+        r#"
+        SELECT o.id AS organization_id, o.name AS organization_name,
+               m.role AS "role: MemberRole", m.created_at AS joined_at
+        FROM memberships m
+        JOIN organizations o ON o.id = m.organization_id
+        WHERE m.user_id = $1
+        ORDER BY m.created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Sign-ins, failures, lockouts and account changes, and what the user did as an admin
+    let audit_events = sqlx::query_as!(
+        ExportAuditEvent,
+        r#"
+        SELECT event_type AS "event_type: AuditEventType", outcome AS "outcome: AuditOutcome",
+               actor_id, target_user_id, ip_address, user_agent, request_id, details, created_at
+        FROM audit_events
+        WHERE target_user_id = $1 OR actor_id = $1
+        ORDER BY id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(DataExportArchive {
+        generated_at: Utc::now(),
+        profile,
+        sessions,
+        failed_logins,
+        password_resets,
+        password_changes,
+        email_changes,
+        account_deletions,
+        memberships,
+        audit_events,
+    })
+}
+
+/// Signed download link for a ready export, valid until `expires_at`
+pub fn download_url(
+    app_config: &AppConfig,
+    config: &ExportConfig,
+    export_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> String {
+    let expires = expires_at.timestamp();
+    format!(
+        "{}/api/v1/exports/{}?expires={}&sig={}",
+        app_config.public_url,
+        export_id,
+        expires,
+        sign(&config.signing_key, &signed_message(export_id, expires))
+    )
+}
+
+/// Check a download link's signature and expiry
+pub fn verify_download(config: &ExportConfig, export_id: Uuid, expires: i64, signature: &str) -> bool {
+    expires > Utc::now().timestamp()
+        && verify_signature(&config.signing_key, &signed_message(export_id, expires), signature)
+}
+
+fn signed_message(export_id: Uuid, expires: i64) -> String {
+    format!("export:{}:{}", export_id, expires)
+}
+
+/// Build the archive in the background, then email the download link
+pub fn spawn_export(
+    pool: Pool<Postgres>,
+    app_config: AppConfig,
+    config: ExportConfig,
+    export_id: Uuid,
+    user_id: Uuid,
+) {
+    // The ready email and any failure are tied to the request that asked for it
+    tokio::spawn(with_request_context(async move {
+        if let Err(e) = build_export(&pool, &app_config, &config, export_id, user_id).await {
+            tracing::error!("Data export {} failed: {:?}", export_id, e);
+            let _ = sqlx::query!(
+                "UPDATE data_exports SET status = 'failed', completed_at = NOW() WHERE id = $1",
+                export_id
+            )
+            .execute(&pool)
+            .await;
+        }
+    }));
+}
+
+async fn build_export(
+    pool: &Pool<Postgres>,
+    app_config: &AppConfig,
+    config: &ExportConfig,
+    export_id: Uuid,
+    user_id: Uuid,
+) -> anyhow::Result<()> {
+    let archive = collect_user_data(pool, user_id).await?;
+    let body = serde_json::to_vec_pretty(&archive)?;
+
+    tokio::fs::create_dir_all(&config.dir).await?;
+    let path: PathBuf = [config.dir.as_str(), &format!("{}.json", export_id)].iter().collect();
+    tokio::fs::write(&path, body).await?;
+    let file_path = path.to_string_lossy().into_owned();
+
+    let expires_at = Utc::now() + Duration::hours(config.link_ttl_hours);
+    sqlx::query!(
+        r#"
+        UPDATE data_exports
+        SET status = 'ready', file_path = $1, completed_at = NOW(), expires_at = $2
+        WHERE id = $3
+        "#,
+        file_path,
+        expires_at,
+        export_id
+    )
+    .execute(pool)
+    .await?;
+
+    let url = download_url(app_config, config, export_id, expires_at);
+    let (email, username) = (archive.profile.email, archive.profile.username);
+    spawn_email("data export ready", move || {
+        send_data_export_ready_email(&email, &username, &url, expires_at)
+    });
+
+    Ok(())
+}
+
+/// Remove archives whose download link has expired
+pub async fn purge_expired_exports(pool: &Pool<Postgres>) -> Result<u64, sqlx::Error> {
+    let expired = sqlx::query!(
+        r#"
+        WITH due AS (
+            SELECT id, file_path FROM data_exports
+            WHERE status = 'ready' AND expires_at <= NOW()
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE data_exports d
+        SET status = 'expired', file_path = NULL
+        FROM due
+        WHERE d.id = due.id
+        RETURNING d.id, due.file_path
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for export in &expired {
+        if let Some(path) = &export.file_path
+            && let Err(e) = tokio::fs::remove_file(path).await
+        {
+            tracing::warn!("Could not remove export archive {}: {}", export.id, e);
+        }
+    }
+
+    Ok(expired.len() as u64)
+}
+
+/// Remove archive files with no pending or ready export, e.g. left behind
+/// when the account was deleted (its export rows go with it)
+pub async fn remove_orphaned_archives(pool: &Pool<Postgres>, dir: &str) -> anyhow::Result<u64> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    // List first: archives written after this point belong to exports the query below will see
+    let mut archives = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if let Some(id) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| Uuid::parse_str(stem).ok())
+        {
+            archives.push((id, path));
+        }
+    }
+
+    let live: HashSet<Uuid> = sqlx::query_scalar!(
+        "SELECT id FROM data_exports WHERE status IN ('pending', 'ready')"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut removed = 0;
+    for (id, path) in archives {
+        if !live.contains(&id) {
+            tokio::fs::remove_file(&path).await?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Remove expired and orphaned archives every `interval` for the lifetime of the server
+pub fn spawn_cleanup_job(pool: Pool<Postgres>, dir: String, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match purge_expired_exports(&pool).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!(removed, "Removed expired data exports"),
+                Err(e) => tracing::error!("Data export cleanup failed: {}", e),
+            }
+            if let Err(e) = remove_orphaned_archives(&pool, &dir).await {
+                tracing::error!("Data export orphan cleanup failed: {:?}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,24 @@
+use sqlx::{Pool, Postgres};
+
+/// Whether an address belongs to an account, or is still held for one whose
+/// email change can be undone. Holding it stops anyone from registering the
+/// old address and so blocking the owner's undo link.
+pub async fn is_email_taken(pool: &Pool<Postgres>, email: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT (
+            EXISTS(SELECT 1 FROM users WHERE LOWER(email) = LOWER($1))
+            OR EXISTS(
+                SELECT 1 FROM email_changes
+                WHERE LOWER(old_email) = LOWER($1)
+                AND confirmed_at IS NOT NULL
+                AND reverted_at IS NULL
+                AND revert_expires_at > NOW()
+            )
+        ) AS "taken!"
+        "#,
+        email
+    )
+    .fetch_one(pool)
+    .await
+}
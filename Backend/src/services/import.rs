@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::auth::validation::{sanitize_input, validate_email};
+use crate::models::import::ImportUserRecord;
+use crate::utils::hash::HashScheme;
+
+/// Outcome of a bulk import run
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped_existing: usize,
+    pub rejected: usize,
+}
+
+/// Load users with pre-hashed passwords from a `.csv` or `.json` file.
+/// CSV needs a header row; JSON is an array of objects with the same fields.
+/// Existing emails are left untouched.
+pub async fn import_users(pool: &Pool<Postgres>, path: &Path) -> anyhow::Result<ImportSummary> {
+    let records = read_records(path)?;
+    let mut summary = ImportSummary::default();
+
+    for (index, record) in records.into_iter().enumerate() {
+        let email = sanitize_input(&record.email);
+        let username = sanitize_input(&record.username);
+
+        if let Err(e) = validate_email(&email) {
+            eprintln!("Record {}: {}", index + 1, e.message);
+            summary.rejected += 1;
+            continue;
+        }
+
+        if username.is_empty() {
+            eprintln!("Record {}: Username is required", index + 1);
+            summary.rejected += 1;
+            continue;
+        }
+
+        if HashScheme::detect(&record.password_hash).is_none() {
+            eprintln!("Record {}: unrecognized password hash format", index + 1);
+            summary.rejected += 1;
+            continue;
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO users (id, username, email, password_hash, email_verified, created_at)
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, NOW()))
+            ON CONFLICT (email) DO NOTHING
+            "#,
+            Uuid::new_v4(),
+            username,
+            email,
+            record.password_hash,
+            record.email_verified,
+            record.created_at
+        )
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            summary.skipped_existing += 1;
+        } else {
+            summary.imported += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn read_records(path: &Path) -> anyhow::Result<Vec<ImportUserRecord>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => {
+            let mut reader = csv::Reader::from_path(path)?;
+            let records = reader
+                .deserialize()
+                .collect::<Result<Vec<ImportUserRecord>, _>>()?;
+            Ok(records)
+        }
+        Some("json") => {
+            let file = std::fs::File::open(path)?;
+            let records = serde_json::from_reader(std::io::BufReader::new(file))?;
+            Ok(records)
+        }
+        _ => anyhow::bail!("Unsupported import file (expected .csv or .json): {}", path.display()),
+    }
+}
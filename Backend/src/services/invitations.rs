@@ -0,0 +1,66 @@
+use sqlx::{PgConnection, PgExecutor};
+use uuid::Uuid;
+
+use crate::models::invitation::PendingInvitation;
+use crate::models::organization::MemberRole;
+
+/// The live (not accepted, revoked or expired) invitation behind a token
+pub async fn find_pending<'e>(
+    executor: impl PgExecutor<'e>,
+    token_hash: &str,
+) -> Result<Option<PendingInvitation>, sqlx::Error> {
+    sqlx::query_as!(
+        PendingInvitation,
+        r#"
+        SELECT id, organization_id, email, role AS "role: MemberRole"
+        FROM invitations
+        WHERE token_hash = $1
+        AND accepted_at IS NULL
+        AND revoked_at IS NULL
+        AND expires_at > NOW()
+        "#,
+        token_hash
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Mark the invitation accepted and add the user to the organization with the
+/// invited role. Returns false when the invitation was used or revoked meanwhile.
+/// Run it inside a transaction.
+pub async fn accept(
+    conn: &mut PgConnection,
+    invitation: &PendingInvitation,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let accepted = sqlx::query!(
+        r#"
+        UPDATE invitations SET accepted_at = NOW(), accepted_by = $2
+        WHERE id = $1 AND accepted_at IS NULL AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+        invitation.id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if accepted.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    // Existing members keep their current role
+    sqlx::query!(
+        r#"
+        INSERT INTO memberships (organization_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (organization_id, user_id) DO NOTHING
+        "#,
+        invitation.organization_id,
+        user_id,
+        invitation.role as MemberRole
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
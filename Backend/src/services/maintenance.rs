@@ -0,0 +1,53 @@
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+
+use crate::services::account_deletion::purge_due_accounts;
+use crate::services::data_export::purge_expired_exports;
+
+/// Rows removed by one `purge_expired` run
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeSummary {
+    pub password_resets: u64,
+    pub failed_logins: u64,
+    pub invitations: u64,
+    pub data_exports: u64,
+    pub deleted_accounts: u64,
+}
+
+/// Remove data that no longer has any effect: expired reset codes, failed
+/// logins outside the lockout window, dead invitations, expired export
+/// archives and accounts past their deletion grace period.
+/// Sessions are kept: they are the users' login history.
+pub async fn purge_expired(pool: &Pool<Postgres>, lockout_secs: i64) -> Result<PurgeSummary, sqlx::Error> {
+    let password_resets = sqlx::query!("DELETE FROM password_resets WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    let failed_logins = sqlx::query!(
+        "DELETE FROM failed_logins WHERE attempt_time <= NOW() - ($1::bigint * interval '1 second')",
+        lockout_secs
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let invitations = sqlx::query!(
+        r#"
+        DELETE FROM invitations
+        WHERE accepted_at IS NULL
+        AND (revoked_at IS NOT NULL OR expires_at <= NOW())
+        "#
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(PurgeSummary {
+        password_resets,
+        failed_logins,
+        invitations,
+        data_exports: purge_expired_exports(pool).await?,
+        deleted_accounts: purge_due_accounts(pool).await?,
+    })
+}
@@ -1 +1,15 @@
+pub mod account_deletion;
+pub mod admin_users;
+pub mod audit;
 pub mod auth;
+pub mod data_export;
+pub mod email_changes;
+pub mod grants;
+pub mod import;
+pub mod invitations;
+pub mod maintenance;
+pub mod organizations;
+pub mod password_history;
+pub mod rbac;
+pub mod security_events;
+pub mod sessions;
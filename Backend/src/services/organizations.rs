@@ -0,0 +1,59 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::policy::{subject_from_claims, AuthzRequest, Decision, PolicySet};
+use crate::models::organization::MemberRole;
+
+/// The user's role in the organization, or `None` if they are not a member
+pub async fn membership_role<'e>(
+    executor: impl PgExecutor<'e>,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<MemberRole>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT role AS "role: MemberRole" FROM memberships
+        WHERE organization_id = $1 AND user_id = $2
+        "#,
+        organization_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Policy decision for a member action on an organization; the caller's role
+/// there is passed to the policies as `subject.org_role`
+pub fn member_decision(
+    policies: &PolicySet,
+    user: &AuthenticatedUser,
+    org_role: MemberRole,
+    action: &str,
+    resource: serde_json::Value,
+) -> Decision {
+    let mut subject = subject_from_claims(&user.claims);
+    subject["org_role"] = serde_json::json!(org_role);
+
+    policies.evaluate(&AuthzRequest {
+        subject,
+        action: action.to_string(),
+        resource,
+        context: serde_json::Value::Null,
+    })
+}
+
+/// URL-friendly slug from an organization name ("Acme Corp." -> "acme-corp")
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.truncate(50);
+    slug.trim_end_matches('-').to_string()
+}
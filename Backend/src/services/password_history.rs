@@ -0,0 +1,89 @@
+use sqlx::{PgConnection, PgExecutor};
+use uuid::Uuid;
+
+use crate::utils::hash_pool::{HashPool, HashPoolError};
+
+pub const PASSWORD_REUSED_MESSAGE: &str =
+    "You have used this password recently. Please choose a different one.";
+
+/// The user's current password hash followed by the last `depth` recorded
+/// ones, newest first. A depth of 0 disables reuse checks and returns none.
+pub async fn recent_hashes<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    depth: usize,
+) -> Result<Vec<String>, sqlx::Error> {
+    if depth == 0 {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT password_hash AS "password_hash!" FROM (
+            SELECT password_hash, NOW() AS created_at FROM users WHERE id = $1
+            UNION ALL
+            (SELECT password_hash, created_at FROM password_history
+             WHERE user_id = $1
+             ORDER BY created_at DESC
+             LIMIT $2)
+        ) recent
+        ORDER BY created_at DESC
+        "#,
+        user_id,
+        depth as i64
+    )
+    .fetch_all(executor)
+    .await
+}
+
+/// Whether `password` matches one of the `recent_hashes`. Fetch them first,
+/// so no connection is held while hashing.
+pub async fn is_reused(hash_pool: &HashPool, password: &str, hashes: &[String]) -> Result<bool, HashPoolError> {
+    for hashed in hashes {
+        if hash_pool.verify(password, hashed).await? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Record a newly set password hash and prune entries beyond `keep`.
+/// Run it in the transaction that sets the password.
+pub async fn record(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    password_hash: &str,
+    keep: usize,
+) -> Result<(), sqlx::Error> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)",
+        user_id,
+        password_hash
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM password_history
+        WHERE user_id = $1
+        AND id NOT IN (
+            SELECT id FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+        )
+        "#,
+        user_id,
+        keep as i64
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use sqlx::{Pool, Postgres};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::models::role::RoleSummary;
+
+pub type PermissionSet = Arc<HashSet<String>>;
+
+/// Per-user permissions resolved from `user_roles`, reused for `ttl` so a guarded
+/// request does not hit the database every time. Role changes take effect once
+/// the entry expires.
+#[derive(Clone)]
+pub struct PermissionCache {
+    entries: Arc<Mutex<HashMap<Uuid, (Instant, PermissionSet)>>>,
+    ttl: Duration,
+}
+
+impl PermissionCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    /// The user's permissions, loaded from the database on a miss
+    pub async fn permissions(&self, pool: &Pool<Postgres>, user_id: Uuid) -> Result<PermissionSet, sqlx::Error> {
+        if let Ok(entries) = self.entries.lock()
+            && let Some((loaded_at, permissions)) = entries.get(&user_id)
+            && loaded_at.elapsed() < self.ttl
+        {
+            return Ok(Arc::clone(permissions));
+        }
+
+        let permissions: PermissionSet = Arc::new(user_permissions(pool, user_id).await?);
+
+        if let Ok(mut entries) = self.entries.lock() {
+            // Drop stale entries so the map stays bounded by the active users
+            let ttl = self.ttl;
+            entries.retain(|_, (loaded_at, _)| loaded_at.elapsed() < ttl);
+            entries.insert(user_id, (Instant::now(), Arc::clone(&permissions)));
+        }
+
+        Ok(permissions)
+    }
+}
+
+/// Every permission granted to the user through their roles
+pub async fn user_permissions(pool: &Pool<Postgres>, user_id: Uuid) -> Result<HashSet<String>, sqlx::Error> {
+    let names = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT p.name
+        FROM user_roles ur
+        JOIN role_permissions rp ON rp.role_id = ur.role_id
+        JOIN permissions p ON p.id = rp.permission_id
+        WHERE ur.user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(names.into_iter().collect())
+}
+
+/// Grant a role by name. Returns false when the role does not exist;
+/// granting a role the user already holds is a no-op.
+pub async fn grant_role(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    role: &str,
+    granted_by: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let role_id = sqlx::query_scalar!("SELECT id FROM roles WHERE name = $1", role)
+        .fetch_optional(pool)
+        .await?;
+
+    let Some(role_id) = role_id else {
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_roles (user_id, role_id, granted_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, role_id) DO NOTHING
+        "#,
+        user_id,
+        role_id,
+        granted_by
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(true)
+}
+
+/// All roles with the permissions they grant
+pub async fn list_roles(pool: &Pool<Postgres>) -> Result<Vec<RoleSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        RoleSummary,
+        r#"
+        SELECT r.name, r.description,
+               COALESCE(ARRAY_AGG(p.name ORDER BY p.name) FILTER (WHERE p.name IS NOT NULL), '{}')
+                   AS "permissions!"
+        FROM roles r
+        LEFT JOIN role_permissions rp ON rp.role_id = r.id
+        LEFT JOIN permissions p ON p.id = rp.permission_id
+        GROUP BY r.id
+        ORDER BY r.name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,131 @@
+use crate::models::audit::AuditEventType;
+use crate::services::security_events::SecurityEvent;
+
+const VENDOR: &str = "uiso";
+const PRODUCT: &str = "auth";
+
+/// `CEF:0|Vendor|Product|Version|SignatureID|Name|Severity|Extension`
+pub fn format(event: &SecurityEvent) -> String {
+    let mut extension = vec![
+        ("rt", event.timestamp.timestamp_millis().to_string()),
+        ("externalId", event.id.to_string()),
+        ("outcome", event.outcome.as_str().to_string()),
+    ];
+    let optional = [
+        ("suid", event.actor_id.map(|id| id.to_string())),
+        ("duid", event.target_user_id.map(|id| id.to_string())),
+        ("src", event.ip_address.clone()),
+        ("requestClientApplication", event.user_agent.clone()),
+    ];
+    extension.extend(optional.into_iter().filter_map(|(key, value)| value.map(|value| (key, value))));
+    if let Some(correlation_id) = &event.correlation_id {
+        extension.push(("cs1Label", "correlationId".to_string()));
+        extension.push(("cs1", correlation_id.clone()));
+    }
+    extension.push(("cs2Label", "details".to_string()));
+    extension.push(("cs2", event.details.to_string()));
+
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        header(VENDOR),
+        header(PRODUCT),
+        header(env!("CARGO_PKG_VERSION")),
+        header(event.event_type.as_str()),
+        header(name(event.event_type)),
+        severity(event),
+        extension
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, extension_value(value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )
+}
+
+fn name(event_type: AuditEventType) -> &'static str {
+    match event_type {
+        AuditEventType::Registration => "Account registered",
+        AuditEventType::LoginSuccess => "Sign-in succeeded",
+        AuditEventType::LoginFailure => "Sign-in failed",
+        AuditEventType::Logout => "Signed out",
+        AuditEventType::PasswordResetRequested => "Password reset requested",
+        AuditEventType::PasswordResetCompleted => "Password reset completed",
+        AuditEventType::Lockout => "Account locked out",
+        AuditEventType::AdminAction => "Admin action",
+        AuditEventType::PasswordChanged => "Password changed",
+        AuditEventType::EmailChanged => "Email address changed",
+        AuditEventType::EmailChangeReverted => "Email address change reverted",
+        AuditEventType::AccountDeletionScheduled => "Account deletion scheduled",
+        AuditEventType::AccountDeletionCancelled => "Account deletion cancelled",
+        AuditEventType::AccountDeleted => "Account deleted",
+    }
+}
+
+// 0 (lowest) to 10
+fn severity(event: &SecurityEvent) -> u8 {
+    match event.event_type {
+        AuditEventType::Lockout => 7,
+        // The owner says someone else changed their address
+        AuditEventType::AdminAction | AuditEventType::EmailChangeReverted => 5,
+        _ if event.is_failure() => 5,
+        _ => 3,
+    }
+}
+
+// Header fields escape pipes and backslashes; CEF has no escape for line
+// breaks there, and one would split the record
+fn header(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+// Extension values escape equals signs, backslashes and line breaks
+fn extension_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::security_events::tests::sample_event;
+
+    #[test]
+    fn escapes_header_fields() {
+        assert_eq!(header(r"a|b\c"), r"a\|b\\c");
+        assert_eq!(header("a=b"), "a=b");
+        assert_eq!(header("line\r\nbreak"), "line  break");
+    }
+
+    #[test]
+    fn escapes_extension_values() {
+        assert_eq!(extension_value(r"a=b\c"), r"a\=b\\c");
+        assert_eq!(extension_value("one\r\ntwo"), r"one\r\ntwo");
+        // Pipes need no escaping after the header
+        assert_eq!(extension_value("a|b"), "a|b");
+    }
+
+    #[test]
+    fn formats_header_and_extension() {
+        let event = SecurityEvent {
+            user_agent: Some("agent=x|y\nz".to_string()),
+            ..sample_event()
+        };
+        let line = format(&event);
+
+        let header_fields: Vec<&str> = line.splitn(8, '|').collect();
+        assert_eq!(
+            header_fields[..7],
+            ["CEF:0", "uiso", "auth", env!("CARGO_PKG_VERSION"), "login_failure", "Sign-in failed", "5"]
+        );
+        let extension = header_fields[7];
+        assert!(extension.starts_with("rt=1792326605000 externalId=42 outcome=failure "));
+        assert!(extension.contains(r"requestClientApplication=agent\=x|y\nz "));
+        assert!(extension.contains("cs1Label=correlationId cs1=req-1 "));
+        assert!(!line.contains('\n'));
+    }
+}
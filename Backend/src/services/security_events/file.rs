@@ -0,0 +1,122 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::config::security_events::SecurityEventFormat;
+use crate::services::security_events::{format_event, SecurityEvent, SecurityEventSink};
+
+/// One event per line, appended to a file that is rotated once it would
+/// grow past `max_bytes`: `<path>` moves to `<path>.1`, `.1` to `.2`, and so
+/// on, keeping `keep` rotated files.
+pub struct FileSink {
+    path: PathBuf,
+    format: SecurityEventFormat,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl FileSink {
+    pub fn open(path: &str, format: SecurityEventFormat, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { path, format, max_bytes, keep, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+}
+
+impl SecurityEventSink for FileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn emit(&mut self, event: &SecurityEvent) -> io::Result<()> {
+        let mut line = format_event(event, self.format);
+        line.push('\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &PathBuf) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::security_events::tests::sample_event;
+
+    #[test]
+    fn rotates_at_the_size_limit() {
+        let dir = std::env::temp_dir().join(format!("security-events-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("events.log");
+        let line_len = format_event(&sample_event(), SecurityEventFormat::Json).len() as u64 + 1;
+
+        // Room for two events per file, two rotated files kept
+        let mut sink =
+            FileSink::open(path.to_str().unwrap(), SecurityEventFormat::Json, 2 * line_len, 2).unwrap();
+        for _ in 0..7 {
+            sink.emit(&sample_event()).unwrap();
+        }
+
+        let lines = |path: PathBuf| fs::read_to_string(path).unwrap().lines().count();
+        assert_eq!(lines(path.clone()), 1);
+        assert_eq!(lines(sink.rotated(1)), 2);
+        assert_eq!(lines(sink.rotated(2)), 2);
+        assert!(!sink.rotated(3).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn counts_an_existing_file_towards_the_limit() {
+        let dir = std::env::temp_dir().join(format!("security-events-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("events.log");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "x".repeat(100)).unwrap();
+
+        let mut sink = FileSink::open(path.to_str().unwrap(), SecurityEventFormat::Json, 100, 1).unwrap();
+        sink.emit(&sample_event()).unwrap();
+
+        assert_eq!(fs::read_to_string(sink.rotated(1)).unwrap(), "x".repeat(100));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
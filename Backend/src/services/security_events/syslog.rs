@@ -0,0 +1,201 @@
+use chrono::SecondsFormat;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::config::security_events::{SecurityEventFormat, SyslogProtocol};
+use crate::models::audit::AuditEventType;
+use crate::services::security_events::{format_event, SecurityEvent, SecurityEventSink};
+
+// Security/authorization messages (authpriv)
+const FACILITY: u8 = 10;
+
+const SEVERITY_WARNING: u8 = 4;
+const SEVERITY_NOTICE: u8 = 5;
+const SEVERITY_INFO: u8 = 6;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// RFC 5424 messages to a collector: one datagram per event over UDP, or
+/// octet-counted frames (RFC 6587) over a TCP connection that is reopened
+/// when it breaks
+pub struct SyslogSink {
+    addr: SocketAddr,
+    transport: Transport,
+    format: SecurityEventFormat,
+    app_name: String,
+    hostname: String,
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(Option<TcpStream>),
+}
+
+impl SyslogSink {
+    pub fn new(
+        addr: &str,
+        protocol: SyslogProtocol,
+        format: SecurityEventFormat,
+        app_name: &str,
+    ) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("cannot resolve syslog address {}", addr))
+        })?;
+
+        let transport = match protocol {
+            SyslogProtocol::Udp => {
+                let local: SocketAddr = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }
+                    .parse()
+                    .expect("valid wildcard address");
+                Transport::Udp(UdpSocket::bind(local)?)
+            }
+            // Connected on the first event, so the server starts without the collector
+            SyslogProtocol::Tcp => Transport::Tcp(None),
+        };
+
+        Ok(Self {
+            addr,
+            transport,
+            format,
+            app_name: header_field(app_name, 48),
+            hostname: header_field(&hostname(), 255),
+        })
+    }
+
+    /// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    fn message(&self, event: &SecurityEvent) -> String {
+        format!(
+            "<{}>1 {} {} {} {} {} - {}",
+            FACILITY * 8 + severity(event),
+            event.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+            event.event_type.as_str(),
+            format_event(event, self.format)
+        )
+    }
+}
+
+impl SecurityEventSink for SyslogSink {
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    fn emit(&mut self, event: &SecurityEvent) -> io::Result<()> {
+        let message = self.message(event);
+        let addr = self.addr;
+
+        match &mut self.transport {
+            Transport::Udp(socket) => socket.send_to(message.as_bytes(), addr).map(|_| ()),
+            Transport::Tcp(stream) => {
+                let frame = format!("{} {}", message.len(), message);
+                // One retry on a fresh connection when the collector dropped the old one
+                for attempt in 0..2 {
+                    if stream.is_none() {
+                        *stream = Some(TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?);
+                    }
+                    let written = stream.as_mut().map(|s| s.write_all(frame.as_bytes()));
+                    match written {
+                        Some(Ok(())) => return Ok(()),
+                        Some(Err(e)) if attempt == 1 => return Err(e),
+                        _ => *stream = None,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn severity(event: &SecurityEvent) -> u8 {
+    if event.is_failure() {
+        SEVERITY_WARNING
+    } else if event.event_type == AuditEventType::AdminAction {
+        SEVERITY_NOTICE
+    } else {
+        SEVERITY_INFO
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+// Header fields are printable ASCII without spaces, of limited length
+fn header_field(value: &str, max_len: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if field.is_empty() { "-".to_string() } else { field }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::security_events::tests::sample_event;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    // `<PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    fn assert_rfc5424(message: &str, format: SecurityEventFormat) {
+        let fields: Vec<&str> = message.splitn(8, ' ').collect();
+        // authpriv (10) * 8 + warning (4), version 1
+        assert_eq!(fields[0], "<84>1");
+        assert_eq!(fields[1], "2026-10-18T12:30:05.000000Z");
+        assert!(!fields[2].is_empty());
+        assert_eq!(fields[3], "uiso-auth");
+        assert_eq!(fields[4], std::process::id().to_string());
+        assert_eq!(fields[5], "login_failure");
+        // No structured data: the event is the message
+        assert_eq!(fields[6], "-");
+        assert_eq!(fields[7], format_event(&sample_event(), format));
+    }
+
+    #[test]
+    fn sends_one_datagram_per_event_over_udp() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let addr = collector.local_addr().unwrap().to_string();
+
+        let mut sink = SyslogSink::new(&addr, SyslogProtocol::Udp, SecurityEventFormat::Json, "uiso-auth").unwrap();
+        sink.emit(&sample_event()).unwrap();
+
+        let mut buf = [0u8; 4096];
+        let (len, _) = collector.recv_from(&mut buf).unwrap();
+        assert_rfc5424(std::str::from_utf8(&buf[..len]).unwrap(), SecurityEventFormat::Json);
+    }
+
+    #[test]
+    fn sends_octet_counted_frames_over_tcp() {
+        let collector = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = collector.local_addr().unwrap().to_string();
+
+        let mut sink = SyslogSink::new(&addr, SyslogProtocol::Tcp, SecurityEventFormat::Cef, "uiso-auth").unwrap();
+        sink.emit(&sample_event()).unwrap();
+        drop(sink);
+
+        let (mut stream, _) = collector.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+
+        let (len, message) = received.split_once(' ').unwrap();
+        assert_eq!(len.parse::<usize>().unwrap(), message.len());
+        assert_rfc5424(message, SecurityEventFormat::Cef);
+    }
+
+    #[test]
+    fn header_fields_are_printable_ascii() {
+        assert_eq!(header_field("uiso auth\n", 48), "uisoauth");
+        assert_eq!(header_field(" ", 48), "-");
+        assert_eq!(header_field("abcdef", 3), "abc");
+    }
+}
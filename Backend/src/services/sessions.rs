@@ -0,0 +1,203 @@
+use actix_web::{http::header::USER_AGENT, HttpRequest};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::auth::jwt::{create_jwt, token_expiry};
+
+/// Open a session for the user and return the access token bound to it.
+/// The token is scoped to the user's first organization, if any.
+pub async fn start_session(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    req: &HttpRequest,
+    ttl_hours: u64,
+) -> anyhow::Result<String> {
+    let expires_at = token_expiry(ttl_hours);
+    let ip_address = req.connection_info().realip_remote_addr().map(str::to_string);
+    let user_agent = req.headers().get(USER_AGENT).and_then(|v| v.to_str().ok());
+
+    let organization_id = default_organization(pool, user_id).await?;
+
+    let session_id = create_session(
+        pool,
+        user_id,
+        organization_id,
+        None,
+        expires_at,
+        ip_address.as_deref(),
+        user_agent,
+    )
+    .await?;
+
+    create_jwt(
+        &user_id.to_string(),
+        &session_id.to_string(),
+        organization_id.map(|id| id.to_string()).as_deref(),
+        None,
+        expires_at,
+    )
+}
+
+/// The organization new sessions are scoped to: the user's first one, if any
+pub async fn default_organization<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT organization_id FROM memberships
+        WHERE user_id = $1
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Record a new session for an access token about to be issued.
+/// `impersonator_id` is the admin behind an impersonation session.
+pub async fn create_session<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    organization_id: Option<Uuid>,
+    impersonator_id: Option<Uuid>,
+    expires_at: DateTime<Utc>,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<Uuid, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO sessions (user_id, organization_id, impersonator_id, expires_at, ip_address, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        user_id,
+        organization_id,
+        impersonator_id,
+        expires_at,
+        ip_address,
+        user_agent
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row.id)
+}
+
+/// A session is active when it belongs to the user, is unrevoked and unexpired,
+/// and is still scoped to `organization_id`, of which the user is still a member.
+/// Impersonation sessions also need the same admin, whose account must still be
+/// usable (not locked or deleted).
+pub async fn is_session_active(
+    pool: &Pool<Postgres>,
+    session_id: Uuid,
+    user_id: Uuid,
+    organization_id: Option<Uuid>,
+    impersonator_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM sessions s
+            WHERE s.id = $1 AND s.user_id = $2
+            AND s.revoked_at IS NULL AND s.expires_at > NOW()
+            AND s.organization_id IS NOT DISTINCT FROM $3
+            AND ($3::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM memberships m
+                WHERE m.organization_id = $3 AND m.user_id = $2
+            ))
+            AND s.impersonator_id IS NOT DISTINCT FROM $4
+            AND ($4::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM users a
+                WHERE a.id = $4 AND a.locked_at IS NULL AND a.deleted_at IS NULL
+            ))
+        ) AS "active!"
+        "#,
+        session_id,
+        user_id,
+        organization_id,
+        impersonator_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.active)
+}
+
+/// Re-scope an active session to another organization (or none) and return
+/// a new token for it. Returns `None` when the user is not a member.
+pub async fn switch_organization(
+    pool: &Pool<Postgres>,
+    session_id: Uuid,
+    user_id: Uuid,
+    organization_id: Option<Uuid>,
+) -> anyhow::Result<Option<String>> {
+    let session = sqlx::query!(
+        r#"
+        UPDATE sessions SET organization_id = $3
+        WHERE id = $1 AND user_id = $2
+        AND revoked_at IS NULL AND expires_at > NOW()
+        AND ($3::uuid IS NULL OR EXISTS (
+            SELECT 1 FROM memberships m
+            WHERE m.organization_id = $3 AND m.user_id = $2
+        ))
+        RETURNING expires_at, impersonator_id
+        "#,
+        session_id,
+        user_id,
+        organization_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(session) = session else {
+        return Ok(None);
+    };
+
+    // An impersonation session stays one
+    let token = create_jwt(
+        &user_id.to_string(),
+        &session_id.to_string(),
+        organization_id.map(|id| id.to_string()).as_deref(),
+        session.impersonator_id.map(|id| id.to_string()).as_deref(),
+        session.expires_at,
+    )?;
+    Ok(Some(token))
+}
+
+/// Revoke a single session (logout)
+pub async fn revoke_session(pool: &Pool<Postgres>, session_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+        session_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke every active session of a user, optionally sparing one.
+/// Returns the number of sessions revoked.
+pub async fn revoke_user_sessions<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    except: Option<Uuid>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE sessions SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        AND ($2::uuid IS NULL OR id <> $2)
+        "#,
+        user_id,
+        except
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,144 @@
+use askama::DynTemplate;
+use chrono::{DateTime, Utc};
+use lettre::message::header::{self, HeaderName, HeaderValue};
+use lettre::message::{Message, SinglePart};
+use lettre::Transport;
+use std::env;
+
+use crate::models::notificationtemplate::{
+    AccountDeletionScheduledTemplate, DataExportReadyTemplate, EmailChangeCodeTemplate,
+    EmailChangedTemplate, EmailInUseTemplate, OrganizationInvitationTemplate, PasswordChangedTemplate,
+};
+use crate::middleware::request_id::{current_request_id, with_request_context_blocking};
+use crate::utils::otp::build_mailer;
+
+/// Send an HTML email through the configured SMTP relay (blocking)
+pub fn send_html_email(to: &str, subject: &str, html_body: String) -> anyhow::Result<()> {
+    let from_address = env::var("SMTP_FROM").map_err(|_| anyhow::anyhow!("SMTP_FROM must be set"))?;
+
+    let mut email = Message::builder()
+        .from(from_address.parse()?)
+        .to(to.parse()?)
+        .subject(subject)
+        .singlepart(
+            SinglePart::builder()
+                .header(header::ContentType::TEXT_HTML)
+                .body(html_body),
+        )?;
+    tag_request_id(&mut email);
+
+    build_mailer()?.send(&email)?;
+    Ok(())
+}
+
+/// Name the request that sent the message in an `X-Request-Id` header, so a
+/// bounce or complaint can be matched with the server logs
+pub fn tag_request_id(email: &mut Message) {
+    if let Some(request_id) = current_request_id() {
+        email
+            .headers_mut()
+            .insert_raw(HeaderValue::new(HeaderName::new_from_ascii_str("X-Request-Id"), request_id));
+    }
+}
+
+/// Send a notification off the request path; failures are only logged
+pub fn spawn_email<F>(description: &'static str, send: F)
+where
+    F: FnOnce() -> anyhow::Result<()> + Send + 'static,
+{
+    tokio::task::spawn_blocking(with_request_context_blocking(move || {
+        if let Err(e) = send() {
+            tracing::error!("Failed to send {} email: {:?}", description, e);
+        }
+    }));
+}
+
+pub fn send_password_changed_email(to: &str, username: &str) -> anyhow::Result<()> {
+    let changed_at = Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+    let template = PasswordChangedTemplate {
+        username,
+        changed_at: &changed_at,
+    };
+
+    send_html_email(to, "Your password was changed", template.dyn_render()?)
+}
+
+pub fn send_email_change_code(to: &str, code: &str, expiry_minutes: i64) -> anyhow::Result<()> {
+    let template = EmailChangeCodeTemplate { code, expiry_minutes };
+    send_html_email(to, "Confirm your new email address", template.dyn_render()?)
+}
+
+// Sent instead of a code when the requested address already has an account
+pub fn send_email_in_use_notice(to: &str) -> anyhow::Result<()> {
+    send_html_email(to, "Email change request", EmailInUseTemplate.dyn_render()?)
+}
+
+pub fn send_email_changed_notice(
+    to: &str,
+    username: &str,
+    new_email: &str,
+    revert_url: &str,
+    revert_ttl_hours: i64,
+) -> anyhow::Result<()> {
+    let template = EmailChangedTemplate {
+        username,
+        new_email,
+        revert_url,
+        revert_ttl_hours,
+    };
+
+    send_html_email(to, "Your email address was changed", template.dyn_render()?)
+}
+
+pub fn send_account_deletion_email(
+    to: &str,
+    username: &str,
+    scheduled_for: DateTime<Utc>,
+    cancel_url: &str,
+) -> anyhow::Result<()> {
+    let scheduled_for = scheduled_for.format("%Y-%m-%d %H:%M UTC").to_string();
+    let template = AccountDeletionScheduledTemplate {
+        username,
+        scheduled_for: &scheduled_for,
+        cancel_url,
+    };
+
+    send_html_email(to, "Your account is scheduled for deletion", template.dyn_render()?)
+}
+
+pub fn send_data_export_ready_email(
+    to: &str,
+    username: &str,
+    download_url: &str,
+    expires_at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let expires_at = expires_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let template = DataExportReadyTemplate {
+        username,
+        download_url,
+        expires_at: &expires_at,
+    };
+
+    send_html_email(to, "Your data export is ready", template.dyn_render()?)
+}
+
+pub fn send_organization_invitation_email(
+    to: &str,
+    organization_name: &str,
+    inviter: &str,
+    role: &str,
+    invite_url: &str,
+    expires_at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let expires_at = expires_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let template = OrganizationInvitationTemplate {
+        organization_name,
+        inviter,
+        role,
+        invite_url,
+        expires_at: &expires_at,
+    };
+
+    let subject = format!("You have been invited to join {}", organization_name);
+    send_html_email(to, &subject, template.dyn_render()?)
+}
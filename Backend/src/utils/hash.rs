@@ -1,5 +1,41 @@
 use argon2::{Argon2, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{SaltString, PasswordHash, rand_core::OsRng};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use sha2::Sha256;
+
+/// Highest PBKDF2 iteration count we verify. Imported hashes come from
+/// outside, and each iteration costs a hashing thread time; Django's own
+/// default is around a million.
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/// Password hash formats we know how to verify.
+/// Everything except Argon2 comes from legacy imports and is upgraded on login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashScheme {
+    Argon2,
+    Bcrypt,
+    Pbkdf2Sha256,
+    Scrypt,
+}
+
+impl HashScheme {
+    /// Recognize the scheme from the stored hash string
+    pub fn detect(hashed: &str) -> Option<Self> {
+        if hashed.starts_with("$argon2") {
+            Some(Self::Argon2)
+        } else if ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|p| hashed.starts_with(p)) {
+            Some(Self::Bcrypt)
+        } else if hashed.starts_with("$pbkdf2-sha256$") || hashed.starts_with("pbkdf2_sha256$") {
+            Some(Self::Pbkdf2Sha256)
+        } else if hashed.starts_with("$scrypt$") {
+            Some(Self::Scrypt)
+        } else {
+            None
+        }
+    }
+}
 
 /// Hash a plaintext password with Argon2
 pub fn hash_password(password: &str) -> Result<String, String> {
@@ -11,12 +47,167 @@ pub fn hash_password(password: &str) -> Result<String, String> {
         .map_err(|e| format!("Hashing error: {}", e))
 }
 
-/// Verify a plaintext password against a hash
+/// Verify a plaintext password against a hash in any supported scheme
 pub fn verify_password(password: &str, hashed: &str) -> Result<bool, String> {
+    match HashScheme::detect(hashed) {
+        Some(HashScheme::Argon2) => verify_phc(password, hashed, &Argon2::default()),
+        Some(HashScheme::Bcrypt) => bcrypt::verify(password, hashed)
+            .map_err(|e| format!("Invalid hash format: {}", e)),
+        Some(HashScheme::Pbkdf2Sha256) if hashed.starts_with('$') => verify_phc(password, hashed, &Pbkdf2),
+        Some(HashScheme::Pbkdf2Sha256) => verify_django_pbkdf2(password, hashed),
+        Some(HashScheme::Scrypt) => verify_phc(password, hashed, &Scrypt),
+        None => Err("Invalid hash format: unrecognized scheme".to_string()),
+    }
+}
+
+/// Whether a stored hash should be replaced with a fresh Argon2 hash
+/// after the next successful verification
+pub fn needs_rehash(hashed: &str) -> bool {
+    HashScheme::detect(hashed) != Some(HashScheme::Argon2)
+}
+
+// PHC string formats ($argon2id$..., $pbkdf2-sha256$..., $scrypt$...)
+fn verify_phc(password: &str, hashed: &str, verifier: &dyn PasswordVerifier) -> Result<bool, String> {
     let parsed_hash = PasswordHash::new(hashed)
         .map_err(|e| format!("Invalid hash format: {}", e))?;
-    
-    Ok(Argon2::default()
+    // PBKDF2's iteration count
+    if let Some(iterations) = parsed_hash.params.get_decimal("i") {
+        check_pbkdf2_iterations(iterations)?;
+    }
+
+    Ok(verifier
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
+
+// Django-style `pbkdf2_sha256$<iterations>$<salt>$<base64 hash>`
+fn verify_django_pbkdf2(password: &str, hashed: &str) -> Result<bool, String> {
+    let parts: Vec<&str> = hashed.splitn(4, '$').collect();
+    let [_, iterations, salt, expected] = parts[..] else {
+        return Err("Invalid hash format: expected 4 fields".to_string());
+    };
+
+    let iterations: u32 = iterations
+        .parse()
+        .map_err(|_| "Invalid hash format: bad iteration count".to_string())?;
+    check_pbkdf2_iterations(iterations)?;
+    let expected = STANDARD
+        .decode(expected)
+        .map_err(|e| format!("Invalid hash format: {}", e))?;
+
+    let mut derived = vec![0u8; expected.len()];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut derived);
+
+    // Constant-time comparison
+    let diff = derived
+        .iter()
+        .zip(expected.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+    Ok(diff == 0 && !expected.is_empty())
+}
+
+fn check_pbkdf2_iterations(iterations: u32) -> Result<(), String> {
+    if iterations > MAX_PBKDF2_ITERATIONS {
+        return Err(format!(
+            "Invalid hash format: {} iterations exceed the limit of {}",
+            iterations, MAX_PBKDF2_ITERATIONS
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    // Known-good hashes of PASSWORD, as the legacy systems stored them
+    const DJANGO_PBKDF2: &str = "pbkdf2_sha256$1000$seasalt$3xmXbyk2QpiyNcnoBbzRPwEBsYPTbDlRdtmLyBvQltA=";
+    const PHC_PBKDF2: &str = "$pbkdf2-sha256$i=1000,l=32$MDEyMzQ1Njc4OWFiY2RlZg$yqSq2SygY1sB4EcH9f2FG0JTMES+wqLsOT5YmiRBplI";
+    const SCRYPT: &str = "$scrypt$ln=4,r=8,p=1$MDEyMzQ1Njc4OWFiY2RlZg$0Pz6RuN9WCY6OfE3FYH5Myx0JqvNjul84VbFyWLBf4g";
+    // OpenBSD test vector for "U*U"
+    const BCRYPT: &str = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+
+    #[test]
+    fn detects_each_scheme() {
+        let argon2 = hash_password(PASSWORD).unwrap();
+        assert_eq!(HashScheme::detect(&argon2), Some(HashScheme::Argon2));
+        for prefix in ["$2a$", "$2b$", "$2x$", "$2y$"] {
+            assert_eq!(HashScheme::detect(&format!("{}05$abc", prefix)), Some(HashScheme::Bcrypt));
+        }
+        assert_eq!(HashScheme::detect(DJANGO_PBKDF2), Some(HashScheme::Pbkdf2Sha256));
+        assert_eq!(HashScheme::detect(PHC_PBKDF2), Some(HashScheme::Pbkdf2Sha256));
+        assert_eq!(HashScheme::detect(SCRYPT), Some(HashScheme::Scrypt));
+    }
+
+    #[test]
+    fn unknown_prefixes_are_rejected() {
+        for hashed in ["", "plaintext", "$1$salt$md5hash", "$2z$05$abc", "pbkdf2_sha1$1000$salt$aGFzaA==", "sha256$salt$hash"] {
+            assert_eq!(HashScheme::detect(hashed), None, "{}", hashed);
+            assert!(verify_password(PASSWORD, hashed).is_err(), "{}", hashed);
+        }
+    }
+
+    #[test]
+    fn verifies_known_legacy_hashes() {
+        for hashed in [DJANGO_PBKDF2, PHC_PBKDF2, SCRYPT] {
+            assert_eq!(verify_password(PASSWORD, hashed), Ok(true), "{}", hashed);
+            assert_eq!(verify_password("wrong password", hashed), Ok(false), "{}", hashed);
+        }
+        assert_eq!(verify_password("U*U", BCRYPT), Ok(true));
+        assert_eq!(verify_password("U*V", BCRYPT), Ok(false));
+    }
+
+    #[test]
+    fn argon2_round_trip_needs_no_rehash() {
+        let hashed = hash_password(PASSWORD).unwrap();
+        assert_eq!(verify_password(PASSWORD, &hashed), Ok(true));
+        assert_eq!(verify_password("wrong password", &hashed), Ok(false));
+        assert!(!needs_rehash(&hashed));
+        for legacy in [DJANGO_PBKDF2, PHC_PBKDF2, SCRYPT, BCRYPT] {
+            assert!(needs_rehash(legacy), "{}", legacy);
+        }
+    }
+
+    #[test]
+    fn malformed_django_hashes_are_errors() {
+        for hashed in [
+            "pbkdf2_sha256$1000$seasalt",
+            "pbkdf2_sha256$many$seasalt$3xmXbyk2QpiyNcnoBbzRPwEBsYPTbDlRdtmLyBvQltA=",
+            "pbkdf2_sha256$-1$seasalt$3xmXbyk2QpiyNcnoBbzRPwEBsYPTbDlRdtmLyBvQltA=",
+            "pbkdf2_sha256$1000$seasalt$not*base64",
+        ] {
+            assert!(verify_password(PASSWORD, hashed).is_err(), "{}", hashed);
+        }
+        // Nothing to compare against is never a match
+        assert_eq!(verify_password(PASSWORD, "pbkdf2_sha256$1000$seasalt$"), Ok(false));
+    }
+
+    #[test]
+    fn malformed_phc_and_bcrypt_hashes_are_errors() {
+        for hashed in [
+            "$pbkdf2-sha256$i=1000,l=32$MDEyMzQ1Njc4OWFiY2RlZg$not*base64",
+            "$scrypt$ln=4,r=8,p=1$$",
+            "$argon2id$v=19$m=abc$salt$hash",
+            "$2a$05$tooshort",
+        ] {
+            assert!(verify_password(PASSWORD, hashed).is_err(), "{}", hashed);
+        }
+        // A PHC string may leave out the hash, but then nothing matches it
+        assert_eq!(verify_password(PASSWORD, "$pbkdf2-sha256$i=1000,l=32$MDEyMzQ1Njc4OWFiY2RlZg"), Ok(false));
+    }
+
+    #[test]
+    fn pbkdf2_iterations_are_capped() {
+        let over = MAX_PBKDF2_ITERATIONS + 1;
+        for hashed in [
+            format!("pbkdf2_sha256${}$seasalt$3xmXbyk2QpiyNcnoBbzRPwEBsYPTbDlRdtmLyBvQltA=", over),
+            format!("pbkdf2_sha256${}$seasalt$3xmXbyk2QpiyNcnoBbzRPwEBsYPTbDlRdtmLyBvQltA=", u32::MAX),
+            format!("$pbkdf2-sha256$i={},l=32$MDEyMzQ1Njc4OWFiY2RlZg$yqSq2SygY1sB4EcH9f2FG0JTMES+wqLsOT5YmiRBplI", over),
+        ] {
+            let error = verify_password(PASSWORD, &hashed).unwrap_err();
+            assert!(error.contains("exceed the limit"), "{}: {}", hashed, error);
+        }
+    }
+}
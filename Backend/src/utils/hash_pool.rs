@@ -0,0 +1,232 @@
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Semaphore};
+
+use crate::config::hashing::HashingConfig;
+use crate::metrics::METRICS;
+use crate::utils::hash::{hash_password, verify_password};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs Argon2 (and legacy) hashing on its own threads, off the actix workers
+/// and tokio's shared blocking pool (which file and email jobs also use).
+/// `workers` bounds how many jobs hash at once, one per thread; `queue` bounds
+/// how many may be waiting or running, so a login burst is shed with 503
+/// instead of slowing every other request down.
+#[derive(Clone)]
+pub struct HashPool {
+    jobs: mpsc::Sender<Job>,
+    workers: Arc<Semaphore>,
+    queue: Arc<Semaphore>,
+    queue_timeout: Duration,
+    retry_after_secs: u64,
+}
+
+#[derive(Debug)]
+pub enum HashPoolError {
+    Saturated { retry_after_secs: u64 },
+    Failed(String),
+}
+
+impl fmt::Display for HashPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashPoolError::Saturated { .. } => write!(f, "Password hashing queue is saturated"),
+            HashPoolError::Failed(e) => write!(f, "Password hashing failed: {}", e),
+        }
+    }
+}
+
+impl HashPool {
+    pub fn new(config: &HashingConfig) -> Self {
+        let workers = config.max_concurrent.max(1);
+        Self {
+            jobs: spawn_workers(workers),
+            workers: Arc::new(Semaphore::new(workers)),
+            queue: Arc::new(Semaphore::new(workers + config.max_queue)),
+            queue_timeout: Duration::from_millis(config.queue_timeout_ms),
+            retry_after_secs: config.retry_after_secs,
+        }
+    }
+
+    /// Hash a new password with Argon2
+    pub async fn hash(&self, password: &str) -> Result<String, HashPoolError> {
+        let password = password.to_string();
+        self.run("hash", move || hash_password(&password)).await?
+            .map_err(HashPoolError::Failed)
+    }
+
+    /// Verify a password against a stored hash of any supported scheme
+    pub async fn verify(&self, password: &str, hashed: &str) -> Result<bool, HashPoolError> {
+        let password = password.to_string();
+        let hashed = hashed.to_string();
+        self.run("verify", move || verify_password(&password, &hashed)).await?
+            .map_err(HashPoolError::Failed)
+    }
+
+    async fn run<T, F>(&self, operation: &'static str, job: F) -> Result<T, HashPoolError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let saturated = HashPoolError::Saturated { retry_after_secs: self.retry_after_secs };
+
+        // Reject immediately once the queue is full
+        let _slot = match Arc::clone(&self.queue).try_acquire_owned() {
+            Ok(slot) => slot,
+            Err(_) => {
+                tracing::warn!("Hash pool queue full, shedding request");
+                return Err(saturated);
+            }
+        };
+
+        let enqueued = Instant::now();
+        let acquired = tokio::time::timeout(self.queue_timeout, Arc::clone(&self.workers).acquire_owned()).await;
+        let queue_time = enqueued.elapsed();
+        METRICS
+            .password_hash_queue_wait
+            .with_label_values(&[operation])
+            .observe(queue_time.as_secs_f64());
+        let permit = match acquired {
+            Ok(Ok(permit)) => permit,
+            _ => {
+                tracing::warn!("Hash pool queue wait exceeded {:?}", self.queue_timeout);
+                return Err(saturated);
+            }
+        };
+
+        // Holding a permit, the job starts on a free thread straight away
+        let started = Instant::now();
+        let (done, result) = oneshot::channel();
+        self.jobs
+            .send(Box::new(move || {
+                let _permit = permit;
+                let _ = done.send(job());
+            }))
+            .map_err(|_| HashPoolError::Failed("hashing threads have stopped".into()))?;
+        let result = result
+            .await
+            .map_err(|_| HashPoolError::Failed("hashing job panicked".into()))?;
+
+        let hash_time = started.elapsed();
+        METRICS
+            .password_hash_duration
+            .with_label_values(&[operation])
+            .observe(hash_time.as_secs_f64());
+        tracing::debug!(
+            queue_ms = queue_time.as_millis() as u64,
+            hash_ms = hash_time.as_millis() as u64,
+            "Password hashing job finished"
+        );
+
+        Ok(result)
+    }
+}
+
+// One thread per worker permit, taking jobs in turn. They exit once every
+// clone of the pool is dropped.
+fn spawn_workers(count: usize) -> mpsc::Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for n in 0..count {
+        let receiver = Arc::clone(&receiver);
+        std::thread::Builder::new()
+            .name(format!("password-hash-{}", n))
+            .spawn(move || loop {
+                let job = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                let Ok(job) = job else { return };
+                // A panicking job drops its result sender; the thread carries on
+                let _ = catch_unwind(AssertUnwindSafe(job));
+            })
+            .expect("Failed to start a password hashing thread");
+    }
+
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use actix_web::{http::StatusCode, ResponseError};
+
+    fn pool(max_concurrent: usize, max_queue: usize, queue_timeout_ms: u64) -> HashPool {
+        HashPool::new(&HashingConfig { max_concurrent, max_queue, queue_timeout_ms, retry_after_secs: 7 })
+    }
+
+    // Occupy a hashing thread until the returned sender is dropped; the task
+    // ends once the job has left the queue
+    async fn block_worker(pool: &HashPool) -> (mpsc::Sender<()>, tokio::task::JoinHandle<Result<(), HashPoolError>>) {
+        let (release, blocked) = mpsc::channel::<()>();
+        let (started, running) = oneshot::channel();
+        let busy = pool.clone();
+        let task = tokio::spawn(async move {
+            busy.run("test", move || {
+                let _ = started.send(());
+                let _ = blocked.recv();
+            })
+            .await
+        });
+        running.await.unwrap();
+        (release, task)
+    }
+
+    #[tokio::test]
+    async fn runs_jobs_on_its_own_threads() {
+        let pool = pool(2, 0, 1000);
+        let thread = pool.run("test", || std::thread::current().name().map(str::to_string)).await.unwrap();
+        assert!(thread.unwrap().starts_with("password-hash-"));
+
+        let hashed = pool.hash("Correct-Horse-Battery-9!").await.unwrap();
+        assert!(pool.verify("Correct-Horse-Battery-9!", &hashed).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn sheds_requests_when_the_queue_is_full() {
+        let pool = pool(1, 0, 60_000);
+        let (release, blocked) = block_worker(&pool).await;
+
+        let error = pool.verify("password", "hash").await.unwrap_err();
+        assert!(matches!(error, HashPoolError::Saturated { retry_after_secs: 7 }));
+
+        let response = AppError::from(error).error_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "7");
+
+        drop(release);
+        blocked.await.unwrap().unwrap();
+        assert!(pool.run("test", || ()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn sheds_requests_that_wait_too_long() {
+        let pool = pool(1, 1, 50);
+        let (release, _blocked) = block_worker(&pool).await;
+
+        // Queued behind the blocked job until the timeout
+        let started = Instant::now();
+        let error = pool.run("test", || ()).await.unwrap_err();
+        assert!(matches!(error, HashPoolError::Saturated { retry_after_secs: 7 }));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let response = AppError::from(error).error_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "7");
+        drop(release);
+    }
+
+    #[tokio::test]
+    async fn survives_a_panicking_job() {
+        let pool = pool(1, 0, 1000);
+
+        let error = pool.run("test", || panic!("hashing bug")).await.unwrap_err();
+        assert!(matches!(error, HashPoolError::Failed(_)));
+        assert_eq!(pool.run("test", || 42).await.unwrap(), 42);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod otp;
-pub mod hash;
\ No newline at end of file
+pub mod hash;
+pub mod hash_pool;
+pub mod email;
+pub mod token;
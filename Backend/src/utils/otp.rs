@@ -6,6 +6,8 @@ use rand::{Rng, thread_rng};
 use std::env; // needed for dyn_render()
 
 use crate::models::otptemplate::OtpEmailTemplate;
+use crate::metrics::METRICS;
+use crate::utils::email::tag_request_id;
 
 pub fn generate_otp() -> String {
     let mut rng = thread_rng();
@@ -13,40 +15,40 @@ pub fn generate_otp() -> String {
     otp.to_string()
 }
 
-// Build mailer function
-pub fn build_mailer() -> SmtpTransport {
+// Build mailer function (mail is sent off the request path, so a missing
+// setting is an error rather than a panic)
+pub fn build_mailer() -> anyhow::Result<SmtpTransport> {
     // Load credentials from env
-    let smtp_user = env::var("SMTP_USER").expect("SMTP_USER must be set.");
-    let smtp_pass = env::var("SMTP_PASS").expect("SMTP_PASS must be set");
+    let smtp_user = env::var("SMTP_USER").map_err(|_| anyhow::anyhow!("SMTP_USER must be set"))?;
+    let smtp_pass = env::var("SMTP_PASS").map_err(|_| anyhow::anyhow!("SMTP_PASS must be set"))?;
     let smtp_host = env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string());
     let smtp_port: u16 = env::var("SMTP_PORT")
         .unwrap_or_else(|_| "587".to_string())
         .parse()
-        .expect("SMTP_PORT must be a number");
+        .map_err(|_| anyhow::anyhow!("SMTP_PORT must be a number"))?;
 
     let creds = Credentials::new(smtp_user, smtp_pass);
 
-    SmtpTransport::starttls_relay(&smtp_host)
-        .unwrap()
+    Ok(SmtpTransport::starttls_relay(&smtp_host)?
         .port(smtp_port)
         .credentials(creds)
-        .build()
+        .build())
 }
 
 // Send OTP function
-pub fn send_otp_email(to: &str, otp: &str) -> anyhow::Result<()> {
-    let from_address = env::var("SMTP_FROM").expect("SMTP_FROM must be set");
+pub fn send_otp_email(to: &str, otp: &str, expiry_minutes: i64) -> anyhow::Result<()> {
+    let from_address = env::var("SMTP_FROM").map_err(|_| anyhow::anyhow!("SMTP_FROM must be set"))?;
 
     // Render HTML body
     let template = OtpEmailTemplate {
         email: to,
         otp,
-        expiry_minutes: config.expiry_minutes,
+        expiry_minutes: expiry_minutes as u32,
     };
 
     let html_body = template.dyn_render()?;
 
-    let email = Message::builder()
+    let mut email = Message::builder()
         .from(from_address.parse()?)
         .to(to.parse()?)
         .subject("Your password reset code")
@@ -55,19 +57,17 @@ pub fn send_otp_email(to: &str, otp: &str) -> anyhow::Result<()> {
                 .header(header::ContentType::TEXT_HTML)
                 .body(html_body),
         )?;
+    tag_request_id(&mut email);
 
-    let mailer = build_mailer();
-
-    println!("📧 Sent OTP {otp} to {to}"); // Debug log only!
-
-    match mailer.send(&email) {
-        Ok(response) => {
-            println!("✅ Email sent! Server response: {:?}", response);
+    match build_mailer().and_then(|mailer| Ok(mailer.send(&email)?)) {
+        Ok(_) => {
+            METRICS.otp_emails.with_label_values(&["success"]).inc();
+            tracing::info!("OTP email sent");
             Ok(())
         }
-        Err(err) => {
-            eprintln!("❌ Failed to send email: {:?}", err);
-            Err(err.into())
+        Err(e) => {
+            METRICS.otp_emails.with_label_values(&["failure"]).inc();
+            Err(e)
         }
     }
 }
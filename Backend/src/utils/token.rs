@@ -0,0 +1,39 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+
+/// Random URL-safe token for links sent by email (256 bits)
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// SHA-256 hex digest; only this is stored, never the token or code itself
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// HMAC-SHA256 of `message`, URL-safe base64; used for signed links
+pub fn sign(key: &str, message: &str) -> String {
+    URL_SAFE_NO_PAD.encode(mac(key, message).finalize().into_bytes())
+}
+
+/// Constant-time check of a signature produced by `sign`
+pub fn verify_signature(key: &str, message: &str, signature: &str) -> bool {
+    match URL_SAFE_NO_PAD.decode(signature) {
+        Ok(bytes) => mac(key, message).verify_slice(&bytes).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn mac(key: &str, message: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+    mac
+}
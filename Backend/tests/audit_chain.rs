@@ -0,0 +1,87 @@
+//! `verify_chain` against a real audit log: an intact chain verifies, and
+//! the first tampered event is reported. The append-only trigger is switched
+//! off only inside the transaction that tampers with a row.
+use sqlx::PgPool;
+
+use backend::models::audit::{AuditEventType, AuditOutcome};
+use backend::services::audit::{self, AuditEvent};
+
+// Three events, the second one with a request id
+async fn append_events(pool: &PgPool) -> Vec<i64> {
+    for request_id in [None, Some("req-2"), None] {
+        let event = AuditEvent {
+            request_id: request_id.map(str::to_string),
+            details: serde_json::json!({ "reason": "invalid_password" }),
+            ..AuditEvent::new(AuditEventType::LoginFailure, AuditOutcome::Failure)
+        };
+        audit::record(pool, event).await;
+    }
+    sqlx::query_scalar("SELECT id FROM audit_events ORDER BY id").fetch_all(pool).await.unwrap()
+}
+
+// Run `statement` on the log with the append-only trigger disabled
+async fn tamper(pool: &PgPool, statement: &str, id: i64) {
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query("ALTER TABLE audit_events DISABLE TRIGGER audit_events_append_only")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    sqlx::query(statement).bind(id).execute(&mut *tx).await.unwrap();
+    sqlx::query("ALTER TABLE audit_events ENABLE TRIGGER audit_events_append_only")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[sqlx::test]
+async fn intact_chain_verifies(pool: PgPool) {
+    let ids = append_events(&pool).await;
+
+    let report = audit::verify_chain(&pool).await.unwrap();
+    assert_eq!(report.checked, 3);
+    assert!(report.broken.is_none());
+
+    let head: String = sqlx::query_scalar("SELECT hash FROM audit_events WHERE id = $1")
+        .bind(ids[2])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(report.head, Some(head));
+}
+
+#[sqlx::test]
+async fn reports_the_first_modified_event(pool: PgPool) {
+    let ids = append_events(&pool).await;
+    tamper(&pool, "UPDATE audit_events SET outcome = 'success' WHERE id = $1", ids[1]).await;
+
+    let report = audit::verify_chain(&pool).await.unwrap();
+    assert_eq!(report.checked, 1);
+    assert!(report.head.is_none());
+    let broken = report.broken.expect("broken chain");
+    assert_eq!(broken.id, ids[1]);
+    assert_eq!(broken.reason, "does not match its hash");
+}
+
+#[sqlx::test]
+async fn reports_a_removed_event(pool: PgPool) {
+    let ids = append_events(&pool).await;
+    tamper(&pool, "DELETE FROM audit_events WHERE id = $1", ids[1]).await;
+
+    let report = audit::verify_chain(&pool).await.unwrap();
+    let broken = report.broken.expect("broken chain");
+    assert_eq!(broken.id, ids[2]);
+    assert_eq!(broken.reason, "does not link to the previous event");
+}
+
+#[sqlx::test]
+async fn trigger_rejects_changes(pool: PgPool) {
+    let ids = append_events(&pool).await;
+
+    let updated = sqlx::query("UPDATE audit_events SET outcome = 'success' WHERE id = $1")
+        .bind(ids[0])
+        .execute(&pool)
+        .await;
+    assert!(updated.is_err());
+    assert!(audit::verify_chain(&pool).await.unwrap().broken.is_none());
+}
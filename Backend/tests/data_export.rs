@@ -0,0 +1,67 @@
+//! What a personal data export contains
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use backend::models::audit::{AuditEventType, AuditOutcome};
+use backend::services::audit::{self, AuditEvent};
+use backend::services::data_export::collect_user_data;
+
+async fn create_user(pool: &PgPool, name: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES ($1, $2, $3, 'x')")
+        .bind(user_id)
+        .bind(name)
+        .bind(format!("{}@example.com", name))
+        .execute(pool)
+        .await
+        .expect("create user");
+    user_id
+}
+
+async fn record(pool: &PgPool, event_type: AuditEventType, actor_id: Option<Uuid>, target_user_id: Uuid) {
+    let event = AuditEvent {
+        actor_id,
+        target_user_id: Some(target_user_id),
+        ip_address: Some("203.0.113.7".to_string()),
+        ..AuditEvent::new(event_type, AuditOutcome::Success)
+    };
+    audit::record(pool, event).await;
+}
+
+#[sqlx::test]
+async fn export_includes_the_users_audit_events(pool: PgPool) {
+    let alice = create_user(&pool, "export_alice").await;
+    let bob = create_user(&pool, "export_bob").await;
+
+    record(&pool, AuditEventType::LoginSuccess, None, alice).await;
+    record(&pool, AuditEventType::PasswordChanged, None, alice).await;
+    // Alice acting as an admin on Bob's account
+    record(&pool, AuditEventType::AdminAction, Some(alice), bob).await;
+    // Nothing to do with Alice
+    record(&pool, AuditEventType::LoginFailure, None, bob).await;
+
+    let archive = collect_user_data(&pool, alice).await.unwrap();
+    let events: Vec<_> = archive
+        .audit_events
+        .iter()
+        .map(|e| (e.event_type, e.actor_id, e.target_user_id))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (AuditEventType::LoginSuccess, None, Some(alice)),
+            (AuditEventType::PasswordChanged, None, Some(alice)),
+            (AuditEventType::AdminAction, Some(alice), Some(bob)),
+        ]
+    );
+
+    // As written to the archive, without the chain hashes
+    let json = serde_json::to_value(&archive).unwrap();
+    let first = &json["audit_events"][0];
+    assert_eq!(first["event_type"], "login_success");
+    assert_eq!(first["ip_address"], "203.0.113.7");
+    assert!(first.get("hash").is_none());
+
+    let bobs = collect_user_data(&pool, bob).await.unwrap();
+    assert_eq!(bobs.audit_events.len(), 2);
+}
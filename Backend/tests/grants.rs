@@ -0,0 +1,294 @@
+//! Resource grants: who `can_access` lets in, and what sharing reveals
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{http::StatusCode, test, web, App};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use backend::auth::extractor::AuthenticatedUser;
+use backend::auth::middleware::AuthMiddleware;
+use backend::config::app::AppConfig;
+use backend::config::sharing::SharingConfig;
+use backend::models::claims::Claims;
+use backend::models::grant::{GrantPermission, ResourceType};
+use backend::routes::auth_routes::shared_link_routes;
+use backend::routes::grant_routes::grant_routes;
+use backend::services::grants::{can_access, share_url};
+use backend::services::sessions::start_session;
+
+use GrantPermission::{Read, Write};
+
+async fn create_user(pool: &PgPool, name: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES ($1, $2, $3, 'x')")
+        .bind(user_id)
+        .bind(name)
+        .bind(format!("{}@example.com", name))
+        .execute(pool)
+        .await
+        .expect("create user");
+    user_id
+}
+
+async fn create_export(pool: &PgPool, owner: Uuid) -> Uuid {
+    sqlx::query_scalar("INSERT INTO data_exports (user_id) VALUES ($1) RETURNING id")
+        .bind(owner)
+        .fetch_one(pool)
+        .await
+        .expect("create export")
+}
+
+async fn create_organization(pool: &PgPool, slug: &str, members: &[Uuid]) -> Uuid {
+    let organization_id: Uuid = sqlx::query_scalar("INSERT INTO organizations (name, slug) VALUES ($1, $1) RETURNING id")
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .expect("create organization");
+    for member in members {
+        sqlx::query("INSERT INTO memberships (organization_id, user_id) VALUES ($1, $2)")
+            .bind(organization_id)
+            .bind(member)
+            .execute(pool)
+            .await
+            .expect("add member");
+    }
+    organization_id
+}
+
+// A grant on the export for a user, an organization, or (neither) a link
+async fn grant(pool: &PgPool, export_id: Uuid, owner: Uuid, user: Option<Uuid>, organization: Option<Uuid>, permission: &str) {
+    let grantee_type = match (user, organization) {
+        (Some(_), _) => "user",
+        (_, Some(_)) => "organization",
+        _ => "link",
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO resource_grants
+            (resource_type, resource_id, owner_id, grantee_type, grantee_user_id, grantee_organization_id,
+             permission, expires_at)
+        VALUES ('data_export', $1, $2, $3, $4, $5, $6, NOW() + INTERVAL '1 hour')
+        "#,
+    )
+    .bind(export_id)
+    .bind(owner)
+    .bind(grantee_type)
+    .bind(user)
+    .bind(organization)
+    .bind(permission)
+    .execute(pool)
+    .await
+    .expect("create grant");
+}
+
+// What the extractor yields for a signed-in user
+fn authenticated(user_id: Uuid, organization_id: Option<Uuid>) -> AuthenticatedUser {
+    AuthenticatedUser {
+        user_id,
+        session_id: Uuid::nil(),
+        organization_id,
+        impersonator_id: None,
+        claims: Claims {
+            sub: user_id.to_string(),
+            sid: Uuid::nil().to_string(),
+            org: organization_id.map(|id| id.to_string()),
+            act: None,
+            exp: 0,
+        },
+    }
+}
+
+async fn allowed(pool: &PgPool, user: &AuthenticatedUser, export_id: Uuid, owner: Uuid, needed: GrantPermission) -> bool {
+    can_access(pool, user, ResourceType::DataExport, export_id, owner, needed).await.unwrap()
+}
+
+#[sqlx::test]
+async fn owners_always_have_access(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let export_id = create_export(&pool, owner).await;
+
+    assert!(allowed(&pool, &authenticated(owner, None), export_id, owner, Write).await);
+}
+
+#[sqlx::test]
+async fn others_need_a_grant(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let reader = create_user(&pool, "reader").await;
+    let stranger = create_user(&pool, "stranger").await;
+    let export_id = create_export(&pool, owner).await;
+    let other_export = create_export(&pool, owner).await;
+
+    grant(&pool, export_id, owner, Some(reader), None, "read").await;
+
+    let reader = authenticated(reader, None);
+    assert!(allowed(&pool, &reader, export_id, owner, Read).await);
+    assert!(!allowed(&pool, &reader, export_id, owner, Write).await);
+    // The grant is for that resource only
+    assert!(!allowed(&pool, &reader, other_export, owner, Read).await);
+    assert!(!allowed(&pool, &authenticated(stranger, None), export_id, owner, Read).await);
+}
+
+#[sqlx::test]
+async fn write_grants_include_read(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let editor = create_user(&pool, "editor").await;
+    let export_id = create_export(&pool, owner).await;
+
+    grant(&pool, export_id, owner, Some(editor), None, "write").await;
+
+    let editor = authenticated(editor, None);
+    assert!(allowed(&pool, &editor, export_id, owner, Read).await);
+    assert!(allowed(&pool, &editor, export_id, owner, Write).await);
+}
+
+#[sqlx::test]
+async fn expired_and_revoked_grants_are_ignored(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let expired = create_user(&pool, "expired").await;
+    let revoked = create_user(&pool, "revoked").await;
+    let export_id = create_export(&pool, owner).await;
+
+    sqlx::query(
+        r#"
+        INSERT INTO resource_grants
+            (resource_type, resource_id, owner_id, grantee_type, grantee_user_id, expires_at, revoked_at)
+        VALUES ('data_export', $1, $2, 'user', $3, NOW() - INTERVAL '1 second', NULL),
+               ('data_export', $1, $2, 'user', $4, NULL, NOW())
+        "#,
+    )
+    .bind(export_id)
+    .bind(owner)
+    .bind(expired)
+    .bind(revoked)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert!(!allowed(&pool, &authenticated(expired, None), export_id, owner, Read).await);
+    assert!(!allowed(&pool, &authenticated(revoked, None), export_id, owner, Read).await);
+}
+
+#[sqlx::test]
+async fn organization_grants_follow_the_active_organization(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let colleague = create_user(&pool, "colleague").await;
+    let organization = create_organization(&pool, "acme", &[owner, colleague]).await;
+    let other_organization = create_organization(&pool, "other", &[colleague]).await;
+    let export_id = create_export(&pool, owner).await;
+
+    grant(&pool, export_id, owner, None, Some(organization), "read").await;
+
+    assert!(allowed(&pool, &authenticated(colleague, Some(organization)), export_id, owner, Read).await);
+    assert!(!allowed(&pool, &authenticated(colleague, Some(other_organization)), export_id, owner, Read).await);
+    assert!(!allowed(&pool, &authenticated(colleague, None), export_id, owner, Read).await);
+}
+
+#[sqlx::test]
+async fn link_grants_give_no_session_access(pool: PgPool) {
+    let owner = create_user(&pool, "owner").await;
+    let stranger = create_user(&pool, "stranger").await;
+    let export_id = create_export(&pool, owner).await;
+
+    grant(&pool, export_id, owner, None, None, "read").await;
+
+    assert!(!allowed(&pool, &authenticated(stranger, None), export_id, owner, Read).await);
+}
+
+// The sharing API and share links behind the authentication middleware, as
+// the server mounts them
+async fn grants_app(
+    pool: PgPool,
+) -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    unsafe { std::env::set_var("JWT_SECRET", "grants-test-secret") };
+
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .app_data(web::Data::new(AppConfig::from_env()))
+            .app_data(web::Data::new(SharingConfig::from_env()))
+            .wrap(AuthMiddleware)
+            .service(grant_routes())
+            .service(shared_link_routes()),
+    )
+    .await
+}
+
+fn share(token: &str, body: serde_json::Value) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri("/api/v1/grants")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(body)
+        .to_request()
+}
+
+#[sqlx::test]
+async fn sharing_by_email_does_not_reveal_accounts(pool: PgPool) {
+    let app = grants_app(pool.clone()).await;
+    let owner = create_user(&pool, "owner").await;
+    let reader = create_user(&pool, "reader").await;
+    let export_id = create_export(&pool, owner).await;
+    let token = start_session(&pool, owner, &test::TestRequest::default().to_http_request(), 1)
+        .await
+        .unwrap();
+
+    // An account, the same one again, and an address nobody uses
+    let mut responses = Vec::new();
+    for email in ["reader@example.com", "reader@example.com", "nobody@example.com"] {
+        let body = json!({
+            "resource_type": "data_export",
+            "resource_id": export_id,
+            "grantee": { "type": "user", "email": email },
+        });
+        let res = test::call_service(&app, share(&token, body)).await;
+        responses.push((res.status(), test::read_body(res).await));
+    }
+    assert_eq!(responses[0].0, StatusCode::ACCEPTED);
+    assert!(responses.iter().all(|response| *response == responses[0]));
+
+    assert!(allowed(&pool, &authenticated(reader, None), export_id, owner, Read).await);
+    let grants: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM resource_grants").fetch_one(&pool).await.unwrap();
+    assert_eq!(grants, 1);
+}
+
+#[sqlx::test]
+async fn data_exports_cannot_be_shared_by_link(pool: PgPool) {
+    let app = grants_app(pool.clone()).await;
+    let owner = create_user(&pool, "owner").await;
+    let export_id = create_export(&pool, owner).await;
+    let token = start_session(&pool, owner, &test::TestRequest::default().to_http_request(), 1)
+        .await
+        .unwrap();
+
+    let body = json!({
+        "resource_type": "data_export",
+        "resource_id": export_id,
+        "grantee": { "type": "link" },
+        "expires_at": chrono::Utc::now() + chrono::Duration::hours(1),
+    });
+    let res = test::call_service(&app, share(&token, body)).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let grants: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM resource_grants").fetch_one(&pool).await.unwrap();
+    assert_eq!(grants, 0);
+}
+
+#[sqlx::test]
+async fn existing_data_export_links_stop_working(pool: PgPool) {
+    let app = grants_app(pool.clone()).await;
+    let owner = create_user(&pool, "owner").await;
+    let export_id = create_export(&pool, owner).await;
+
+    // A link handed out before they were refused
+    grant(&pool, export_id, owner, None, None, "read").await;
+    let grant_id: Uuid = sqlx::query_scalar("SELECT id FROM resource_grants").fetch_one(&pool).await.unwrap();
+
+    let url = share_url(&AppConfig::from_env(), &SharingConfig::from_env(), grant_id, chrono::Utc::now() + chrono::Duration::hours(1));
+    let path = &url[url.find("/api/v1/shared/").unwrap()..];
+    let res = test::try_call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+    let status = match res {
+        Ok(res) => res.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,183 @@
+//! Impersonation never hands out admin privileges: admins cannot be
+//! impersonated, and an impersonation token is refused by every permission
+//! guard, whatever roles the impersonated user holds. Nor can it take the
+//! user's data out through an export or a share.
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{http::StatusCode, test, web, App};
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+use backend::auth::middleware::AuthMiddleware;
+use backend::config::app::AppConfig;
+use backend::config::export::ExportConfig;
+use backend::config::impersonation::ImpersonationConfig;
+use backend::config::sharing::SharingConfig;
+use backend::routes::admin_routes::admin_routes;
+use backend::routes::grant_routes::grant_routes;
+use backend::routes::user_routes::protected_routes;
+use backend::services::rbac::{grant_role, PermissionCache};
+use backend::services::sessions::start_session;
+
+async fn create_user(pool: &PgPool, name: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES ($1, $2, $3, 'x')")
+        .bind(user_id)
+        .bind(name)
+        .bind(format!("{}@example.com", name))
+        .execute(pool)
+        .await
+        .expect("create user");
+    user_id
+}
+
+async fn sign_in(pool: &PgPool, user_id: Uuid) -> String {
+    start_session(pool, user_id, &test::TestRequest::default().to_http_request(), 1)
+        .await
+        .expect("session")
+}
+
+// The admin, account and sharing APIs behind the authentication middleware,
+// as the server mounts them.
+// Permissions are not cached, so role changes apply to the next request.
+async fn admin_app(
+    pool: PgPool,
+) -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    unsafe { std::env::set_var("JWT_SECRET", "impersonation-test-secret") };
+
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .app_data(web::Data::new(PermissionCache::new(Duration::ZERO)))
+            .app_data(web::Data::new(ImpersonationConfig::from_env()))
+            .app_data(web::Data::new(AppConfig::from_env()))
+            .app_data(web::Data::new(ExportConfig::from_env()))
+            .app_data(web::Data::new(SharingConfig::from_env()))
+            .wrap(AuthMiddleware)
+            .service(protected_routes())
+            .service(grant_routes())
+            .service(admin_routes()),
+    )
+    .await
+}
+
+// Guards refuse a request with an error rather than a response; render both alike
+async fn respond<S, B>(app: &S, req: actix_http::Request) -> (StatusCode, serde_json::Value)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+{
+    let res = match test::try_call_service(app, req).await {
+        Ok(res) => res.map_into_boxed_body().into_parts().1,
+        Err(e) => e.error_response(),
+    };
+    let status = res.status();
+    let body = actix_web::body::to_bytes(res.into_body()).await.unwrap_or_default();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn post(uri: &str, token: &str) -> actix_http::Request {
+    post_json(uri, token, serde_json::json!({}))
+}
+
+fn post_json(uri: &str, token: &str, body: serde_json::Value) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri(uri)
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(body)
+        .to_request()
+}
+
+fn get(uri: &str, token: &str) -> actix_http::Request {
+    test::TestRequest::get()
+        .uri(uri)
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request()
+}
+
+#[sqlx::test]
+async fn admins_cannot_be_impersonated(pool: PgPool) {
+    let app = admin_app(pool.clone()).await;
+    let support = create_user(&pool, "support").await;
+    let admin = create_user(&pool, "admin").await;
+    grant_role(&pool, support, "admin", None).await.unwrap();
+    grant_role(&pool, admin, "admin", None).await.unwrap();
+
+    let token = sign_in(&pool, support).await;
+    let (status, body) = respond(&app, post(&format!("/api/v1/admin/users/{}/impersonate", admin), &token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "privileged_account");
+
+    let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE user_id = $1")
+        .bind(admin)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sessions, 0);
+}
+
+// Support signs in and impersonates a customer; returns both tokens
+async fn impersonate_customer<S, B>(app: &S, pool: &PgPool, customer: Uuid) -> (String, String)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+{
+    let support = create_user(pool, "support").await;
+    grant_role(pool, support, "admin", None).await.unwrap();
+
+    let token = sign_in(pool, support).await;
+    let (status, body) = respond(app, post(&format!("/api/v1/admin/users/{}/impersonate", customer), &token)).await;
+    assert_eq!(status, StatusCode::OK);
+    (token, body["token"].as_str().unwrap().to_string())
+}
+
+#[sqlx::test]
+async fn impersonation_token_is_refused_by_admin_routes(pool: PgPool) {
+    let app = admin_app(pool.clone()).await;
+    let customer = create_user(&pool, "customer").await;
+    let (token, impersonation_token) = impersonate_customer(&app, &pool, customer).await;
+
+    // Even once the user holds every permission
+    grant_role(&pool, customer, "admin", None).await.unwrap();
+    assert_eq!(respond(&app, get("/api/v1/admin/users", &token)).await.0, StatusCode::OK);
+
+    for uri in ["/api/v1/admin/users", "/api/v1/admin/roles", "/api/v1/admin/audit-events"] {
+        let (status, body) = respond(&app, get(uri, &impersonation_token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+        assert_eq!(body["code"], "impersonation_forbidden", "{}", uri);
+    }
+}
+
+#[sqlx::test]
+async fn impersonation_token_cannot_export_or_share(pool: PgPool) {
+    let app = admin_app(pool.clone()).await;
+    let customer = create_user(&pool, "customer").await;
+    let (_, impersonation_token) = impersonate_customer(&app, &pool, customer).await;
+
+    let (status, body) = respond(&app, post("/api/v1/me/export", &impersonation_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "impersonation_forbidden");
+
+    // An export the customer requested themselves
+    let export_id: Uuid = sqlx::query_scalar("INSERT INTO data_exports (user_id) VALUES ($1) RETURNING id")
+        .bind(customer)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let (status, _) = respond(&app, get(&format!("/api/v1/me/export/{}", export_id), &impersonation_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let share = serde_json::json!({
+        "resource_type": "data_export",
+        "resource_id": export_id,
+        "grantee": { "type": "user", "email": "support@example.com" },
+    });
+    let (status, body) = respond(&app, post_json("/api/v1/grants", &impersonation_token, share)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "impersonation_forbidden");
+
+    let exports: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM data_exports").fetch_one(&pool).await.unwrap();
+    let grants: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM resource_grants").fetch_one(&pool).await.unwrap();
+    assert_eq!((exports, grants), (1, 0));
+}
@@ -0,0 +1,187 @@
+//! The reset flow end to end. `#[sqlx::test]` creates a fresh database for
+//! each test from `DATABASE_URL` and runs the migrations on it.
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{http::StatusCode, test, web, App};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use backend::auth::breached::BreachedPasswordChecker;
+use backend::auth::password_policy::PasswordPolicy;
+use backend::config::breach::BreachCheckConfig;
+use backend::config::hashing::HashingConfig;
+use backend::config::login::LoginLimitConfig;
+use backend::config::otp::OtpConfig;
+use backend::config::security::SecurityConfig;
+use backend::routes::auth_routes::auth_routes;
+use backend::services::admin_users::{self, Actor};
+use backend::utils::hash_pool::HashPool;
+use backend::utils::token::hash_token;
+
+const EMAIL: &str = "reset-user@example.com";
+const OLD_PASSWORD: &str = "Correct-Horse-Battery-9!";
+const NEW_PASSWORD: &str = "Another-Horse-Battery-7!";
+
+// The operator running the action, as `authctl` records it
+fn operator() -> Actor {
+    Actor { user_id: None, ip_address: None }
+}
+
+async fn create_user(pool: &PgPool, hash_pool: &HashPool) -> Uuid {
+    let hashed = hash_pool.hash(OLD_PASSWORD).await.expect("hash");
+    admin_users::create_user(pool, &operator(), "reset-user", EMAIL, &hashed, true, 5)
+        .await
+        .expect("create user")
+}
+
+// The auth routes with what sign-in and the reset flow need
+async fn auth_app(
+    pool: &PgPool,
+    hash_pool: &HashPool,
+) -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    // Sessions are signed on sign-in
+    unsafe { std::env::set_var("JWT_SECRET", "password-reset-test-secret") };
+
+    let policy = PasswordPolicy::from_config(&SecurityConfig::default());
+    let breach_checker = BreachedPasswordChecker::from_config(&BreachCheckConfig::from_env()).expect("breach index");
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(OtpConfig::from_env()))
+            .app_data(web::Data::new(LoginLimitConfig::from_env()))
+            .app_data(web::Data::new(SecurityConfig::default()))
+            .app_data(web::Data::new(hash_pool.clone()))
+            .app_data(web::Data::new(policy))
+            .app_data(web::Data::new(breach_checker))
+            .service(auth_routes()),
+    )
+    .await
+}
+
+fn login(password: &str) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri("/api/v1/auth/login")
+        .set_json(json!({ "email": EMAIL, "password": password }))
+        .to_request()
+}
+
+// Request a code, then use the one the email would have carried
+async fn reset_password<S, B>(app: &S, pool: &PgPool, user_id: Uuid)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/reset/request")
+        .set_json(json!({ "email": EMAIL }))
+        .to_request();
+    let res = test::call_service(app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let otp: String = sqlx::query_scalar("SELECT otp_code FROM password_resets WHERE user_id = $1 AND NOT used")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("reset code");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/reset/verify")
+        .set_json(json!({
+            "email": EMAIL,
+            "otp": otp,
+            "new_password": NEW_PASSWORD,
+            "confirm_password": NEW_PASSWORD,
+        }))
+        .to_request();
+    let res = test::call_service(app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn forced_reset_is_lifted_by_the_reset_flow(pool: PgPool) {
+    let hash_pool = HashPool::new(&HashingConfig::from_env());
+    let app = auth_app(&pool, &hash_pool).await;
+
+    let user_id = create_user(&pool, &hash_pool).await;
+    admin_users::force_password_reset(&pool, &operator(), user_id).await.expect("force reset");
+
+    // Locked: the old password no longer signs in
+    let res = test::call_service(&app, login(OLD_PASSWORD)).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    reset_password(&app, &pool, user_id).await;
+
+    let res = test::call_service(&app, login(NEW_PASSWORD)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn reverted_email_change_is_lifted_by_the_reset_flow(pool: PgPool) {
+    let hash_pool = HashPool::new(&HashingConfig::from_env());
+    let app = auth_app(&pool, &hash_pool).await;
+    let user_id = create_user(&pool, &hash_pool).await;
+
+    // A confirmed change away from EMAIL, with its undo link
+    let revert_token = "revert-token";
+    sqlx::query("UPDATE users SET email = 'taken-over@example.com' WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .expect("change email");
+    sqlx::query(
+        r#"
+        INSERT INTO email_changes
+            (user_id, old_email, new_email, code_hash, expires_at, confirmed_at, revert_token_hash, revert_expires_at)
+        VALUES ($1, $2, 'taken-over@example.com', '', NOW(), NOW(), $3, NOW() + INTERVAL '1 hour')
+        "#,
+    )
+    .bind(user_id)
+    .bind(EMAIL)
+    .bind(hash_token(revert_token))
+    .execute(&pool)
+    .await
+    .expect("email change");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/email/revert")
+        .set_form([("token", revert_token)])
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // The address is back, but locked until the password is reset
+    let res = test::call_service(&app, login(OLD_PASSWORD)).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    reset_password(&app, &pool, user_id).await;
+
+    let res = test::call_service(&app, login(NEW_PASSWORD)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn reset_request_does_not_reveal_the_account(pool: PgPool) {
+    let hash_pool = HashPool::new(&HashingConfig::from_env());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(OtpConfig::from_env()))
+            .service(auth_routes()),
+    )
+    .await;
+    create_user(&pool, &hash_pool).await;
+
+    // A known account, the same one again (throttled) and an unknown one
+    let mut bodies = Vec::new();
+    for email in [EMAIL, EMAIL, "nobody@example.com"] {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/auth/reset/request")
+            .set_json(json!({ "email": email }))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        bodies.push(test::read_body(res).await);
+    }
+    assert!(bodies.iter().all(|body| *body == bodies[0]));
+}
@@ -0,0 +1,459 @@
+//! Row-level security through `begin_user_scope`: queries in one user's
+//! scope neither see nor change another user's rows, even without a filter.
+//! `rls_isolation.sql` runs the same policies straight from psql.
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{http::StatusCode, test, web, App};
+use serde_json::json;
+use sqlx::PgPool;
+use std::path::Path;
+use uuid::Uuid;
+
+use backend::auth::breached::BreachedPasswordChecker;
+use backend::auth::extractor::AuthenticatedUser;
+use backend::auth::middleware::AuthMiddleware;
+use backend::auth::password_policy::PasswordPolicy;
+use backend::auth::policy::PolicySet;
+use backend::config::account_deletion::AccountDeletionConfig;
+use backend::config::app::AppConfig;
+use backend::config::breach::BreachCheckConfig;
+use backend::config::email_change::EmailChangeConfig;
+use backend::config::hashing::HashingConfig;
+use backend::config::invitation::InvitationConfig;
+use backend::config::login::LoginLimitConfig;
+use backend::config::otp::OtpConfig;
+use backend::config::security::SecurityConfig;
+use backend::database::rls::{begin_organization_scope, begin_scope, begin_user_scope};
+use backend::models::claims::Claims;
+use backend::routes::auth_routes::auth_routes;
+use backend::routes::org_routes::{invitation_routes, organization_routes};
+use backend::routes::user_routes::protected_routes;
+use backend::services::admin_users::{self, Actor};
+use backend::services::sessions::start_session;
+use backend::utils::hash_pool::HashPool;
+use backend::utils::token::{hash_token, sign};
+
+// A user with two sessions
+async fn create_user(pool: &PgPool, name: &str) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES ($1, $2, $3, 'x')")
+        .bind(user_id)
+        .bind(name)
+        .bind(format!("{}@example.com", name))
+        .execute(pool)
+        .await
+        .expect("create user");
+
+    for _ in 0..2 {
+        sqlx::query("INSERT INTO sessions (user_id, expires_at) VALUES ($1, NOW() + INTERVAL '1 hour')")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .expect("create session");
+    }
+    user_id
+}
+
+// What the extractor yields for a signed-in user
+fn authenticated(user_id: Uuid) -> AuthenticatedUser {
+    AuthenticatedUser {
+        user_id,
+        session_id: Uuid::nil(),
+        organization_id: None,
+        impersonator_id: None,
+        claims: Claims {
+            sub: user_id.to_string(),
+            sid: Uuid::nil().to_string(),
+            org: None,
+            act: None,
+            exp: 0,
+        },
+    }
+}
+
+#[sqlx::test]
+async fn user_scope_cannot_read_another_users_rows(pool: PgPool) {
+    let alice = create_user(&pool, "rls_alice").await;
+    let bob = create_user(&pool, "rls_bob").await;
+
+    let mut tx = begin_user_scope(&pool, &authenticated(alice)).await.unwrap();
+
+    let bob_found: Option<Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE id = $1")
+        .bind(bob)
+        .fetch_optional(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(bob_found, None);
+
+    // A query that forgot its user filter
+    let users: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM users").fetch_all(&mut *tx).await.unwrap();
+    assert_eq!(users, [alice]);
+    let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions").fetch_one(&mut *tx).await.unwrap();
+    assert_eq!(sessions, 2);
+}
+
+#[sqlx::test]
+async fn user_scope_cannot_update_another_users_rows(pool: PgPool) {
+    let alice = create_user(&pool, "rls_alice").await;
+    let bob = create_user(&pool, "rls_bob").await;
+
+    let mut tx = begin_user_scope(&pool, &authenticated(alice)).await.unwrap();
+
+    let renamed = sqlx::query("UPDATE users SET username = 'taken_over' WHERE id = $1")
+        .bind(bob)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(renamed.rows_affected(), 0);
+
+    // Unfiltered writes only reach Alice's rows
+    let revoked = sqlx::query("UPDATE sessions SET revoked_at = NOW()").execute(&mut *tx).await.unwrap();
+    assert_eq!(revoked.rows_affected(), 2);
+
+    // Nor can rows be created on Bob's behalf
+    let inserted = sqlx::query("INSERT INTO sessions (user_id, expires_at) VALUES ($1, NOW() + INTERVAL '1 hour')")
+        .bind(bob)
+        .execute(&mut *tx)
+        .await;
+    let error = inserted.expect_err("insert for another user");
+    assert_eq!(error.as_database_error().and_then(|e| e.code()).as_deref(), Some("42501"));
+    drop(tx);
+
+    let mut tx = begin_user_scope(&pool, &authenticated(alice)).await.unwrap();
+    sqlx::query("UPDATE sessions SET revoked_at = NOW()").execute(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    // Checked outside any scope
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+        .bind(bob)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(username, "rls_bob");
+    let active: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(bob)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(active, 2);
+}
+
+async fn create_organization(pool: &PgPool, slug: &str, members: &[Uuid]) -> Uuid {
+    let organization_id: Uuid = sqlx::query_scalar("INSERT INTO organizations (name, slug) VALUES ($1, $1) RETURNING id")
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .expect("create organization");
+    for member in members {
+        sqlx::query("INSERT INTO memberships (organization_id, user_id) VALUES ($1, $2)")
+            .bind(organization_id)
+            .bind(member)
+            .execute(pool)
+            .await
+            .expect("add member");
+    }
+    organization_id
+}
+
+async fn invite(pool: &PgPool, organization_id: Uuid, email: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO invitations (organization_id, email, role, token_hash, expires_at)
+        VALUES ($1, $2, 'member', $2, NOW() + INTERVAL '1 hour')
+        "#,
+    )
+    .bind(organization_id)
+    .bind(email)
+    .execute(pool)
+    .await
+    .expect("invite");
+}
+
+#[sqlx::test]
+async fn organization_scope_requires_membership(pool: PgPool) {
+    let alice = create_user(&pool, "rls_alice").await;
+    let bob = create_user(&pool, "rls_bob").await;
+    let bobs_organization = create_organization(&pool, "rls-bob-org", &[bob]).await;
+
+    let scope = begin_organization_scope(&pool, &authenticated(alice), bobs_organization).await.unwrap();
+    assert!(scope.is_none());
+}
+
+#[sqlx::test]
+async fn organization_scope_is_limited_to_that_organization(pool: PgPool) {
+    let alice = create_user(&pool, "rls_alice").await;
+    let bob = create_user(&pool, "rls_bob").await;
+    let carol = create_user(&pool, "rls_carol").await;
+    let shared = create_organization(&pool, "rls-shared", &[alice, carol]).await;
+    let other = create_organization(&pool, "rls-other", &[alice, bob]).await;
+    invite(&pool, shared, "shared@example.com").await;
+    invite(&pool, other, "other@example.com").await;
+
+    let mut tx = begin_organization_scope(&pool, &authenticated(alice), shared).await.unwrap().unwrap();
+
+    // Fellow members of that organization, not of Alice's others
+    let mut users: Vec<String> = sqlx::query_scalar("SELECT username FROM users").fetch_all(&mut *tx).await.unwrap();
+    users.sort();
+    assert_eq!(users, ["rls_alice", "rls_carol"]);
+    let invited: Vec<String> = sqlx::query_scalar("SELECT email FROM invitations").fetch_all(&mut *tx).await.unwrap();
+    assert_eq!(invited, ["shared@example.com"]);
+
+    // Other members are read-only, and the other organization is out of reach
+    let renamed = sqlx::query("UPDATE users SET username = 'taken_over' WHERE id = $1")
+        .bind(carol)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(renamed.rows_affected(), 0);
+    let inserted = sqlx::query(
+        "INSERT INTO invitations (organization_id, email, token_hash, expires_at) VALUES ($1, 'x@example.com', 'x', NOW())",
+    )
+    .bind(other)
+    .execute(&mut *tx)
+    .await;
+    let error = inserted.expect_err("invitation to another organization");
+    assert_eq!(error.as_database_error().and_then(|e| e.code()).as_deref(), Some("42501"));
+}
+
+const PASSWORD: &str = "Correct-Horse-Battery-9!";
+const NEW_PASSWORD: &str = "Another-Horse-Battery-7!";
+
+// Writes to these tables fail unless made in a scope (as `uiso_app`), so a
+// handler that runs a query on the table owner's connection fails the request
+async fn reject_unscoped_writes(pool: &PgPool) {
+    sqlx::query(
+        r#"
+        CREATE FUNCTION reject_unscoped_write() RETURNS TRIGGER LANGUAGE plpgsql AS $$
+        BEGIN
+            IF current_user <> 'uiso_app' THEN
+                RAISE EXCEPTION 'unscoped % on %', TG_OP, TG_TABLE_NAME;
+            END IF;
+            RETURN NULL;
+        END
+        $$
+        "#,
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    for table in [
+        "users", "sessions", "failed_logins", "password_history", "email_changes",
+        "account_deletions", "organizations", "memberships", "invitations",
+    ] {
+        sqlx::query(&format!(
+            "CREATE TRIGGER scoped_writes AFTER INSERT OR UPDATE OR DELETE ON {} \
+             FOR EACH STATEMENT EXECUTE FUNCTION reject_unscoped_write()",
+            table
+        ))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+}
+
+// The account and organization APIs behind the authentication middleware, as
+// the server mounts them
+async fn user_app(
+    pool: PgPool,
+) -> impl Service<actix_http::Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    unsafe { std::env::set_var("JWT_SECRET", "rls-isolation-test-secret") };
+
+    let policies = PolicySet::load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("policies")).unwrap();
+    let breach_checker = BreachedPasswordChecker::from_config(&BreachCheckConfig::from_env()).unwrap();
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .app_data(web::Data::new(HashPool::new(&HashingConfig::from_env())))
+            .app_data(web::Data::new(PasswordPolicy::from_config(&SecurityConfig::default())))
+            .app_data(web::Data::new(breach_checker))
+            .app_data(web::Data::new(LoginLimitConfig::from_env()))
+            .app_data(web::Data::new(OtpConfig::from_env()))
+            .app_data(web::Data::new(AppConfig::from_env()))
+            .app_data(web::Data::new(EmailChangeConfig::from_env()))
+            .app_data(web::Data::new(AccountDeletionConfig::from_env()))
+            .app_data(web::Data::new(InvitationConfig::from_env()))
+            .app_data(web::Data::new(policies))
+            .wrap(AuthMiddleware)
+            .service(protected_routes())
+            .service(organization_routes())
+            .service(invitation_routes())
+            .service(auth_routes()),
+    )
+    .await
+}
+
+fn request(method: &str, uri: &str, token: &str, body: serde_json::Value) -> actix_http::Request {
+    let req = match method {
+        "GET" => test::TestRequest::get(),
+        "DELETE" => test::TestRequest::delete(),
+        _ => test::TestRequest::post(),
+    };
+    req.uri(uri)
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(body)
+        .to_request()
+}
+
+async fn call<S, B>(app: &S, req: actix_http::Request) -> (StatusCode, serde_json::Value)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+async fn create_account(pool: &PgPool, name: &str) -> (Uuid, String) {
+    let hashed = HashPool::new(&HashingConfig::from_env()).hash(PASSWORD).await.unwrap();
+    let operator = Actor { user_id: None, ip_address: None };
+    let email = format!("{}@example.com", name);
+    let user_id = admin_users::create_user(pool, &operator, name, &email, &hashed, true, 5).await.unwrap();
+    let token = start_session(pool, user_id, &test::TestRequest::default().to_http_request(), 1)
+        .await
+        .unwrap();
+    (user_id, token)
+}
+
+#[sqlx::test]
+async fn account_handlers_write_in_the_users_scope(pool: PgPool) {
+    let app = user_app(pool.clone()).await;
+    let (alice, token) = create_account(&pool, "rls_alice").await;
+    reject_unscoped_writes(&pool).await;
+
+    let (status, body) = call(&app, request("POST", "/api/v1/me/password", &token, json!({
+        "current_password": PASSWORD,
+        "new_password": NEW_PASSWORD,
+        "keep_current_session": true,
+    })))
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // A wrong password is counted, in the user's scope
+    let (status, _) = call(&app, request("POST", "/api/v1/me/email", &token, json!({
+        "new_email": "rls_alice_new@example.com",
+        "current_password": PASSWORD,
+    })))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&app, request("POST", "/api/v1/me/email", &token, json!({
+        "new_email": "rls_alice_new@example.com",
+        "current_password": NEW_PASSWORD,
+    })))
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+
+    // The code the email would have carried
+    let code_hash = sign(&EmailChangeConfig::from_env().code_key, &format!("email-change:{}:123456", alice));
+    let mut tx = begin_scope(&pool, alice, None).await.unwrap();
+    sqlx::query("UPDATE email_changes SET code_hash = $1").bind(code_hash).execute(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let (status, body) = call(&app, request("POST", "/api/v1/me/email/confirm", &token, json!({ "code": "123456" }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Undone from the link sent to the previous address
+    let mut tx = begin_scope(&pool, alice, None).await.unwrap();
+    sqlx::query("UPDATE email_changes SET revert_token_hash = $1").bind(hash_token("revert")).execute(&mut *tx).await.unwrap();
+    tx.commit().await.unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/email/revert")
+        .set_form([("token", "revert")])
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let (email, locked, active_sessions): (String, bool, i64) = sqlx::query_as(
+        "SELECT email, locked_at IS NOT NULL, (SELECT COUNT(*) FROM sessions WHERE user_id = $1 AND revoked_at IS NULL) \
+         FROM users WHERE id = $1",
+    )
+    .bind(alice)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((email.as_str(), locked, active_sessions), ("rls_alice@example.com", true, 0));
+    let failed_logins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM failed_logins").fetch_one(&pool).await.unwrap();
+    assert_eq!(failed_logins, 1);
+}
+
+#[sqlx::test]
+async fn account_deletion_is_scheduled_in_the_users_scope(pool: PgPool) {
+    let app = user_app(pool.clone()).await;
+    let (alice, token) = create_account(&pool, "rls_alice").await;
+    reject_unscoped_writes(&pool).await;
+
+    let (status, body) = call(&app, request("DELETE", "/api/v1/me", &token, json!({ "current_password": PASSWORD }))).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+
+    let scheduled: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM account_deletions WHERE user_id = $1")
+        .bind(alice)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(scheduled, 1);
+}
+
+#[sqlx::test]
+async fn organization_handlers_write_in_the_organizations_scope(pool: PgPool) {
+    let app = user_app(pool.clone()).await;
+    let (alice, alice_token) = create_account(&pool, "rls_alice").await;
+    let (bob, bob_token) = create_account(&pool, "rls_bob").await;
+    reject_unscoped_writes(&pool).await;
+
+    let (status, body) = call(&app, request("POST", "/api/v1/orgs", &alice_token, json!({ "name": "RLS Org" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let organization_id = body["id"].as_str().unwrap().to_string();
+    let uri = |path: &str| format!("/api/v1/orgs/{}{}", organization_id, path);
+
+    // Invite, revoke, and invite again
+    let (status, body) = call(&app, request("POST", &uri("/invitations"), &alice_token, json!({ "email": "rls_bob@example.com" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, _) = call(&app, request("DELETE", &uri(&format!("/invitations/{}", body["id"].as_str().unwrap())), &alice_token, json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, request("POST", &uri("/invitations"), &alice_token, json!({ "email": "rls_bob@example.com" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = call(&app, request("GET", &uri("/invitations"), &alice_token, json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    // Bob is not a member yet
+    let (status, _) = call(&app, request("GET", &uri("/members"), &bob_token, json!({}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The token the email would have carried
+    let organization: Uuid = organization_id.parse().unwrap();
+    let mut tx = begin_scope(&pool, alice, Some(organization)).await.unwrap();
+    sqlx::query("UPDATE invitations SET token_hash = $1 WHERE revoked_at IS NULL")
+        .bind(hash_token("invitation"))
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let (status, body) = call(&app, request("POST", "/api/v1/invitations/accept", &bob_token, json!({ "token": "invitation" }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = call(&app, request("GET", &uri("/members"), &bob_token, json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut members: Vec<Uuid> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| member["user_id"].as_str().unwrap().parse().unwrap())
+        .collect();
+    members.sort();
+    let mut expected = vec![alice, bob];
+    expected.sort();
+    assert_eq!(members, expected);
+
+    let (status, body) = call(&app, request("DELETE", &uri("/members/me"), &bob_token, json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, _) = call(&app, request("DELETE", &uri("/members/me"), &alice_token, json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let organizations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organizations").fetch_one(&pool).await.unwrap();
+    assert_eq!(organizations, 0);
+}
@@ -0,0 +1,121 @@
+-- Row-level security isolation checks.
+--
+-- Run against a migrated database; everything happens inside a transaction
+-- that is rolled back, so no data is left behind:
+--
+--   psql "$DATABASE_URL" -v ON_ERROR_STOP=1 -f tests/rls_isolation.sql
+--
+-- Each check raises an exception (and psql exits non-zero) on failure.
+
+BEGIN;
+
+-- Two users with a session, a failed login and an organization each
+INSERT INTO users (id, username, email, password_hash) VALUES
+    ('00000000-0000-0000-0000-0000000000a1', 'rls_alice', 'rls_alice@example.com', 'x'),
+    ('00000000-0000-0000-0000-0000000000b1', 'rls_bob', 'rls_bob@example.com', 'x');
+
+INSERT INTO sessions (user_id, expires_at) VALUES
+    ('00000000-0000-0000-0000-0000000000a1', NOW() + INTERVAL '1 hour'),
+    ('00000000-0000-0000-0000-0000000000b1', NOW() + INTERVAL '1 hour'),
+    ('00000000-0000-0000-0000-0000000000b1', NOW() + INTERVAL '1 hour');
+
+INSERT INTO failed_logins (user_id) VALUES
+    ('00000000-0000-0000-0000-0000000000a1'),
+    ('00000000-0000-0000-0000-0000000000b1');
+
+INSERT INTO organizations (id, name, slug) VALUES
+    ('00000000-0000-0000-0000-0000000000a2', 'Alice Org', 'rls-alice-org'),
+    ('00000000-0000-0000-0000-0000000000b2', 'Bob Org', 'rls-bob-org');
+
+INSERT INTO memberships (organization_id, user_id, role) VALUES
+    ('00000000-0000-0000-0000-0000000000a2', '00000000-0000-0000-0000-0000000000a1', 'owner'),
+    ('00000000-0000-0000-0000-0000000000b2', '00000000-0000-0000-0000-0000000000b1', 'owner');
+
+-- What `database::rls::begin_user_scope` does for Alice
+SET LOCAL ROLE uiso_app;
+SELECT set_config('app.user_id', '00000000-0000-0000-0000-0000000000a1', true),
+       set_config('app.tenant_id', '00000000-0000-0000-0000-0000000000a2', true);
+
+DO $$
+DECLARE
+    n BIGINT;
+BEGIN
+    -- "Buggy" queries without a user filter only see Alice's rows
+    SELECT COUNT(*) INTO n FROM users WHERE username LIKE 'rls_%';
+    ASSERT n = 1, format('users: expected 1 visible row, got %s', n);
+
+    SELECT COUNT(*) INTO n FROM sessions;
+    ASSERT n = 1, format('sessions: expected 1 visible row, got %s', n);
+
+    SELECT COUNT(*) INTO n FROM failed_logins;
+    ASSERT n = 1, format('failed_logins: expected 1 visible row, got %s', n);
+
+    SELECT COUNT(*) INTO n FROM organizations WHERE slug LIKE 'rls-%';
+    ASSERT n = 1, format('organizations: expected 1 visible row, got %s', n);
+
+    SELECT COUNT(*) INTO n FROM memberships;
+    ASSERT n = 1, format('memberships: expected 1 visible row, got %s', n);
+
+    -- Looking Bob up by id finds nothing
+    SELECT COUNT(*) INTO n FROM users WHERE id = '00000000-0000-0000-0000-0000000000b1';
+    ASSERT n = 0, 'users: Bob is visible to Alice';
+
+    -- Unfiltered writes only touch Alice's rows
+    UPDATE sessions SET revoked_at = NOW();
+    GET DIAGNOSTICS n = ROW_COUNT;
+    ASSERT n = 1, format('sessions: unfiltered UPDATE touched %s rows', n);
+
+    DELETE FROM failed_logins;
+    GET DIAGNOSTICS n = ROW_COUNT;
+    ASSERT n = 1, format('failed_logins: unfiltered DELETE removed %s rows', n);
+
+    -- Rows cannot be written on Bob's behalf
+    BEGIN
+        INSERT INTO sessions (user_id, expires_at)
+        VALUES ('00000000-0000-0000-0000-0000000000b1', NOW() + INTERVAL '1 hour');
+        RAISE EXCEPTION 'sessions: inserted a row for another user';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    -- Tombstones are never visible
+    SELECT COUNT(*) INTO n FROM deleted_accounts;
+    ASSERT n = 0, 'deleted_accounts: rows visible to the app role';
+END
+$$;
+
+-- Without a user in scope, nothing is visible (fails closed)
+SELECT set_config('app.user_id', '', true), set_config('app.tenant_id', '', true);
+
+DO $$
+DECLARE
+    n BIGINT;
+BEGIN
+    SELECT COUNT(*) INTO n FROM users;
+    ASSERT n = 0, format('users: %s rows visible without app.user_id', n);
+
+    SELECT COUNT(*) INTO n FROM sessions;
+    ASSERT n = 0, format('sessions: %s rows visible without app.user_id', n);
+END
+$$;
+
+-- Bob's rows were left alone by Alice's unfiltered writes
+RESET ROLE;
+
+DO $$
+DECLARE
+    n BIGINT;
+BEGIN
+    SELECT COUNT(*) INTO n FROM sessions
+    WHERE user_id = '00000000-0000-0000-0000-0000000000b1' AND revoked_at IS NULL;
+    ASSERT n = 2, format('sessions: Bob has %s active sessions, expected 2', n);
+
+    SELECT COUNT(*) INTO n FROM failed_logins
+    WHERE user_id = '00000000-0000-0000-0000-0000000000b1';
+    ASSERT n = 1, format('failed_logins: Bob has %s rows, expected 1', n);
+END
+$$;
+
+\echo 'RLS isolation checks passed'
+
+ROLLBACK;
@@ -1,11 +1,7 @@
 import React, { createContext, useContext, useState, useEffect, type ReactNode } from 'react';
-import { authAPI, type LoginData, type RegisterData } from '@/lib/api';
+import { authAPI, userAPI, type LoginData, type RegisterData, type UserProfile } from '@/lib/api';
 
-interface User {
-  id: string;
-  username: string;
-  email: string;
-}
+type User = UserProfile;
 
 interface AuthContextType {
   user: User | null;
@@ -41,13 +37,7 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
       console.log('AuthContext: Starting login for:', data);
       await authAPI.login(data);
       console.log('AuthContext: Login successful');
-      // In a real app, you'd decode the JWT to get user info
-      // For now, we'll just set a placeholder user
-      setUser({
-        id: '1',
-        username: data.email.split('@')[0],
-        email: data.email,
-      });
+      setUser(await userAPI.me());
     } catch (error) {
       console.error('AuthContext: Login failed:', error);
       console.error('Error details:', error);
@@ -60,12 +50,7 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
       console.log('AuthContext: Starting registration for:', data);
       await authAPI.register(data);
       console.log('AuthContext: Registration successful');
-      // In a real app, you'd decode the JWT to get user info
-      setUser({
-        id: '1',
-        username: data.username,
-        email: data.email,
-      });
+      setUser(await userAPI.me());
     } catch (error) {
       console.error('AuthContext: Registration failed:', error);
       console.error('Error details:', error);
@@ -84,9 +69,12 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
   };
 
   useEffect(() => {
-    // Check if user is already authenticated
-    // In a real app, you'd check for a valid JWT token
-    setIsLoading(false);
+    // Restore the session from the HTTP-only cookie, if it is still valid
+    userAPI
+      .me()
+      .then(setUser)
+      .catch(() => setUser(null))
+      .finally(() => setIsLoading(false));
   }, []);
 
   const value = {
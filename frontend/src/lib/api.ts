@@ -19,6 +19,29 @@ export interface RegisterData {
   username: string;
   email: string;
   password: string;
+  invite_token?: string;
+}
+
+export interface ValidationDetail {
+  field: string;
+  message: string;
+  suggestions?: string[];
+}
+
+export interface UserProfile {
+  id: string;
+  username: string;
+  email: string;
+  display_name: string | null;
+  avatar_url: string | null;
+  email_verified: boolean;
+  created_at: string | null;
+}
+
+export interface UpdateProfileData {
+  username?: string;
+  display_name?: string;
+  avatar_url?: string;
 }
 
 export interface AuthResponse {
@@ -51,4 +74,119 @@ export const authAPI = {
   },
 };
 
+export interface DataExport {
+  id: string;
+  status: 'pending' | 'ready' | 'failed' | 'expired';
+  created_at: string;
+  completed_at: string | null;
+  expires_at: string | null;
+  download_url?: string;
+}
+
+export const userAPI = {
+  me: async (): Promise<UserProfile> => {
+    const response = await api.get('/me');
+    return response.data;
+  },
+
+  updateMe: async (data: UpdateProfileData): Promise<UserProfile> => {
+    const response = await api.patch('/me', data);
+    return response.data;
+  },
+
+  requestEmailChange: async (newEmail: string, currentPassword: string) => {
+    const response = await api.post('/me/email', {
+      new_email: newEmail,
+      current_password: currentPassword,
+    });
+    return response.data;
+  },
+
+  confirmEmailChange: async (code: string): Promise<{ message: string; email: string }> => {
+    const response = await api.post('/me/email/confirm', { code });
+    return response.data;
+  },
+
+  deleteMe: async (currentPassword: string): Promise<{ message: string; scheduled_for: string }> => {
+    const response = await api.delete('/me', { data: { current_password: currentPassword } });
+    return response.data;
+  },
+
+  requestExport: async (): Promise<DataExport> => {
+    const response = await api.post('/me/export');
+    return response.data;
+  },
+
+  getExport: async (id: string): Promise<DataExport> => {
+    const response = await api.get(`/me/export/${id}`);
+    return response.data;
+  },
+};
+
+export type MemberRole = 'owner' | 'admin' | 'member';
+
+export interface Organization {
+  id: string;
+  name: string;
+  slug: string;
+  role: MemberRole;
+  created_at: string;
+}
+
+export interface Invitation {
+  id: string;
+  email: string;
+  role: MemberRole;
+  invited_by: string | null;
+  expires_at: string;
+  created_at: string;
+}
+
+export const orgAPI = {
+  list: async (): Promise<{ organizations: Organization[]; active_organization_id: string | null }> => {
+    const response = await api.get('/orgs');
+    return response.data;
+  },
+
+  create: async (name: string, slug?: string): Promise<Organization> => {
+    const response = await api.post('/orgs', { name, slug });
+    return response.data;
+  },
+
+  addMember: async (organizationId: string, email: string, role: MemberRole = 'member') => {
+    const response = await api.post(`/orgs/${organizationId}/members`, { email, role });
+    return response.data;
+  },
+
+  leave: async (organizationId: string) => {
+    const response = await api.delete(`/orgs/${organizationId}/members/me`);
+    return response.data;
+  },
+
+  switch: async (organizationId: string | null) => {
+    const response = await api.post('/orgs/switch', { organization_id: organizationId });
+    return response.data;
+  },
+
+  invite: async (organizationId: string, email: string, role: MemberRole = 'member'): Promise<Invitation> => {
+    const response = await api.post(`/orgs/${organizationId}/invitations`, { email, role });
+    return response.data;
+  },
+
+  listInvitations: async (organizationId: string): Promise<Invitation[]> => {
+    const response = await api.get(`/orgs/${organizationId}/invitations`);
+    return response.data;
+  },
+
+  revokeInvitation: async (organizationId: string, invitationId: string) => {
+    const response = await api.delete(`/orgs/${organizationId}/invitations/${invitationId}`);
+    return response.data;
+  },
+
+  acceptInvitation: async (token: string): Promise<{ message: string; organization_id: string }> => {
+    const response = await api.post('/invitations/accept', { token });
+    return response.data;
+  },
+};
+
 export default api;
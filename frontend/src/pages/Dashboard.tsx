@@ -28,6 +28,7 @@ const Dashboard: React.FC = () => {
             <div className="space-y-2">
               <h3 className="text-lg font-medium">User Information</h3>
               <div className="bg-gray-100 p-4 rounded-md">
+                {user?.display_name && <p><strong>Name:</strong> {user.display_name}</p>}
                 <p><strong>Username:</strong> {user?.username}</p>
                 <p><strong>Email:</strong> {user?.email}</p>
                 <p><strong>User ID:</strong> {user?.id}</p>
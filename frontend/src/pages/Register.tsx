@@ -5,6 +5,7 @@ import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
 import { PasswordInput } from '@/components/ui/password-input';
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
+import type { ValidationDetail } from '@/lib/api';
 
 const Register: React.FC = () => {
   const [formData, setFormData] = useState({
@@ -54,11 +55,14 @@ const Register: React.FC = () => {
     } catch (err: unknown) {
       console.error('Registration failed:', err);
       if (err && typeof err === 'object' && 'response' in err) {
-        const axiosError = err as { response?: { status: number } };
+        const axiosError = err as { response?: { status: number; data?: { errors?: ValidationDetail[] } } };
         if (axiosError.response?.status === 409) {
           setError('An account with this email already exists');
         } else if (axiosError.response?.status === 400) {
-          setError('Invalid input. Please check your information.');
+          // Show the server's validation messages, including password strength suggestions
+          const details = axiosError.response.data?.errors ?? [];
+          const messages = details.flatMap((d) => [d.message, ...(d.suggestions ?? [])]);
+          setError(messages.length > 0 ? messages.join(' ') : 'Invalid input. Please check your information.');
         } else {
           setError('Registration failed. Please try again.');
         }